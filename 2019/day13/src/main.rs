@@ -5,8 +5,6 @@ use std::{
     time::Duration,
 };
 
-use itertools::Itertools;
-
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
     prelude::{Alignment, Buffer, Color, Frame, Line, Rect, Span, Style, Stylize, Widget},
@@ -17,9 +15,10 @@ use ratatui::{
 use fxhash::FxHashMap;
 use intcode::IntcodeComputer;
 
-mod terminal;
+use replay::Replay;
 
-const WINNING_INPUT: &str = "resources/winning_computer_input";
+mod replay;
+mod terminal;
 
 const JOYSTICK_NEUTRAL: i64 = 0;
 const JOYSTICK_LEFT: i64 = -1;
@@ -158,29 +157,57 @@ impl Display {
             .iter()
             .find_map(|(p, t)| if *t == tile { Some(*p) } else { None })
     }
+}
+
+// Where the ball gets to the row above the paddle, leaving the joystick neutral.
+fn landing_x(computer: &IntcodeComputer, paddle_y: usize) -> Option<usize> {
+    let mut computer = computer.fork();
+    while !computer.is_halted() {
+        computer.io.add_input(JOYSTICK_NEUTRAL);
+        computer.exec();
+        loop {
+            match last_output(&mut computer) {
+                Output::Tile(pos, TileType::Ball) if pos.y + 1 == paddle_y => return Some(pos.x),
+                Output::End => break,
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// Plays the game by moving the paddle to where the ball will come down.
+///
+/// Until the ball gets to the paddle, its path doesn't depend on the joystick,
+/// so it's found by running a copy of the game with the joystick neutral.
+#[derive(Default)]
+struct Autopilot {
+    // Where the paddle needs to be when the ball comes down.
+    target: Option<usize>,
+}
 
-    // Finds where to move the paddle based on where the ball is.
-    fn calc_next_input(&self) -> i64 {
-        let Some(ball) = self.get_tile(TileType::Ball) else {
-            // Sometimes we don't find the ball?!?
+impl Autopilot {
+    fn next_input(&mut self, computer: &IntcodeComputer, display: &Display) -> i64 {
+        let (Some(ball), Some(paddle)) = (
+            display.get_tile(TileType::Ball),
+            display.get_tile(TileType::Paddle),
+        ) else {
             return JOYSTICK_NEUTRAL;
         };
 
-        let ball_x = ball.x;
-        let paddle_x = self.get_tile(TileType::Paddle).unwrap().x;
-
-        match ball_x.cmp(&paddle_x) {
+        let target = if ball.y + 1 == paddle.y {
+            // Bouncing, the next target is computed once the ball goes up.
+            self.target = None;
+            ball.x
+        } else {
+            *self
+                .target
+                .get_or_insert_with(|| landing_x(computer, paddle.y).unwrap_or(ball.x))
+        };
+        match target.cmp(&paddle.x) {
             Ordering::Less => JOYSTICK_LEFT,
             Ordering::Greater => JOYSTICK_RIGHT,
-            Ordering::Equal => {
-                // Keeping towards the middle (without this we sometimes arrive too late and miss the ball).
-                let middle = self.max_dims.unwrap().x / 2;
-                match ball_x.cmp(&middle) {
-                    Ordering::Less => JOYSTICK_RIGHT,
-                    Ordering::Greater => JOYSTICK_LEFT,
-                    Ordering::Equal => JOYSTICK_NEUTRAL,
-                }
-            }
+            Ordering::Equal => JOYSTICK_NEUTRAL,
         }
     }
 }
@@ -196,11 +223,7 @@ fn block_tiles_count(computer: &IntcodeComputer) -> usize {
         .count()
 }
 
-fn parse_computer_input(input: &str) -> Vec<i64> {
-    input.split(',').map(|v| v.parse().unwrap()).collect()
-}
-
-fn high_score<const FROM_FILE: bool>(computer: &IntcodeComputer) -> usize {
+fn high_score(computer: &IntcodeComputer) -> usize {
     let mut computer = computer.clone();
     // Enable game mode
     computer.write_mem(0, 2);
@@ -208,124 +231,134 @@ fn high_score<const FROM_FILE: bool>(computer: &IntcodeComputer) -> usize {
     let mut display = Display::empty();
     display.update(&mut computer);
 
-    if FROM_FILE {
-        let input = std::fs::read_to_string(WINNING_INPUT).expect("Missing saved file");
-        let saved_inputs: Vec<i64> = parse_computer_input(&input);
-        for i in saved_inputs {
-            computer.io.add_input(i);
-            display.update(&mut computer);
-        }
-        assert!(computer.is_halted());
-    } else {
-        while !computer.is_halted() {
-            let i = display.calc_next_input();
-            computer.io.add_input(i);
-            display.update(&mut computer);
-        }
+    let mut autopilot = Autopilot::default();
+    while !computer.is_halted() {
+        let i = autopilot.next_input(&computer, &display);
+        computer.io.add_input(i);
+        display.update(&mut computer);
     }
 
     display.score
 }
 
+// Plays the inputs of the replay, failing if the game ends before them.
+fn play_replay(
+    computer: &mut IntcodeComputer,
+    display: &mut Display,
+    replay: &Replay,
+) -> Result<(), String> {
+    let frames = replay.inputs().len();
+    for (frame, i) in replay.inputs().iter().enumerate() {
+        if computer.is_halted() {
+            return Err(format!(
+                "Game over at frame {frame} of {frames}, the replay doesn't match the program"
+            ));
+        }
+        computer.io.add_input(*i);
+        display.update(computer);
+    }
+    Ok(())
+}
+
 /// The UI for the game.
 struct App {
     computer: IntcodeComputer,
     display: Display,
     exit: bool,
-    saved_inputs: Vec<i64>,
+    // All the inputs since the start of the game.
+    replay: Replay,
+    // Where to save the replay.
+    record: Option<String>,
 }
 
 impl App {
-    const SAVED_FILE: &'static str = "resources/saved_keypresses";
-
-    fn new(computer: &IntcodeComputer, use_saved: bool) -> Self {
+    fn new(computer: &IntcodeComputer, record: Option<String>) -> Self {
         let mut app = Self {
             computer: computer.clone(),
             display: Display::empty(),
             exit: false,
-            saved_inputs: Vec::new(),
+            replay: Replay::new(computer),
+            record,
         };
 
         // Enable game mode
         app.computer.write_mem(0, 2);
-
-        if use_saved {
-            app.load_input(Self::SAVED_FILE);
-            app.replay();
-        }
+        // First screen
+        app.display.update(&mut app.computer);
         app
     }
 
-    fn load_input(&mut self, saved_file: &str) {
-        if let Ok(input) = std::fs::read_to_string(saved_file) {
-            self.saved_inputs = parse_computer_input(&input);
-        }
+    /// Continues the game from where the replay stopped.
+    fn restore(&mut self, replay: Replay) -> Result<(), String> {
+        play_replay(&mut self.computer, &mut self.display, &replay)?;
+        self.replay = replay;
+        Ok(())
     }
 
-    fn replay(&mut self) {
-        self.display.update(&mut self.computer);
-        for i in &self.saved_inputs {
-            self.computer.io.add_input(*i);
-            self.display.update(&mut self.computer);
+    fn save_replay(&self) -> io::Result<()> {
+        if let Some(path) = &self.record {
+            self.replay.save(path)?;
         }
-    }
-
-    fn save_input(&self) {
-        let s = self.saved_inputs.iter().join(",");
-        std::fs::write(Self::SAVED_FILE, s).unwrap();
+        Ok(())
     }
 
     /// runs the application's main loop until the user quits
     fn run(&mut self, terminal: &mut terminal::Tui) -> io::Result<()> {
         while !self.exit {
-            self.display.update(&mut self.computer);
+            self.next_frame();
 
             terminal.draw(|frame| self.render_frame(frame))?;
             self.handle_events()?;
         }
-        Ok(())
+        self.save_replay()
     }
 
-    /// Plays the game automatically using the moves saved in the winning input file.
-    fn auto_run_from_file(&mut self, terminal: &mut terminal::Tui) -> io::Result<()> {
-        self.load_input(WINNING_INPUT);
+    /// Runs the game until it waits for the next input.
+    /// Once the game is over, like after a complete replay, the screen stays as it is.
+    fn next_frame(&mut self) {
+        if !self.computer.is_halted() {
+            self.display.update(&mut self.computer);
+        }
+    }
 
-        // First screen
-        self.display.update(&mut self.computer);
+    /// Shows the game played by the replay.
+    fn watch(&mut self, terminal: &mut terminal::Tui, replay: &Replay) -> io::Result<()> {
         terminal.draw(|frame| self.render_frame(frame))?;
 
-        for i in &self.saved_inputs {
+        for i in replay.inputs() {
+            if self.computer.is_halted() {
+                return Err(io::Error::other("The replay doesn't match the program"));
+            }
             thread::sleep(Duration::from_millis(10));
 
-            self.computer.io.add_input(*i);
+            self.input(*i);
             self.display.update(&mut self.computer);
 
             terminal.draw(|frame| self.render_frame(frame))?;
         }
 
-        thread::sleep(Duration::from_millis(1000));
+        thread::sleep(Duration::from_secs(1));
         Ok(())
     }
 
-    /// Plays the game automatically using the moves saved in the winning input file.
+    /// Plays the game automatically with the autopilot.
     fn auto_run(&mut self, terminal: &mut terminal::Tui) -> io::Result<()> {
-        // First screen
-        self.display.update(&mut self.computer);
         terminal.draw(|frame| self.render_frame(frame))?;
 
+        let mut autopilot = Autopilot::default();
         while !self.computer.is_halted() {
             // Making it quite fast
             thread::sleep(Duration::from_millis(2));
 
-            let i = self.display.calc_next_input();
-            self.computer.io.add_input(i);
+            let i = autopilot.next_input(&self.computer, &self.display);
+            self.input(i);
             self.display.update(&mut self.computer);
 
             terminal.draw(|frame| self.render_frame(frame))?;
         }
 
-        thread::sleep(Duration::from_millis(3000));
-        Ok(())
+        thread::sleep(Duration::from_secs(3));
+        self.save_replay()
     }
 
     fn render_frame(&self, frame: &mut Frame) {
@@ -337,41 +370,36 @@ impl App {
             // it's important to check that the event is a key press event as
             // crossterm also emits key release and repeat events on Windows.
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_key_event(key_event);
+                self.handle_key_event(key_event)?;
             }
             _ => {}
-        };
+        }
         Ok(())
     }
 
-    fn handle_key_event(&mut self, key_event: KeyEvent) {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> io::Result<()> {
         match key_event.code {
             KeyCode::Char('q') => self.exit(),
-            KeyCode::Left => self.left(),
-            KeyCode::Right => self.right(),
-            KeyCode::Char(' ') => self.neutral(),
-            KeyCode::Char('s') => self.save_input(),
+            KeyCode::Left => self.input(JOYSTICK_LEFT),
+            KeyCode::Right => self.input(JOYSTICK_RIGHT),
+            KeyCode::Char(' ') => self.input(JOYSTICK_NEUTRAL),
+            KeyCode::Char('s') => self.save_replay()?,
             _ => {}
         }
+        Ok(())
     }
 
     fn exit(&mut self) {
         self.exit = true;
     }
 
-    fn left(&mut self) {
-        self.saved_inputs.push(JOYSTICK_LEFT);
-        self.computer.io.add_input(JOYSTICK_LEFT);
-    }
-
-    fn right(&mut self) {
-        self.saved_inputs.push(JOYSTICK_RIGHT);
-        self.computer.io.add_input(JOYSTICK_RIGHT);
-    }
-
-    fn neutral(&mut self) {
-        self.saved_inputs.push(JOYSTICK_NEUTRAL);
-        self.computer.io.add_input(JOYSTICK_NEUTRAL);
+    fn input(&mut self, i: i64) {
+        // Inputs after the game over would make the replay invalid.
+        if self.computer.is_halted() {
+            return;
+        }
+        self.replay.push(i);
+        self.computer.io.add_input(i);
     }
 }
 
@@ -447,38 +475,197 @@ impl Widget for &App {
     }
 }
 
-fn main() -> io::Result<()> {
-    let param = std::env::args().nth(1).unwrap_or_default();
-    if !param.is_empty() {
-        // Not reading from stdin in this case, as it messes up with crossterm.
-        let input = std::fs::read_to_string("resources/input").expect("Unable to read input file");
-        let computer = IntcodeComputer::build(&input);
+enum Mode {
+    Play,
+    Auto,
+    Watch,
+}
 
-        if param.starts_with("auto") {
-            let mut terminal = terminal::init(false)?;
-            let mut app = App::new(&computer, false);
-            let app_result = if param == "auto_file" {
-                app.auto_run_from_file(&mut terminal)
-            } else {
-                app.auto_run(&mut terminal)
-            };
-            terminal::restore(false)?;
-            return app_result;
+const USAGE: &str = "Usage: play|auto [--replay FILE] [--record FILE], or --replay FILE";
+
+struct Options {
+    mode: Mode,
+    // Replay to start from, or to watch.
+    replay: Option<String>,
+    // Where to record the game.
+    record: Option<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let (mut mode, mut replay, mut record) = (None, None, None);
+        let mut it = args.iter();
+        while let Some(arg) = it.next() {
+            match arg.as_str() {
+                "play" => mode = Some(Mode::Play),
+                "auto" => mode = Some(Mode::Auto),
+                "--replay" | "--record" => {
+                    let file = it.next().ok_or(format!("Missing file after {arg}"))?;
+                    if arg == "--replay" {
+                        replay = Some(file.clone());
+                    } else {
+                        record = Some(file.clone());
+                    }
+                }
+                _ => return Err(format!("Unknown argument {arg}. {USAGE}")),
+            }
         }
+        let mode = match (mode, &replay) {
+            (Some(mode), _) => mode,
+            (None, Some(_)) => Mode::Watch,
+            (None, None) => return Err(USAGE.to_string()),
+        };
+        Ok(Self {
+            mode,
+            replay,
+            record,
+        })
+    }
+}
 
-        let mut terminal = terminal::init(true)?;
-        let mut app = App::new(&computer, param == "saved");
-        let app_result = app.run(&mut terminal);
-        terminal::restore(true)?;
+fn run_tui(computer: &IntcodeComputer, options: Options) -> io::Result<()> {
+    let mut app = App::new(computer, options.record);
+    let replay = options
+        .replay
+        .map(|path| Replay::load(&path, computer))
+        .transpose()
+        .map_err(io::Error::other)?;
+
+    if let Mode::Watch = options.mode {
+        let mut terminal = terminal::init(false)?;
+        let app_result = app.watch(&mut terminal, &replay.unwrap());
+        terminal::restore(false)?;
         return app_result;
     }
 
+    if let Some(replay) = replay {
+        app.restore(replay).map_err(io::Error::other)?;
+    }
+    let raw_mode = matches!(options.mode, Mode::Play);
+    let mut terminal = terminal::init(raw_mode)?;
+    let app_result = if raw_mode {
+        app.run(&mut terminal)
+    } else {
+        app.auto_run(&mut terminal)
+    };
+    terminal::restore(raw_mode)?;
+    app_result
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let options = Options::parse(&args).map_err(io::Error::other)?;
+        // Not reading from stdin in this case, as it messes up with crossterm.
+        let input = std::fs::read_to_string("resources/input").expect("Unable to read input file");
+        let computer = IntcodeComputer::build(&input);
+        return run_tui(&computer, options);
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input).unwrap();
     let computer = IntcodeComputer::build(&input);
 
     println!("Part 1: {}", block_tiles_count(&computer));
-    println!("Part 2: {}", high_score::<false>(&computer));
+    println!("Part 2: {}", high_score(&computer));
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A game showing a wall, whose score is the sum of the 3 inputs it reads.
+    const GAME: &str = "1,0,0,200,104,0,104,0,104,1,3,30,1,30,31,31,104,-1,104,0,4,31,\
+                        1001,32,-1,32,1005,32,10,99,0,0,3";
+
+    fn play(inputs: &[i64]) -> Result<usize, String> {
+        let computer = IntcodeComputer::build(GAME);
+        let mut replay = Replay::new(&computer);
+        for i in inputs {
+            replay.push(*i);
+        }
+        let mut app = App::new(&computer, None);
+        app.restore(replay)?;
+        assert_eq!(app.replay.inputs(), inputs);
+        Ok(app.display.score)
+    }
+
+    #[test]
+    fn test_play_replay() {
+        assert_eq!(play(&[1, 0, 1]), Ok(2));
+        // Stopped before the end of the game.
+        assert_eq!(play(&[1]), Ok(1));
+        assert_eq!(
+            play(&[1, 1, 1, 0]),
+            Err("Game over at frame 3 of 4, the replay doesn't match the program".to_string())
+        );
+    }
+
+    #[test]
+    fn test_play_after_complete_replay() {
+        let computer = IntcodeComputer::build(GAME);
+        let mut replay = Replay::new(&computer);
+        for i in [1, 0, 1] {
+            replay.push(i);
+        }
+        let mut app = App::new(&computer, None);
+        app.restore(replay).unwrap();
+        assert!(app.computer.is_halted());
+
+        app.next_frame();
+        app.input(JOYSTICK_LEFT);
+        app.next_frame();
+        assert_eq!(app.display.score, 2);
+        assert_eq!(app.replay.inputs(), [1, 0, 1]);
+    }
+
+    #[test]
+    fn test_autopilot() {
+        // A wall at (0, 0), the paddle at (5, 5), and the ball going down from (1, 1) to (4, 4),
+        // whatever the joystick.
+        let mut program = vec![
+            104, 0, 104, 0, 104, 1, 104, 5, 104, 5, 104, 3, 104, 1, 104, 1, 104, 4,
+        ];
+        for i in 2..=4 {
+            program.extend([3, 100, 104, i, 104, i, 104, 4]);
+        }
+        program.push(99);
+        let mut computer = IntcodeComputer::build(
+            &program
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        let mut display = Display::empty();
+        display.update(&mut computer);
+
+        assert_eq!(landing_x(&computer, 5), Some(4));
+        assert_eq!(landing_x(&computer, 10), None);
+        let mut autopilot = Autopilot::default();
+        assert_eq!(autopilot.next_input(&computer, &display), JOYSTICK_LEFT);
+        assert_eq!(autopilot.target, Some(4));
+        // The prediction didn't change the game.
+        computer.io.add_input(JOYSTICK_LEFT);
+        display.update(&mut computer);
+        assert_eq!(display.get_tile(TileType::Ball), Some(Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn test_options() {
+        let args = |s: &str| s.split(' ').map(ToString::to_string).collect::<Vec<_>>();
+        let options = Options::parse(&args("play --record a --replay b")).unwrap();
+        assert!(matches!(options.mode, Mode::Play));
+        assert_eq!(options.record.as_deref(), Some("a"));
+        assert_eq!(options.replay.as_deref(), Some("b"));
+        assert!(matches!(
+            Options::parse(&args("--replay b")).unwrap().mode,
+            Mode::Watch
+        ));
+        assert!(Options::parse(&args("auto --record")).is_err());
+        assert!(Options::parse(&args("--record a")).is_err());
+        assert!(Options::parse(&args("saved")).is_err());
+    }
+}
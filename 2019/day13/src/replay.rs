@@ -0,0 +1,199 @@
+//! Recorded games, to watch them again or continue playing from where they stopped.
+//!
+//! A replay is a text file like:
+//!
+//! ```text
+//! # Intcode arcade replay
+//! checksum 3a9c01f5e4b2d687
+//! frames 12
+//! <<<...>>>..<
+//! ```
+//!
+//! Lines starting with `#` are comments. The checksum identifies the game program the replay was
+//! recorded with, as a replay only makes sense with the same program.
+//! Then comes the joystick position for each frame, `<` for left, `.` for neutral and `>` for
+//! right, on lines of at most 80 frames.
+use std::{fmt, fs, io};
+
+use intcode::IntcodeComputer;
+
+use crate::{JOYSTICK_LEFT, JOYSTICK_NEUTRAL, JOYSTICK_RIGHT};
+
+const HEADER: &str = "# Intcode arcade replay";
+const FRAMES_PER_LINE: usize = 80;
+
+/// FNV-1a hash of the program.
+pub fn checksum(computer: &IntcodeComputer) -> u64 {
+    computer
+        .dump_memory()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    checksum: u64,
+    inputs: Vec<i64>,
+}
+
+impl Replay {
+    /// An empty replay for the game program.
+    pub fn new(computer: &IntcodeComputer) -> Self {
+        Self {
+            checksum: checksum(computer),
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, input: i64) {
+        self.inputs.push(input);
+    }
+
+    /// The joystick inputs, one per frame.
+    pub fn inputs(&self) -> &[i64] {
+        &self.inputs
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let mut field = |name: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .map(str::trim)
+                .ok_or_else(|| format!("Missing {name}"))
+        };
+        let checksum = field("checksum")?;
+        let checksum = u64::from_str_radix(checksum, 16)
+            .map_err(|_| format!("Invalid checksum {checksum}"))?;
+        let frames = field("frames")?;
+        let frames: usize = frames
+            .parse()
+            .map_err(|_| format!("Invalid frames count {frames}"))?;
+
+        let inputs = lines
+            .flat_map(str::chars)
+            .map(|c| match c {
+                '<' => Ok(JOYSTICK_LEFT),
+                '.' => Ok(JOYSTICK_NEUTRAL),
+                '>' => Ok(JOYSTICK_RIGHT),
+                _ => Err(format!("Invalid joystick position '{c}'")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if inputs.len() != frames {
+            return Err(format!("Expected {frames} frames, found {}", inputs.len()));
+        }
+        Ok(Self { checksum, inputs })
+    }
+
+    /// Loads a replay, checking it was recorded with the game program.
+    pub fn load(path: &str, computer: &IntcodeComputer) -> Result<Self, String> {
+        let s = fs::read_to_string(path).map_err(|err| format!("Unable to read {path}: {err}"))?;
+        let replay = Self::parse(&s)?;
+        if replay.checksum != checksum(computer) {
+            return Err(format!(
+                "{path} was recorded with another program (checksum {:016x}, expected {:016x})",
+                replay.checksum,
+                checksum(computer)
+            ));
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "checksum {:016x}", self.checksum)?;
+        writeln!(f, "frames {}", self.inputs.len())?;
+        for line in self.inputs.chunks(FRAMES_PER_LINE) {
+            let line: String = line
+                .iter()
+                .map(|i| match *i {
+                    JOYSTICK_LEFT => '<',
+                    JOYSTICK_RIGHT => '>',
+                    _ => '.',
+                })
+                .collect();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        let a = IntcodeComputer::build("1,0,0,0,99");
+        let b = IntcodeComputer::build("1,0,0,0,99");
+        let c = IntcodeComputer::build("2,0,0,0,99");
+        assert_eq!(checksum(&a), checksum(&b));
+        assert_ne!(checksum(&a), checksum(&c));
+    }
+
+    #[test]
+    fn test_format() {
+        let computer = IntcodeComputer::build("1,0,0,0,99");
+        let mut replay = Replay::new(&computer);
+        for i in 0..100 {
+            replay.push(i % 3 - 1);
+        }
+        let s = replay.to_string();
+        let lines: Vec<&str> = s.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(lines[2], "frames 100");
+        assert!(lines[3].starts_with("<.><.>"));
+        assert_eq!(lines[3].len(), FRAMES_PER_LINE);
+        assert_eq!(Replay::parse(&s), Ok(replay));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Replay::parse("frames 1\n<").unwrap_err(),
+            "Missing checksum"
+        );
+        assert_eq!(
+            Replay::parse("checksum 12\nframes 2\n<").unwrap_err(),
+            "Expected 2 frames, found 1"
+        );
+        assert_eq!(
+            Replay::parse("checksum 12\nframes 1\nx").unwrap_err(),
+            "Invalid joystick position 'x'"
+        );
+        assert_eq!(
+            Replay::parse("checksum zz\nframes 1\n<").unwrap_err(),
+            "Invalid checksum zz"
+        );
+    }
+
+    #[test]
+    fn test_load_other_program() {
+        let computer = IntcodeComputer::build("1,0,0,0,99");
+        let path = std::env::temp_dir().join("day13_test_replay");
+        let path = path.to_str().unwrap();
+        let mut replay = Replay::new(&computer);
+        replay.push(JOYSTICK_LEFT);
+        replay.save(path).unwrap();
+
+        assert_eq!(Replay::load(path, &computer), Ok(replay));
+        let other = IntcodeComputer::build("2,0,0,0,99");
+        assert!(Replay::load(path, &other)
+            .unwrap_err()
+            .contains("recorded with another program"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::BinaryHeap,
+    collections::{BinaryHeap, VecDeque},
     io::{self, Read},
 };
 
@@ -24,15 +24,6 @@ impl Direction {
             East => 4,
         }
     }
-
-    fn reverse(self) -> Self {
-        match self {
-            North => South,
-            South => North,
-            West => East,
-            East => West,
-        }
-    }
 }
 
 const ALL_DIRECTIONS: [Direction; 4] = [North, South, West, East];
@@ -164,56 +155,30 @@ impl Maze {
     }
 }
 
-// Build the maze.
+// Build the maze, with a breadth first search.
+// Each position reached keeps its own copy of the computer, forked from the previous position,
+// so there is no need to move the droid back.
 fn discover_maze(computer: &IntcodeComputer) -> Maze {
-    // Building the maze cannot be done recursively, otherwise the computer wouldn't be in the right state.
-    // So we need to move back once we have reached a dead-end.
-
-    let mut computer = computer.clone();
     let mut maze = Maze::new();
-    let mut pos = Pos::zero();
-    // We need to save position and direction, as computer requires both.
-    let mut path: Vec<(Pos, Direction)> = Vec::new();
-
-    'outer: loop {
-        let unexplored_directions: Vec<(Direction, Pos)> = ALL_DIRECTIONS
-            .iter()
-            .map(|&d| (d, pos.move_towards(d)))
-            .filter(|(_, pos)| !maze.0.contains_key(pos))
-            .collect();
-
-        for (dir, next_pos) in unexplored_directions {
-            let status = run(&mut computer, dir);
-            match status {
-                Status::HitWall => {
-                    maze.0.insert(next_pos, Element::Wall);
-                    // position hasn't changed
-                }
-                Status::Moved => {
-                    // We move into the first unexplored empty space we found.
-                    // There might be more empty spaces around this position, we will explore them on our way back.
-                    maze.0.insert(next_pos, Element::Empty);
-                    path.push((pos, dir)); // we add the position to the path before leaving it
-                    pos = next_pos;
-                    continue 'outer;
-                }
-                Status::MovedAndFound => {
-                    maze.0.insert(next_pos, Element::Oxygen);
-                    // println!("!!! Found the system at {:?}", next_pos);
-                    path.push((pos, dir));
-                    pos = next_pos;
-                    continue 'outer;
-                }
+    maze.0.insert(Pos::zero(), Element::Empty);
+    let mut queue = VecDeque::from([(Pos::zero(), computer.fork())]);
+
+    while let Some((pos, computer)) = queue.pop_front() {
+        for dir in ALL_DIRECTIONS {
+            let next_pos = pos.move_towards(dir);
+            if maze.0.contains_key(&next_pos) {
+                continue;
+            }
+            let mut next_computer = computer.fork();
+            let element = match run(&mut next_computer, dir) {
+                Status::HitWall => Element::Wall,
+                Status::Moved => Element::Empty,
+                Status::MovedAndFound => Element::Oxygen,
+            };
+            maze.0.insert(next_pos, element);
+            if element != Element::Wall {
+                queue.push_back((next_pos, next_computer));
             }
-        }
-
-        // Dead end, so need to move back.
-        if let Some((back_pos, back_dir)) = path.pop() {
-            pos = back_pos;
-            let status = run(&mut computer, back_dir.reverse());
-            assert_eq!(status, Status::Moved);
-        } else {
-            break;
         }
     }
     //maze.print_with_droid(Some(Pos::zero()));
@@ -1,7 +1,14 @@
 use itertools::Itertools;
-use std::io::{self, Read};
+use std::{
+    fmt,
+    io::{self, Read},
+};
 
-use intcode::IntcodeComputer;
+use intcode::{compress::compress, IntcodeComputer};
+
+// Movement functions and main routine can have 20 characters at most.
+const MAX_ROUTINE_LEN: usize = 20;
+const MAX_FUNCTIONS: usize = 3;
 
 fn get_scaffolds_view(computer: &mut IntcodeComputer) -> Vec<char> {
     computer.exec();
@@ -30,6 +37,36 @@ enum Direction {
 }
 use Direction::{East, North, South, West};
 
+impl Direction {
+    fn from(c: char) -> Option<Self> {
+        match c {
+            '^' => Some(North),
+            '>' => Some(East),
+            'v' => Some(South),
+            '<' => Some(West),
+            _ => None,
+        }
+    }
+
+    fn turn_left(self) -> Self {
+        match self {
+            North => West,
+            East => North,
+            South => East,
+            West => South,
+        }
+    }
+
+    fn turn_right(self) -> Self {
+        match self {
+            North => East,
+            East => South,
+            South => West,
+            West => North,
+        }
+    }
+}
+
 struct Grid {
     values: Vec<char>,
     rows: usize,
@@ -58,7 +95,7 @@ impl Grid {
             North => pos < self.cols,
             East => pos % self.cols == self.cols - 1,
             South => pos / self.cols == self.rows - 1,
-            West => pos % self.cols == 0,
+            West => pos.is_multiple_of(self.cols),
         }
     }
 
@@ -106,6 +143,42 @@ impl Grid {
     fn get_alignment_parameter(&self, pos: usize) -> usize {
         self.col(pos) * self.row(pos)
     }
+
+    // Position and direction of the robot.
+    fn robot(&self) -> (usize, Direction) {
+        self.values
+            .iter()
+            .enumerate()
+            .find_map(|(pos, c)| Direction::from(*c).map(|d| (pos, d)))
+            .expect("No robot on the map")
+    }
+
+    // The path visiting all the scaffolds, going straight at the intersections.
+    // It's a list of turns, each followed by the number of steps forward.
+    fn path(&self) -> Vec<Instruction> {
+        let (mut pos, mut direction) = self.robot();
+        let mut path = Vec::new();
+        loop {
+            let turn: fn(usize) -> Instruction;
+            if self.is_scaffold_in(pos, direction.turn_left()) {
+                direction = direction.turn_left();
+                turn = Left;
+            } else if self.is_scaffold_in(pos, direction.turn_right()) {
+                direction = direction.turn_right();
+                turn = Right;
+            } else {
+                // End of the scaffolds.
+                return path;
+            }
+
+            let mut steps = 0;
+            while self.is_scaffold_in(pos, direction) {
+                pos = self.next_pos(pos, direction);
+                steps += 1;
+            }
+            path.push(turn(steps));
+        }
+    }
 }
 
 fn alignment_params_sum(computer: &IntcodeComputer) -> usize {
@@ -124,7 +197,7 @@ fn alignment_params_sum(computer: &IntcodeComputer) -> usize {
 }
 
 #[allow(dead_code)]
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Instruction {
     A,
     B,
@@ -136,29 +209,33 @@ enum Instruction {
 }
 use Instruction::{Left, Right, VideoFeedOff, VideoFeedOn, A, B, C};
 
-impl Instruction {
-    fn get_string(self) -> String {
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            A => "A".to_string(),
-            B => "B".to_string(),
-            C => "C".to_string(),
-            Left(n) => format!("L,{n}"),
-            Right(n) => format!("R,{n}"),
-            VideoFeedOn => "y".to_string(),
-            VideoFeedOff => "n".to_string(),
+            A => write!(f, "A"),
+            B => write!(f, "B"),
+            C => write!(f, "C"),
+            Left(n) => write!(f, "L,{n}"),
+            Right(n) => write!(f, "R,{n}"),
+            VideoFeedOn => write!(f, "y"),
+            VideoFeedOff => write!(f, "n"),
         }
     }
 }
 
 fn build_computer_string(input: &[Instruction]) -> String {
-    input.iter().map(|i| i.get_string()).join(",")
+    input.iter().join(",")
 }
 
 fn computer_write_line(computer: &mut IntcodeComputer, input: &[Instruction]) {
     const NEWLINE: i64 = 10;
 
     let s = build_computer_string(input);
-    assert!(s.len() <= 20, "Input string too big: {}", s.len());
+    assert!(
+        s.len() <= MAX_ROUTINE_LEN,
+        "Input string too big: {}",
+        s.len()
+    );
 
     s.chars().map(|c| c as i64).for_each(|i| {
         computer.io.add_input(i);
@@ -166,28 +243,32 @@ fn computer_write_line(computer: &mut IntcodeComputer, input: &[Instruction]) {
     computer.io.add_input(NEWLINE);
 }
 
+// Splits the path into the main routine and the movement functions A, B and C.
+fn movement_functions(path: &[Instruction]) -> (Vec<Instruction>, Vec<Vec<Instruction>>) {
+    let compression =
+        compress(path, MAX_FUNCTIONS, MAX_ROUTINE_LEN).expect("Path cannot be compressed");
+    let main = compression.main.iter().map(|i| [A, B, C][*i]).collect();
+    let mut functions = compression.functions;
+    // All functions must be defined, even if not used.
+    functions.resize(MAX_FUNCTIONS, Vec::new());
+    (main, functions)
+}
+
 fn collected_dust_amount(computer: &IntcodeComputer) -> i64 {
+    // The path is computed by following the scaffolds ignoring the intersections,
+    // and then finding the common parts in it.
+    let scaffolds = Grid::convert(&get_scaffolds_view(&mut computer.clone()));
+    let (main, functions) = movement_functions(&scaffolds.path());
+
     let mut computer = computer.clone();
     // Wake the robot up.
     computer.write_mem(0, 2);
 
-    // The path was computed by following the scaffolds ignoring the intersections,
-    // and then finding the common parts in it by hand.
-    // A: R10,L12,R6
-    // B: R6,R10,R12,R6
-    // C: R10,L12,L12
-    // A,A,B,C,B,C,B,C,B,A
-    let movement_fcts = vec![A, A, B, C, B, C, B, C, B, A];
-    let a_fct = vec![Right(10), Left(12), Right(6)];
-    let b_fct = vec![Right(6), Right(10), Right(12), Right(6)];
-    let c_fct = vec![Right(10), Left(12), Left(12)];
-    let video_feed = vec![VideoFeedOff];
-
-    computer_write_line(&mut computer, &movement_fcts);
-    computer_write_line(&mut computer, &a_fct);
-    computer_write_line(&mut computer, &b_fct);
-    computer_write_line(&mut computer, &c_fct);
-    computer_write_line(&mut computer, &video_feed);
+    computer_write_line(&mut computer, &main);
+    for function in &functions {
+        computer_write_line(&mut computer, function);
+    }
+    computer_write_line(&mut computer, &[VideoFeedOff]);
 
     computer.exec();
 
@@ -209,3 +290,118 @@ fn main() {
     println!("Part 1: {}", alignment_params_sum(&computer));
     println!("Part 2: {}", collected_dust_amount(&computer));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(map: &str) -> Grid {
+        Grid::convert(&map.chars().collect::<Vec<_>>())
+    }
+
+    // Checks the path can be compressed and gives back the same path.
+    fn check_compression(path: &[Instruction]) {
+        let (main, functions) = movement_functions(path);
+        assert!(build_computer_string(&main).len() <= MAX_ROUTINE_LEN);
+        let expanded: Vec<Instruction> = main
+            .iter()
+            .flat_map(|f| match f {
+                A => functions[0].clone(),
+                B => functions[1].clone(),
+                C => functions[2].clone(),
+                _ => panic!("Invalid main routine"),
+            })
+            .collect();
+        assert_eq!(expanded, path);
+    }
+
+    #[test]
+    fn test_example() {
+        let scaffolds = grid(
+            "#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......
+",
+        );
+        let path = scaffolds.path();
+        assert_eq!(
+            build_computer_string(&path),
+            "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+        );
+        check_compression(&path);
+    }
+
+    #[test]
+    fn test_alignment_example() {
+        let scaffolds = grid(
+            "..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..
+",
+        );
+        assert_eq!(
+            scaffolds
+                .get_intersections()
+                .iter()
+                .map(|p| scaffolds.get_alignment_parameter(*p))
+                .sum::<usize>(),
+            76
+        );
+    }
+
+    #[test]
+    fn test_spiral() {
+        let scaffolds = grid(
+            "#########
+#.......#
+#.#####.#
+#.#...#.#
+#.#.^##.#
+#.#.....#
+#.#######
+#........
+#########
+",
+        );
+        let path = scaffolds.path();
+        assert_eq!(
+            build_computer_string(&path),
+            "R,2,L,2,L,4,L,4,L,6,L,6,L,8,L,8,L,8"
+        );
+        check_compression(&path);
+    }
+
+    #[test]
+    fn test_crossing() {
+        // The path goes straight through the intersection.
+        let scaffolds = grid(
+            "....#####
+....#...#
+^########
+....#....
+....#....
+....#....
+",
+        );
+        assert_eq!(scaffolds.get_intersections(), [2 * 9 + 4]);
+        let path = scaffolds.path();
+        assert_eq!(build_computer_string(&path), "R,8,L,2,L,4,L,5");
+        check_compression(&path);
+    }
+}
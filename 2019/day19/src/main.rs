@@ -3,15 +3,9 @@ use std::io::{self, Read};
 use intcode::IntcodeComputer;
 
 fn is_drone_pulled(computer: &IntcodeComputer, x: usize, y: usize) -> bool {
-    // Computer has to be restarted for each attempt.
-    let mut computer = computer.clone();
-
-    computer.io.add_input(x.try_into().unwrap());
-    computer.io.add_input(y.try_into().unwrap());
-
-    computer.exec();
-
-    computer.io.get_output().unwrap() == 1
+    // Computer has to be restarted for each attempt, so we query a copy of it.
+    let output = computer.query(&[x.try_into().unwrap(), y.try_into().unwrap()]);
+    output[0] == 1
 }
 
 fn beam_size(computer: &IntcodeComputer) -> usize {
@@ -0,0 +1,306 @@
+//! Compiler of boolean formulas over the sensors into springscript.
+//!
+//! Formulas use the sensors A to I, `!`, `&`, `|` and parenthesis, like `!(A & B & C) & D & (E | H)`.
+//! The result is computed in J, using T as the only other register.
+use std::{fmt, iter::Peekable, str::Chars};
+
+use itertools::Itertools;
+
+use crate::{Instruction, Reg, AND, NOT, OR};
+
+pub const MAX_INSTRUCTIONS: usize = 15;
+
+// Above that, only the given order of the operands is tried.
+const MAX_PERMUTED_OPERANDS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Sensor(Reg),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut chars = s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        // Accept C style operators as well.
+        chars = chars.replace("&&", "&").replace("||", "|");
+        let mut it = chars.chars().peekable();
+        let expr = Self::parse_or(&mut it)?;
+        if let Some(c) = it.next() {
+            return Err(format!("Unexpected '{c}'"));
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(it: &mut Peekable<Chars>) -> Result<Self, String> {
+        let mut ops = vec![Self::parse_and(it)?];
+        while it.next_if_eq(&'|').is_some() {
+            ops.push(Self::parse_and(it)?);
+        }
+        Ok(if ops.len() == 1 {
+            ops.pop().unwrap()
+        } else {
+            Expr::Or(ops)
+        })
+    }
+
+    fn parse_and(it: &mut Peekable<Chars>) -> Result<Self, String> {
+        let mut ops = vec![Self::parse_unary(it)?];
+        while it.next_if_eq(&'&').is_some() {
+            ops.push(Self::parse_unary(it)?);
+        }
+        Ok(if ops.len() == 1 {
+            ops.pop().unwrap()
+        } else {
+            Expr::And(ops)
+        })
+    }
+
+    fn parse_unary(it: &mut Peekable<Chars>) -> Result<Self, String> {
+        match it.next() {
+            Some('!') => Ok(Expr::Not(Box::new(Self::parse_unary(it)?))),
+            Some('(') => {
+                let expr = Self::parse_or(it)?;
+                if it.next() != Some(')') {
+                    return Err("Missing ')'".to_string());
+                }
+                Ok(expr)
+            }
+            Some(c) => Reg::sensor(c)
+                .map(Expr::Sensor)
+                .ok_or_else(|| format!("Unknown sensor '{c}'")),
+            None => Err("Unexpected end of formula".to_string()),
+        }
+    }
+
+    /// Value of the formula, with the registers indexed as in `Reg::index()`.
+    #[cfg(test)]
+    pub fn eval(&self, regs: &[bool]) -> bool {
+        match self {
+            Expr::Sensor(r) => regs[r.index()],
+            Expr::Not(e) => !e.eval(regs),
+            Expr::And(ops) => ops.iter().all(|e| e.eval(regs)),
+            Expr::Or(ops) => ops.iter().any(|e| e.eval(regs)),
+        }
+    }
+
+    // Negation normal form: negations only apply to sensors.
+    fn nnf(&self, negate: bool) -> Nnf {
+        match self {
+            Expr::Sensor(r) => Nnf::Lit(*r, !negate),
+            Expr::Not(e) => e.nnf(!negate),
+            Expr::And(ops) | Expr::Or(ops) => {
+                let ops = ops.iter().map(|e| e.nnf(negate)).collect();
+                // De Morgan's laws.
+                if matches!(self, Expr::And(_)) == negate {
+                    Nnf::Or(ops)
+                } else {
+                    Nnf::And(ops)
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let group = |ops: &[Expr], sep: &str| {
+            ops.iter()
+                .map(|e| match e {
+                    Expr::And(_) | Expr::Or(_) => format!("({e})"),
+                    _ => e.to_string(),
+                })
+                .join(sep)
+        };
+        match self {
+            Expr::Sensor(r) => write!(f, "{r}"),
+            Expr::Not(e) => match **e {
+                Expr::Sensor(_) | Expr::Not(_) => write!(f, "!{e}"),
+                _ => write!(f, "!({e})"),
+            },
+            Expr::And(ops) => write!(f, "{}", group(ops, " & ")),
+            Expr::Or(ops) => write!(f, "{}", group(ops, " | ")),
+        }
+    }
+}
+
+// Formula in negation normal form.
+#[derive(Debug, Clone)]
+enum Nnf {
+    // A sensor, or its negation if false.
+    Lit(Reg, bool),
+    And(Vec<Nnf>),
+    Or(Vec<Nnf>),
+}
+
+impl Nnf {
+    fn negate(&self) -> Self {
+        match self {
+            Nnf::Lit(r, positive) => Nnf::Lit(*r, !positive),
+            Nnf::And(ops) => Nnf::Or(ops.iter().map(Nnf::negate).collect()),
+            Nnf::Or(ops) => Nnf::And(ops.iter().map(Nnf::negate).collect()),
+        }
+    }
+}
+
+// Registers known to be false, as they are initially.
+#[derive(Clone, Copy)]
+struct Fresh([bool; 2]);
+
+impl Fresh {
+    fn is_fresh(self, r: Reg) -> bool {
+        self.0[r.index()]
+    }
+
+    // The registers still false after the code.
+    fn after(self, code: &[Instruction]) -> Self {
+        let mut fresh = self;
+        for ins in code {
+            let (Instruction::And(_, y) | Instruction::Or(_, y) | Instruction::Not(_, y)) = ins;
+            fresh.0[y.index()] = false;
+        }
+        fresh
+    }
+}
+
+fn shortest(candidates: impl Iterator<Item = Vec<Instruction>>) -> Option<Vec<Instruction>> {
+    candidates.min_by_key(Vec::len)
+}
+
+// Generates the code computing the formula into `dst`, possibly using `scratch`.
+// `complement` allows computing the negation of the formula and inverting it.
+fn gen(
+    e: &Nnf,
+    dst: Reg,
+    scratch: Option<Reg>,
+    fresh: Fresh,
+    complement: bool,
+) -> Option<Vec<Instruction>> {
+    let (ops, and) = match e {
+        Nnf::Lit(x, true) if fresh.is_fresh(dst) => return Some(vec![OR(*x, dst)]),
+        Nnf::Lit(x, true) => return Some(vec![NOT(*x, dst), NOT(dst, dst)]),
+        Nnf::Lit(x, false) => return Some(vec![NOT(*x, dst)]),
+        Nnf::And(ops) => (ops, true),
+        Nnf::Or(ops) => (ops, false),
+    };
+    let combine = |x, y| if and { AND(x, y) } else { OR(x, y) };
+
+    let orders: Vec<Vec<&Nnf>> = if ops.len() <= MAX_PERMUTED_OPERANDS {
+        ops.iter().permutations(ops.len()).collect()
+    } else {
+        vec![ops.iter().collect()]
+    };
+    let direct = orders.into_iter().filter_map(|order| {
+        let mut code = gen(order[0], dst, scratch, fresh, true)?;
+        for op in &order[1..] {
+            match (op, scratch) {
+                (Nnf::Lit(x, true), _) => code.push(combine(*x, dst)),
+                (Nnf::Lit(x, false), Some(s)) => code.extend([NOT(*x, s), combine(s, dst)]),
+                // dst & !x == !(!dst | x), and dst | !x == !(!dst & x)
+                (Nnf::Lit(x, false), None) => code.extend([
+                    NOT(dst, dst),
+                    if and { OR(*x, dst) } else { AND(*x, dst) },
+                    NOT(dst, dst),
+                ]),
+                (_, Some(s)) => {
+                    code.extend(gen(op, s, None, fresh.after(&code), true)?);
+                    code.push(combine(s, dst));
+                }
+                (_, None) => return None,
+            }
+        }
+        Some(code)
+    });
+    let complemented = complement
+        .then(|| {
+            let mut code = gen(&e.negate(), dst, scratch, fresh, false)?;
+            code.push(NOT(dst, dst));
+            Some(code)
+        })
+        .flatten();
+    shortest(direct.chain(complemented))
+}
+
+/// Compiles the formula into the shortest springscript program among the equivalent forms tried:
+/// all orders of the operands, and computing the negation of sub-formulas before inverting it.
+/// This isn't necessarily the shortest possible program, as other forms aren't tried.
+/// Returns None if none of these forms fits in 15 instructions.
+pub fn compile(expr: &Expr) -> Option<Vec<Instruction>> {
+    gen(
+        &expr.nnf(false),
+        Reg::J,
+        Some(Reg::T),
+        Fresh([true, true]),
+        true,
+    )
+    .filter(|code| code.len() <= MAX_INSTRUCTIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run_springscript;
+
+    // Checks the program gives the same result as the formula, for all the sensor values.
+    fn check(formula: &str) -> Vec<Instruction> {
+        let expr = Expr::parse(formula).unwrap();
+        let program = compile(&expr).unwrap();
+        for sensors in 0..(1 << Reg::SENSORS.len()) {
+            let mut regs = vec![false; 2];
+            regs.extend((0..Reg::SENSORS.len()).map(|i| sensors & (1 << i) != 0));
+            assert_eq!(
+                run_springscript(&program, &regs),
+                expr.eval(&regs),
+                "{formula} for {regs:?}"
+            );
+        }
+        program
+    }
+
+    #[test]
+    fn test_parse() {
+        let expr = Expr::parse("!(A & B & C) & D & (E | H)").unwrap();
+        assert_eq!(expr.to_string(), "!(A & B & C) & D & (E | H)");
+        assert_eq!(
+            Expr::parse("(!a || !c) && d").unwrap_err(),
+            "Unknown sensor 'a'"
+        );
+        assert_eq!(
+            Expr::parse("(!A || !C) && D").unwrap().to_string(),
+            "(!A | !C) & D"
+        );
+        assert_eq!(Expr::parse("A & (B").unwrap_err(), "Missing ')'");
+        assert_eq!(Expr::parse("A B").unwrap_err(), "Unexpected 'B'");
+        assert!(Expr::parse("T").is_err());
+    }
+
+    #[test]
+    fn test_compile() {
+        assert_eq!(check("D").len(), 1);
+        assert_eq!(check("!D").len(), 1);
+        assert_eq!(check("!(A & B & C) & D & (E | H)").len(), 8);
+        // As short as the shortest known program for part 2.
+        assert_eq!(check("!(A & B & (C | !H)) & D").len(), 6);
+    }
+
+    #[test]
+    fn test_compile_complex() {
+        check("(!A | !C) & D");
+        check("(!B & D & !E) | (!A & D) | (!B & C & D) | (!C & H & D)");
+        check("(A | B) & (C | D) & !(E & F)");
+        check("!(!(A | B) & !(C & !D))");
+    }
+
+    #[test]
+    fn test_too_long() {
+        // Only needs T and J, but more than 15 instructions.
+        let expr =
+            Expr::parse("(A | B) & (C | D) & (E | F) & (G | H) & (I | !A) & (B | !C)").unwrap();
+        assert_eq!(compile(&expr), None);
+        // Needs more than two registers.
+        let expr = Expr::parse("(A & B | C & D) & (E & F | G & H)").unwrap();
+        assert_eq!(compile(&expr), None);
+    }
+}
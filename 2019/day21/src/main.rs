@@ -6,8 +6,10 @@ use std::{
 
 use intcode::IntcodeComputer;
 
-#[derive(Clone, Copy)]
-#[allow(dead_code)]
+mod compiler;
+mod search;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Reg {
     T,
     J,
@@ -23,6 +25,24 @@ enum Reg {
 }
 
 impl Reg {
+    const SENSORS: [Reg; 9] = [
+        Reg::A,
+        Reg::B,
+        Reg::C,
+        Reg::D,
+        Reg::E,
+        Reg::F,
+        Reg::G,
+        Reg::H,
+        Reg::I,
+    ];
+
+    fn sensor(c: char) -> Option<Self> {
+        Self::SENSORS
+            .into_iter()
+            .find(|r| r.to_string() == c.to_string())
+    }
+
     fn is_writable(self) -> bool {
         matches!(self, Reg::T | Reg::J)
     }
@@ -63,6 +83,7 @@ impl fmt::Display for Reg {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Instruction {
     And(Reg, Reg),
     Or(Reg, Reg),
@@ -70,8 +91,7 @@ enum Instruction {
 }
 
 impl Instruction {
-    #[allow(dead_code)]
-    fn exec(&self, reg: &mut [bool]) {
+    fn exec(self, reg: &mut [bool]) {
         match self {
             Instruction::And(x, y) => reg[y.index()] = reg[x.index()] && reg[y.index()],
             Instruction::Or(x, y) => reg[y.index()] = reg[x.index()] || reg[y.index()],
@@ -105,6 +125,15 @@ impl fmt::Display for Instruction {
     }
 }
 
+// Executes the springscript for the given registers, returning J.
+fn run_springscript(instructions: &[Instruction], regs: &[bool]) -> bool {
+    let mut regs = regs.to_vec();
+    for ins in instructions {
+        ins.exec(&mut regs);
+    }
+    regs[Reg::J.index()]
+}
+
 const NEWLINE: i64 = 10;
 
 fn write_string(computer: &mut IntcodeComputer, s: &str) {
@@ -153,8 +182,15 @@ impl ComputerOutput {
     }
 }
 
-fn survey_hull(computer: &IntcodeComputer, instructions: &[Instruction], run: bool) -> i64 {
-    assert!(instructions.len() <= 15, "Too many instructions, max is 15");
+fn run_droid(
+    computer: &IntcodeComputer,
+    instructions: &[Instruction],
+    run: bool,
+) -> ComputerOutput {
+    assert!(
+        instructions.len() <= compiler::MAX_INSTRUCTIONS,
+        "Too many instructions, max is 15"
+    );
 
     let mut computer = computer.clone();
     write_instruction(&mut computer, instructions);
@@ -163,78 +199,58 @@ fn survey_hull(computer: &IntcodeComputer, instructions: &[Instruction], run: bo
 
     computer.exec();
 
-    let output = ComputerOutput::read(&mut computer);
-    match output {
-        ComputerOutput::LastMoments(_) => output.print(),
-        ComputerOutput::HullDamage(damage) => return damage,
-    }
-    panic!("Didn't make it across");
+    ComputerOutput::read(&mut computer)
 }
 
-fn get_walk_instructions() -> Vec<Instruction> {
-    use Reg::{A, C, D, J, T};
-    // Supports jumping over following:
-    // #####.###########
-    // #####...#########
-    // #####.#..########
-
-    // (!a || !c) && d
-    vec![
-        NOT(A, J), // 1-away is empty
-        NOT(C, T),
-        OR(T, J),  // or 3-away is empty
-        AND(D, J), // and 4-away is ground
-    ]
+// Searches a program surviving the hulls the droid fell on so far, and sends it to the droid.
+// Each fall teaches a new hull, until the droid makes it across.
+fn survey_hull(computer: &IntcodeComputer, run: bool) -> i64 {
+    let sensors = if run { Reg::SENSORS.len() } else { 4 };
+    let mut hulls: Vec<Vec<bool>> = Vec::new();
+    loop {
+        let instructions =
+            search::find_program(&hulls, sensors).expect("No springscript program found");
+        let output = run_droid(computer, &instructions, run);
+        match output {
+            ComputerOutput::LastMoments(ref chars) => {
+                let hull = search::parse_hull(&chars.iter().collect::<String>());
+                let Some(hull) = hull.filter(|hull| !hulls.contains(hull)) else {
+                    output.print();
+                    panic!("Didn't make it across");
+                };
+                hulls.push(hull);
+            }
+            ComputerOutput::HullDamage(damage) => return damage,
+        }
+    }
 }
 
 fn survey_hull_part1(computer: &IntcodeComputer) -> i64 {
-    let instructions = get_walk_instructions();
-    survey_hull(computer, &instructions, false)
+    survey_hull(computer, false)
 }
 
-fn get_run_instructions() -> Vec<Instruction> {
-    use Reg::{A, B, C, D, E, H, J, T};
-    // Supports jumping over following:
-    // #####.###########
-    // #####.#..########
-    // #####...#########
-    // #####...##.##.###
-    // #####..##########
-    // #####.#.#..######
-    // #####.##..#.#####
-    // #####..###...####
-    // #####.###..#.####
-
-    // (!b && d && !e) || (!a && d) || (!b && c && d) || (!c && h && d)
-    vec![
-        // !b && d && !e
-        NOT(B, T), // T = NOT B
-        NOT(E, J), // J = NOT E
-        AND(T, J), // J = NOT B AND NOT E
-        AND(D, J), // J = NOT B AND NOT E AND D
-        // !a && d
-        NOT(A, T), // T = NOT A
-        AND(D, T), // T = NOT A AND D
-        OR(T, J),  // J = (NOT B AND NOT E AND D) OR (NOT A AND D)
-        // !b && c && d
-        NOT(B, T), // T = NOT B
-        AND(C, T), // T = NOT B AND C
-        AND(D, T), // T = NOT B AND C AND D
-        OR(T, J),  // J = (NOT B AND NOT E AND D) OR (NOT A AND D) OR (NOT B AND C AND D)
-        // !c && h && d
-        NOT(C, T), // T = NOT C
-        AND(D, T), // T = NOT C AND D
-        AND(H, T), // T = NOT C AND D AND H
-        OR(T, J), // J = (NOT B AND NOT E AND D) OR (NOT A AND D) OR (NOT B AND C AND D) OR (NOT C AND D AND H)
-    ]
+fn survey_hull_part2(computer: &IntcodeComputer) -> i64 {
+    survey_hull(computer, true)
 }
 
-fn survey_hull_part2(computer: &IntcodeComputer) -> i64 {
-    let instructions = get_run_instructions();
-    survey_hull(computer, &instructions, true)
+// Prints the springscript of a formula given on the command line.
+fn print_compiled(formula: &str) {
+    match compiler::Expr::parse(formula) {
+        Ok(expr) => match compiler::compile(&expr) {
+            Some(instructions) => instructions.iter().for_each(|ins| println!("{ins}")),
+            None => println!("{expr} needs more than 15 instructions"),
+        },
+        Err(err) => println!("Invalid formula: {err}"),
+    }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == "--compile" {
+        print_compiled(&args[2]);
+        return;
+    }
+
     let mut input = String::new();
     io::stdin().read_to_string(&mut input).unwrap();
     let computer = IntcodeComputer::build(&input);
@@ -248,26 +264,66 @@ mod tests {
     use super::*;
     use itertools::Itertools;
 
+    use compiler::{compile, Expr};
+
     fn regs(pos_regs: &[bool]) -> Vec<bool> {
         let mut regs = vec![false, false]; // J and T
         regs.extend(pos_regs);
         regs
     }
 
-    // Executes the given springscript for the set if position registers.
-    fn exec_springscript(instructions: &[Instruction], regs: &[bool]) -> bool {
-        let mut regs = regs.to_vec();
-        // println!("Pos regs: {:?}", &regs[2..]);
-        for ins in instructions {
-            ins.exec(&mut regs);
-            // println!(
-            //     "{}: J={} T={}",
-            //     ins,
-            //     regs[Reg::J.index()],
-            //     regs[Reg::T.index()]
-            // );
-        }
-        regs[Reg::J.index()]
+    // The programs written by hand.
+    fn get_walk_instructions() -> Vec<Instruction> {
+        use Reg::{A, C, D, J, T};
+        // Supports jumping over following:
+        // #####.###########
+        // #####...#########
+        // #####.#..########
+
+        // (!a || !c) && d
+        vec![
+            NOT(A, J), // 1-away is empty
+            NOT(C, T),
+            OR(T, J),  // or 3-away is empty
+            AND(D, J), // and 4-away is ground
+        ]
+    }
+
+    fn get_run_instructions() -> Vec<Instruction> {
+        use Reg::{A, B, C, D, E, H, J, T};
+        // Supports jumping over following:
+        // #####.###########
+        // #####.#..########
+        // #####...#########
+        // #####...##.##.###
+        // #####..##########
+        // #####.#.#..######
+        // #####.##..#.#####
+        // #####..###...####
+        // #####.###..#.####
+
+        // (!b && d && !e) || (!a && d) || (!b && c && d) || (!c && h && d)
+        vec![
+            // !b && d && !e
+            NOT(B, T), // T = NOT B
+            NOT(E, J), // J = NOT E
+            AND(T, J), // J = NOT B AND NOT E
+            AND(D, J), // J = NOT B AND NOT E AND D
+            // !a && d
+            NOT(A, T), // T = NOT A
+            AND(D, T), // T = NOT A AND D
+            OR(T, J),  // J = (NOT B AND NOT E AND D) OR (NOT A AND D)
+            // !b && c && d
+            NOT(B, T), // T = NOT B
+            AND(C, T), // T = NOT B AND C
+            AND(D, T), // T = NOT B AND C AND D
+            OR(T, J),  // J = (NOT B AND NOT E AND D) OR (NOT A AND D) OR (NOT B AND C AND D)
+            // !c && h && d
+            NOT(C, T), // T = NOT C
+            AND(D, T), // T = NOT C AND D
+            AND(H, T), // T = NOT C AND D AND H
+            OR(T, J), // J = (NOT B AND NOT E AND D) OR (NOT A AND D) OR (NOT B AND C AND D) OR (NOT C AND D AND H)
+        ]
     }
 
     // Executes the logical expression equivalent to the WALK springscript.
@@ -277,6 +333,7 @@ mod tests {
     }
 
     // Executes the logical expression equivalent to the RUN springscript.
+    #[allow(clippy::many_single_char_names)]
     fn exec_run(r: &[bool]) -> bool {
         let (a, b, c, d, e, _f, _g, h, _i) =
             (r[2], r[3], r[4], r[5], r[6], r[7], r[8], r[9], r[10]);
@@ -294,7 +351,7 @@ mod tests {
             .collect();
         for p in combi {
             let r: Vec<bool> = p.iter().copied().copied().collect(); // ugly..
-            let springscript_result = exec_springscript(instructions, &regs(&r));
+            let springscript_result = run_springscript(instructions, &regs(&r));
             let converted_result = exec_fn(&regs(&r));
             assert_eq!(springscript_result, converted_result, "Failed for {r:?}");
         }
@@ -311,4 +368,19 @@ mod tests {
         let instructions = get_run_instructions();
         verify_instructions(&instructions, 9, exec_run);
     }
+
+    #[test]
+    fn test_compiled_walk() {
+        let instructions = compile(&Expr::parse("(!A | !C) & D").unwrap()).unwrap();
+        assert_eq!(instructions, get_walk_instructions());
+        verify_instructions(&instructions, 4, exec_walk);
+    }
+
+    #[test]
+    fn test_compiled_run() {
+        let expr = Expr::parse("(!B & D & !E) | (!A & D) | (!B & C & D) | (!C & D & H)").unwrap();
+        let instructions = compile(&expr).unwrap();
+        assert!(instructions.len() <= get_run_instructions().len());
+        verify_instructions(&instructions, 9, exec_run);
+    }
 }
@@ -0,0 +1,294 @@
+//! Automatic search of a springscript program getting the droid across known hull patterns.
+//!
+//! The droid jumps when J is true, and a jump lands 4 tiles ahead, so a program must jump only
+//! if D is ground. The search looks for the smallest formula X so that `X & D` survives all the
+//! patterns: formulas are built by combining smaller ones, keeping only one formula for each
+//! truth table over the sensor values seen on the patterns.
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    compiler::{compile, Expr},
+    run_springscript, Instruction, Reg,
+};
+
+// Bit of the D sensor in a window.
+const D: u16 = 1 << 3;
+
+// Formulas with more sensors than that are not tried.
+const MAX_SENSORS_IN_FORMULA: usize = 6;
+
+/// Finds the hull in the output of a droid that fell: the first line made of ground and holes.
+pub fn parse_hull(output: &str) -> Option<Vec<bool>> {
+    output
+        .lines()
+        .find(|line| line.contains('#') && line.chars().all(|c| c == '#' || c == '.'))
+        .map(|line| line.chars().map(|c| c == '#').collect())
+}
+
+// What the sensors see from the position, one bit for each tile ahead. Past the end is ground.
+fn window(hull: &[bool], pos: usize) -> u16 {
+    (0..Reg::SENSORS.len())
+        .filter(|k| *hull.get(pos + k + 1).unwrap_or(&true))
+        .map(|k| 1 << k)
+        .sum()
+}
+
+/// Whether the droid gets across the hull, starting on its first tile.
+pub fn survives(hull: &[bool], jump: impl Fn(u16) -> bool) -> bool {
+    let mut pos = 0;
+    while pos < hull.len() {
+        if !hull[pos] {
+            return false;
+        }
+        pos += if jump(window(hull, pos)) { 4 } else { 1 };
+    }
+    true
+}
+
+/// Whether the springscript program gets the droid across the hull.
+pub fn program_survives(program: &[Instruction], hull: &[bool]) -> bool {
+    survives(hull, |window| {
+        let mut regs = vec![false; 2];
+        regs.extend((0..Reg::SENSORS.len()).map(|k| window & (1 << k) != 0));
+        run_springscript(program, &regs)
+    })
+}
+
+// Formula and its truth table, one bit for each window.
+struct Candidate {
+    expr: Expr,
+    truth: Vec<u64>,
+}
+
+fn join(and: bool, a: &Expr, b: &Expr) -> Expr {
+    let mut ops = Vec::new();
+    for e in [a, b] {
+        match (e, and) {
+            (Expr::And(sub), true) | (Expr::Or(sub), false) => ops.extend(sub.iter().cloned()),
+            _ => ops.push(e.clone()),
+        }
+    }
+    if and {
+        Expr::And(ops)
+    } else {
+        Expr::Or(ops)
+    }
+}
+
+struct Search<'a> {
+    hulls: &'a [Vec<bool>],
+    // Mask of the sensors available.
+    mask: u16,
+    // Index of each window where D is ground.
+    windows: HashMap<u16, usize>,
+}
+
+impl Search<'_> {
+    fn jumps(&self, truth: &[u64], window: u16) -> bool {
+        let window = window & self.mask;
+        window & D != 0 && {
+            let i = self.windows[&window];
+            truth[i / 64] & (1 << (i % 64)) != 0
+        }
+    }
+
+    // The program jumping when the formula and D are true, if it gets across all the hulls.
+    fn program(&self, candidate: &Candidate) -> Option<Vec<Instruction>> {
+        if !self
+            .hulls
+            .iter()
+            .all(|hull| survives(hull, |window| self.jumps(&candidate.truth, window)))
+        {
+            return None;
+        }
+        let expr = join(true, &candidate.expr, &Expr::Sensor(Reg::D));
+        compile(&expr).filter(|program| {
+            self.hulls
+                .iter()
+                .all(|hull| program_survives(program, hull))
+        })
+    }
+
+    fn sensor_truth(&self, k: usize, positive: bool) -> Vec<u64> {
+        let mut truth = vec![0; self.windows.len().div_ceil(64)];
+        for (window, i) in &self.windows {
+            if (window & (1 << k) != 0) == positive {
+                truth[i / 64] |= 1 << (i % 64);
+            }
+        }
+        truth
+    }
+}
+
+/// Finds a springscript program getting the droid across all the hulls, using only the first
+/// `sensors` sensors.
+///
+/// Returns None if there is no such program with a formula small enough.
+pub fn find_program(hulls: &[Vec<bool>], sensors: usize) -> Option<Vec<Instruction>> {
+    let mask = (1 << sensors) - 1;
+    let mut windows = HashMap::new();
+    for hull in hulls {
+        for pos in (0..hull.len()).filter(|pos| hull[*pos]) {
+            let window = window(hull, pos) & mask;
+            if window & D != 0 {
+                let i = windows.len();
+                windows.entry(window).or_insert(i);
+            }
+        }
+    }
+    let search = Search {
+        hulls,
+        mask,
+        windows,
+    };
+
+    // Simply jumping when D is ground.
+    if hulls
+        .iter()
+        .all(|hull| survives(hull, |window| window & D != 0))
+    {
+        return compile(&Expr::Sensor(Reg::D));
+    }
+
+    let mut seen = HashSet::new();
+    // Formulas by number of sensors in them.
+    let mut levels: Vec<Vec<Candidate>> = vec![Vec::new(), Vec::new()];
+    for (k, sensor) in Reg::SENSORS.iter().enumerate().take(sensors) {
+        for positive in [true, false] {
+            let truth = search.sensor_truth(k, positive);
+            if seen.insert(truth.clone()) {
+                let expr = Expr::Sensor(*sensor);
+                let expr = if positive {
+                    expr
+                } else {
+                    Expr::Not(Box::new(expr))
+                };
+                let candidate = Candidate { expr, truth };
+                if let Some(program) = search.program(&candidate) {
+                    return Some(program);
+                }
+                levels[1].push(candidate);
+            }
+        }
+    }
+
+    for n in 2..=MAX_SENSORS_IN_FORMULA {
+        let mut level = Vec::new();
+        for i in 1..=n / 2 {
+            for (ai, a) in levels[i].iter().enumerate() {
+                let others = if i == n - i {
+                    &levels[i][ai + 1..]
+                } else {
+                    &levels[n - i][..]
+                };
+                for b in others {
+                    for and in [true, false] {
+                        let truth: Vec<u64> = a
+                            .truth
+                            .iter()
+                            .zip(&b.truth)
+                            .map(|(x, y)| if and { x & y } else { x | y })
+                            .collect();
+                        if !seen.insert(truth.clone()) {
+                            continue;
+                        }
+                        let candidate = Candidate {
+                            expr: join(and, &a.expr, &b.expr),
+                            truth,
+                        };
+                        if let Some(program) = search.program(&candidate) {
+                            return Some(program);
+                        }
+                        level.push(candidate);
+                    }
+                }
+            }
+        }
+        levels.push(level);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hulls(patterns: &[&str]) -> Vec<Vec<bool>> {
+        patterns
+            .iter()
+            .map(|p| p.chars().map(|c| c == '#').collect())
+            .collect()
+    }
+
+    fn check(patterns: &[&str], sensors: usize) -> Vec<Instruction> {
+        let hulls = hulls(patterns);
+        let program = find_program(&hulls, sensors).unwrap();
+        for hull in &hulls {
+            assert!(program_survives(&program, hull));
+        }
+        program
+    }
+
+    #[test]
+    fn test_parse_hull() {
+        let output = "Input instructions:\n\nWalking...\n\n\nDidn't make it across:\n\n\
+                      .................\n.................\n@................\n\
+                      #####.###########\n\n.................\n";
+        assert_eq!(
+            parse_hull(output),
+            Some(hulls(&["#####.###########"])[0].clone())
+        );
+        assert_eq!(parse_hull("Walking...\n"), None);
+    }
+
+    #[test]
+    fn test_survives() {
+        let hull = &hulls(&["#####.#..########"])[0];
+        assert!(!survives(hull, |_| false));
+        // Jumping from the last ground tile before the hole lands on a hole.
+        assert!(!survives(hull, |window| window & 1 == 0));
+        assert!(survives(hull, |window| window & D != 0 && window & 0b101 != 0b101));
+    }
+
+    #[test]
+    fn test_no_hull() {
+        assert_eq!(check(&[], 4).len(), 1);
+    }
+
+    #[test]
+    fn test_walk() {
+        let program = check(
+            &[
+                "#####.###########",
+                "#####...#########",
+                "#####.#..########",
+            ],
+            4,
+        );
+        assert!(program.len() <= 4);
+    }
+
+    #[test]
+    fn test_run() {
+        check(
+            &[
+                "#####.###########",
+                "#####.#..########",
+                "#####...#########",
+                "#####...##.##.###",
+                "#####..##########",
+                "#####.#.#..######",
+                "#####.##..#.#####",
+                "#####..###...####",
+                "#####.###..#.####",
+            ],
+            Reg::SENSORS.len(),
+        );
+    }
+
+    #[test]
+    fn test_impossible() {
+        // A hole of 4 tiles can't be jumped over.
+        assert_eq!(find_program(&hulls(&["###....###"]), 4), None);
+    }
+}
@@ -0,0 +1,252 @@
+//! Benchmarks of the interpreter and the fast computer, on the real inputs of days 9, 13 and 19,
+//! and on a program with nested loops. All but day 13 also run the program translated to C and Rust.
+//!
+//! The inputs aren't part of the repository, the days without one are skipped.
+use std::{cmp::Ordering, fs};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use intcode::{
+    assemble,
+    translate::{Language, Native},
+    FastIntcodeComputer, IntcodeComputer,
+};
+use itertools::Itertools;
+
+fn get_input(day: &str) -> Option<String> {
+    let path = format!("../{day}/resources/input");
+    match fs::read_to_string(&path) {
+        Ok(input) => Some(input.trim().to_string()),
+        Err(err) => {
+            eprintln!("Skipping {day}, unable to read {path}: {err}");
+            None
+        }
+    }
+}
+
+// What the benchmarks need from both computers.
+trait Computer: Clone {
+    fn add_input(&mut self, input: i64);
+    fn get_output(&mut self) -> Option<i64>;
+    fn exec(&mut self);
+    fn is_halted(&self) -> bool;
+    fn write_mem(&mut self, addr: usize, val: i64);
+}
+
+macro_rules! impl_computer {
+    ($t:ty) => {
+        impl Computer for $t {
+            fn add_input(&mut self, input: i64) {
+                self.io.add_input(input);
+            }
+
+            fn get_output(&mut self) -> Option<i64> {
+                self.io.get_output()
+            }
+
+            fn exec(&mut self) {
+                <$t>::exec(self);
+            }
+
+            fn is_halted(&self) -> bool {
+                <$t>::is_halted(self)
+            }
+
+            fn write_mem(&mut self, addr: usize, val: i64) {
+                <$t>::write_mem(self, addr, val);
+            }
+        }
+    };
+}
+
+impl_computer!(IntcodeComputer);
+impl_computer!(FastIntcodeComputer);
+
+// Day 9 part 2, the BOOST program in sensor boost mode.
+fn boost<C: Computer>(computer: &C) -> i64 {
+    let mut computer = computer.clone();
+    computer.add_input(2);
+    computer.exec();
+    computer.get_output().unwrap()
+}
+
+fn boost_native(native: &Native) -> i64 {
+    *native.query(&[2]).unwrap().last().unwrap()
+}
+
+// Day 13 part 2, playing the game by keeping the paddle under the ball.
+fn play_breakout<C: Computer>(computer: &C) -> i64 {
+    let mut computer = computer.clone();
+    computer.write_mem(0, 2);
+    let (mut ball, mut paddle, mut score) = (0, 0, 0);
+    loop {
+        computer.exec();
+        while let Some(x) = computer.get_output() {
+            let y = computer.get_output().unwrap();
+            let val = computer.get_output().unwrap();
+            match (x, y, val) {
+                (-1, 0, _) => score = val,
+                (_, _, 3) => paddle = x,
+                (_, _, 4) => ball = x,
+                _ => {}
+            }
+        }
+        if computer.is_halted() {
+            return score;
+        }
+        computer.add_input(match ball.cmp(&paddle) {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        });
+    }
+}
+
+// Day 19 part 1, the number of points pulled by the beam in the 50x50 area.
+fn beam_size<C: Computer>(computer: &C) -> i64 {
+    let mut count = 0;
+    for y in 0..50 {
+        for x in 0..50 {
+            let mut computer = computer.clone();
+            computer.add_input(x);
+            computer.add_input(y);
+            computer.exec();
+            if computer.get_output() == Some(1) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+// All the queries in one process, the program starting again after each one.
+fn beam_size_native(native: &Native) -> i64 {
+    let inputs: Vec<i64> = (0..50)
+        .flat_map(|y| (0..50).flat_map(move |x| [x, y]))
+        .collect();
+    let output = native.query_repeated(&inputs).unwrap();
+    output
+        .iter()
+        .filter(|v| **v == 1)
+        .count()
+        .try_into()
+        .unwrap()
+}
+
+// Counts the pairs (i, j) of numbers below the input with i * j below the input too.
+const PAIRS: &str = r"
+        in [n]
+outer:  add #0, #0 -> [j]
+inner:  mul [i], [j] -> [p]
+        lt [p], [n] -> [t]
+        add [count], [t] -> [count]
+        add [j], #1 -> [j]
+        lt [j], [n] -> [t]
+        jt [t], inner
+        add [i], #1 -> [i]
+        lt [i], [n] -> [t]
+        jt [t], outer
+        out [count]
+        hlt
+n:      .data 0
+i:      .data 0
+j:      .data 0
+p:      .data 0
+t:      .data 0
+count:  .data 0
+";
+
+// 9 millions iterations of the inner loop, each of 6 instructions.
+const PAIRS_LIMIT: i64 = 3000;
+
+fn pairs<C: Computer>(computer: &C) -> i64 {
+    let mut computer = computer.clone();
+    computer.add_input(PAIRS_LIMIT);
+    computer.exec();
+    computer.get_output().unwrap()
+}
+
+fn pairs_native(native: &Native) -> i64 {
+    *native.query(&[PAIRS_LIMIT]).unwrap().last().unwrap()
+}
+
+// The translated programs are compiled before, and run as separate processes,
+// so their times include starting the process.
+fn bench_day<F, G>(
+    c: &mut Criterion,
+    name: &str,
+    input: &str,
+    interpreter: F,
+    fast: G,
+    translated: Option<fn(&Native) -> i64>,
+) where
+    F: Fn(&IntcodeComputer) -> i64,
+    G: Fn(&FastIntcodeComputer) -> i64,
+{
+    let computer = IntcodeComputer::build(input);
+    let fast_computer = FastIntcodeComputer::build(input);
+    let expected = interpreter(&computer);
+    assert_eq!(expected, fast(&fast_computer));
+
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    group.bench_function("interpreter", |b| b.iter(|| interpreter(&computer)));
+    group.bench_function("fast", |b| b.iter(|| fast(&fast_computer)));
+    if let Some(translated) = translated {
+        let mem: Vec<i64> = input.split(',').map(|v| v.parse().unwrap()).collect();
+        for (language, id) in [
+            (Language::C, "translated_c"),
+            (Language::Rust, "translated_rust"),
+        ] {
+            let native = Native::build(&mem, language).unwrap();
+            assert_eq!(expected, translated(&native));
+            group.bench_function(id, |b| b.iter(|| translated(&native)));
+        }
+    }
+    group.finish();
+}
+
+fn day09_boost(c: &mut Criterion) {
+    if let Some(input) = get_input("day09") {
+        bench_day(c, "day09_boost", &input, boost, boost, Some(boost_native));
+    }
+}
+
+fn day13_breakout(c: &mut Criterion) {
+    if let Some(input) = get_input("day13") {
+        bench_day(
+            c,
+            "day13_breakout",
+            &input,
+            play_breakout,
+            play_breakout,
+            None,
+        );
+    }
+}
+
+fn day19_beam(c: &mut Criterion) {
+    if let Some(input) = get_input("day19") {
+        bench_day(
+            c,
+            "day19_beam",
+            &input,
+            beam_size,
+            beam_size,
+            Some(beam_size_native),
+        );
+    }
+}
+
+fn pairs_loops(c: &mut Criterion) {
+    let input = assemble(PAIRS).unwrap().iter().join(",");
+    bench_day(c, "pairs", &input, pairs, pairs, Some(pairs_native));
+}
+
+criterion_group!(
+    benches,
+    day09_boost,
+    day13_breakout,
+    day19_beam,
+    pairs_loops
+);
+criterion_main!(benches);
@@ -0,0 +1,754 @@
+//! Harness for text adventure games, like the one of day 25.
+//!
+//! The game describes rooms like this:
+//!
+//! ```text
+//! == Hull Breach ==
+//! You got in through a hole in the floor here.
+//!
+//! Doors here lead:
+//! - north
+//! - east
+//!
+//! Items here:
+//! - mutex
+//!
+//! Command?
+//! ```
+//!
+//! Commands are directions, `take <item>`, `drop <item>` and `inv`.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fmt::Write as _,
+    io::{self, BufRead, Write},
+};
+
+use crate::{ASCIIBus, ExecState, IntcodeComputer};
+
+/// Items that end the game, make it loop forever, or prevent from moving, in day 25.
+pub const DEADLY_ITEMS: [&str; 5] = [
+    "escape pod",
+    "giant electromagnet",
+    "infinite loop",
+    "molten lava",
+    "photons",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    /// Waiting for a command.
+    Running,
+    Over,
+    /// The game didn't ask for a command in the allowed time.
+    Stuck,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub output: String,
+    pub state: GameState,
+}
+
+/// A text adventure game.
+pub trait Game: Clone {
+    /// Runs the game until it asks for the first command.
+    fn start(&mut self) -> Response;
+
+    /// Sends a command to the game.
+    fn send(&mut self, command: &str) -> Response;
+}
+
+/// Game running on an Intcode computer.
+#[derive(Debug, Clone)]
+pub struct IntcodeGame {
+    computer: IntcodeComputer<ASCIIBus>,
+    step_limit: usize,
+}
+
+impl IntcodeGame {
+    /// Number of instructions the game can execute for one command before being considered stuck.
+    pub const DEFAULT_STEP_LIMIT: usize = 10_000_000;
+
+    #[must_use]
+    pub fn new(computer: &IntcodeComputer) -> Self {
+        Self {
+            computer: computer.clone().with_bus(ASCIIBus::new()),
+            step_limit: Self::DEFAULT_STEP_LIMIT,
+        }
+    }
+
+    #[must_use]
+    pub fn with_step_limit(mut self, step_limit: usize) -> Self {
+        self.step_limit = step_limit;
+        self
+    }
+
+    fn run(&mut self) -> Response {
+        for _ in 0..self.step_limit {
+            let state = match self.computer.step() {
+                ExecState::Running => continue,
+                ExecState::NeedsInput => GameState::Running,
+                ExecState::Halted => GameState::Over,
+                // A broken game is over as well, so that searches can continue.
+                ExecState::Error(e) => {
+                    let mut output = self.computer.io.take_output();
+                    writeln!(output, "\nError: {e}").unwrap();
+                    return Response {
+                        output,
+                        state: GameState::Over,
+                    };
+                }
+            };
+            return Response {
+                output: self.computer.io.take_output(),
+                state,
+            };
+        }
+        Response {
+            output: self.computer.io.take_output(),
+            state: GameState::Stuck,
+        }
+    }
+}
+
+impl Game for IntcodeGame {
+    fn start(&mut self) -> Response {
+        self.run()
+    }
+
+    fn send(&mut self, command: &str) -> Response {
+        self.computer.io.add_line(command);
+        self.run()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Room {
+    pub name: String,
+    pub description: String,
+    pub doors: Vec<String>,
+    pub items: Vec<String>,
+}
+
+/// Parses all the room descriptions found in the output.
+#[must_use]
+pub fn parse_rooms(output: &str) -> Vec<Room> {
+    #[derive(PartialEq)]
+    enum Section {
+        Text,
+        Doors,
+        Items,
+    }
+
+    let mut rooms: Vec<Room> = Vec::new();
+    let mut section = Section::Text;
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("== ").and_then(|l| l.strip_suffix(" ==")) {
+            rooms.push(Room {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            section = Section::Text;
+            continue;
+        }
+        let Some(room) = rooms.last_mut() else {
+            continue;
+        };
+        match line {
+            "Doors here lead:" => section = Section::Doors,
+            "Items here:" => section = Section::Items,
+            "" => section = Section::Text,
+            _ => {
+                if let Some(entry) = line.strip_prefix("- ") {
+                    match section {
+                        Section::Doors => room.doors.push(entry.to_string()),
+                        Section::Items => room.items.push(entry.to_string()),
+                        Section::Text => {}
+                    }
+                } else if section == Section::Text && room.description.is_empty() {
+                    room.description = line.to_string();
+                }
+            }
+        }
+    }
+    rooms
+}
+
+/// Parses saved commands, one per line. Lines starting with # are comments.
+#[must_use]
+pub fn parse_script(script: &str) -> Vec<String> {
+    script
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Plays the game interactively on the terminal, starting with the commands of the script.
+///
+/// # Panics
+///
+/// Will panic if the terminal cannot be read or written.
+pub fn play<G: Game>(game: &mut G, script: &[String]) {
+    let mut script = script.iter();
+    let mut response = game.start();
+    loop {
+        println!("{}", response.output);
+        if response.state != GameState::Running {
+            println!("Game over");
+            break;
+        }
+
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let command = if let Some(cmd) = script.next() {
+            println!("{cmd}");
+            cmd.clone()
+        } else {
+            let mut line = String::new();
+            if io::stdin().lock().read_line(&mut line).unwrap() == 0 {
+                break;
+            }
+            line.trim().to_string()
+        };
+        response = game.send(&command);
+    }
+}
+
+fn opposite(door: &str) -> &str {
+    match door {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => panic!("Unknown direction {door}"),
+    }
+}
+
+fn direction_offset(door: &str) -> (i32, i32) {
+    match door {
+        "north" => (0, -1),
+        "south" => (0, 1),
+        "east" => (1, 0),
+        "west" => (-1, 0),
+        _ => panic!("Unknown direction {door}"),
+    }
+}
+
+/// Explores the game automatically, collecting the items and solving the pressure plate.
+pub struct Adventure<G: Game> {
+    game: G,
+    current: String,
+    start: String,
+    // Rooms as they were on the first visit.
+    rooms: BTreeMap<String, Room>,
+    // Where each door of each room goes.
+    edges: BTreeMap<(String, String), String>,
+    avoided_items: BTreeSet<String>,
+    inventory: Vec<String>,
+    // The room and door leading to the pressure-sensitive floor, that ejects us back.
+    checkpoint: Option<(String, String)>,
+}
+
+impl<G: Game> Adventure<G> {
+    /// Starts the game. The avoided items are never taken.
+    /// Other items are tested on a copy of the game before taking them.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the game doesn't start in a room.
+    pub fn new(mut game: G, avoided_items: &[&str]) -> Self {
+        let response = game.start();
+        let room = parse_rooms(&response.output)
+            .pop()
+            .expect("Game didn't start in a room");
+        Self {
+            game,
+            current: room.name.clone(),
+            start: room.name.clone(),
+            rooms: BTreeMap::from([(room.name.clone(), room)]),
+            edges: BTreeMap::new(),
+            avoided_items: avoided_items.iter().map(ToString::to_string).collect(),
+            inventory: Vec::new(),
+            checkpoint: None,
+        }
+    }
+
+    #[must_use]
+    pub fn inventory(&self) -> &[String] {
+        &self.inventory
+    }
+
+    #[must_use]
+    pub fn avoided_items(&self) -> &BTreeSet<String> {
+        &self.avoided_items
+    }
+
+    #[must_use]
+    pub fn rooms(&self) -> &BTreeMap<String, Room> {
+        &self.rooms
+    }
+
+    // Checks on a copy of the game that taking the item doesn't end it, or prevent from moving.
+    fn is_safe(&self, item: &str, room: &Room) -> bool {
+        let mut game = self.game.clone();
+        if game.send(&format!("take {item}")).state != GameState::Running {
+            return false;
+        }
+        room.doors.first().is_none_or(|door| {
+            let response = game.send(door);
+            response.state == GameState::Running && !response.output.contains("can't move")
+        })
+    }
+
+    fn collect_items(&mut self, room: &Room) {
+        for item in &room.items {
+            if self.avoided_items.contains(item) {
+                continue;
+            }
+            if !self.is_safe(item, room) {
+                self.avoided_items.insert(item.clone());
+                continue;
+            }
+            let response = self.game.send(&format!("take {item}"));
+            assert_eq!(response.state, GameState::Running);
+            self.inventory.push(item.clone());
+        }
+    }
+
+    // Goes through the door, recording where it leads.
+    fn move_to(&mut self, door: &str) {
+        let from = self.current.clone();
+        let response = self.game.send(door);
+        assert_eq!(response.state, GameState::Running, "Game over while moving");
+        let rooms = parse_rooms(&response.output);
+        let room = rooms.last().expect("No room after moving").clone();
+        if room.name == from {
+            // Ejected back, the door leads to the pressure-sensitive floor.
+            let floor = rooms.first().unwrap().clone();
+            self.edges
+                .insert((from.clone(), door.to_string()), floor.name.clone());
+            self.rooms.entry(floor.name.clone()).or_insert(floor);
+            self.checkpoint = Some((from, door.to_string()));
+            return;
+        }
+
+        self.edges
+            .insert((from.clone(), door.to_string()), room.name.clone());
+        self.edges
+            .insert((room.name.clone(), opposite(door).to_string()), from);
+        self.current.clone_from(&room.name);
+        if !self.rooms.contains_key(&room.name) {
+            self.rooms.insert(room.name.clone(), room.clone());
+            self.collect_items(&room);
+        }
+    }
+
+    // Breadth first search from the current room, until `is_target` returns true for a door.
+    // Returns the doors to go through. Doesn't go through the checkpoint.
+    fn find_path<F>(&self, is_target: F) -> Option<Vec<String>>
+    where
+        F: Fn(&str, &str) -> bool,
+    {
+        let mut queue: VecDeque<&str> = VecDeque::from([self.current.as_str()]);
+        let mut paths: HashMap<&str, Vec<String>> =
+            HashMap::from([(self.current.as_str(), vec![])]);
+        while let Some(room) = queue.pop_front() {
+            let path = paths[room].clone();
+            for door in &self.rooms[room].doors {
+                if self.checkpoint.as_ref() == Some(&(room.to_string(), door.clone())) {
+                    continue;
+                }
+                let mut next_path = path.clone();
+                next_path.push(door.clone());
+                if is_target(room, door) {
+                    return Some(next_path);
+                }
+                if let Some(next) = self.edges.get(&(room.to_string(), door.clone())) {
+                    if !paths.contains_key(next.as_str()) {
+                        paths.insert(next, next_path);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Visits all the rooms, taking all the items that are safe.
+    pub fn explore(&mut self) {
+        let room = self.rooms[&self.current].clone();
+        self.collect_items(&room);
+        while let Some(path) = self.find_path(|room, door| {
+            !self
+                .edges
+                .contains_key(&(room.to_string(), door.to_string()))
+        }) {
+            for door in path {
+                self.move_to(&door);
+            }
+        }
+    }
+
+    /// Goes to the checkpoint and tries all the combinations of the items in the inventory,
+    /// changing one item at a time (Gray code order), until being allowed on the pressure-sensitive floor.
+    /// Returns the last output of the game.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the game ends differently.
+    pub fn solve_checkpoint(&mut self) -> Option<String> {
+        let (room, door) = self.checkpoint.clone()?;
+        let path =
+            self.find_path(|r, d| self.edges.get(&(r.to_string(), d.to_string())) == Some(&room));
+        for d in path.unwrap_or_default() {
+            self.move_to(&d);
+        }
+
+        let items = self.inventory.clone();
+        let mut carried = vec![true; items.len()];
+        for i in 0_u64..(1 << items.len()) {
+            if i > 0 {
+                // From one Gray code to the next, the changed bit is the lowest set bit of i.
+                let k = i.trailing_zeros() as usize;
+                let action = if carried[k] { "drop" } else { "take" };
+                carried[k] = !carried[k];
+                let response = self.game.send(&format!("{action} {}", items[k]));
+                assert_eq!(response.state, GameState::Running);
+            }
+            let response = self.game.send(&door);
+            match response.state {
+                GameState::Running => {}
+                GameState::Over => return Some(response.output),
+                GameState::Stuck => panic!("Game stuck at checkpoint"),
+            }
+        }
+        None
+    }
+
+    // Position of each room, if they fit on a grid.
+    fn positions(&self) -> Option<BTreeMap<(i32, i32), &Room>> {
+        let mut positions: HashMap<&str, (i32, i32)> =
+            HashMap::from([(self.start.as_str(), (0, 0))]);
+        let mut queue = VecDeque::from([self.start.as_str()]);
+        while let Some(room) = queue.pop_front() {
+            let (x, y) = positions[room];
+            for ((from, door), to) in &self.edges {
+                if from != room {
+                    continue;
+                }
+                let (dx, dy) = direction_offset(door);
+                let pos = (x + dx, y + dy);
+                match positions.get(to.as_str()) {
+                    Some(p) if *p != pos => return None,
+                    Some(_) => {}
+                    None => {
+                        positions.insert(to, pos);
+                        queue.push_back(to);
+                    }
+                }
+            }
+        }
+        let grid: BTreeMap<(i32, i32), &Room> = positions
+            .iter()
+            .map(|(name, (x, y))| ((*y, *x), &self.rooms[*name]))
+            .collect();
+        // Two rooms at the same place.
+        (grid.len() == positions.len()).then_some(grid)
+    }
+
+    fn items_str(&self, room: &Room) -> String {
+        room.items
+            .iter()
+            .map(|item| {
+                if self.avoided_items.contains(item) {
+                    format!("[-{item}-]")
+                } else {
+                    format!("({item})")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Map of the explored rooms, with their items. Deadly items are between [- -].
+    ///
+    /// If the rooms don't fit on a grid, the rooms are listed with where their doors lead.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if a door leads in an unknown direction.
+    #[must_use]
+    pub fn map(&self) -> String {
+        let Some(grid) = self.positions() else {
+            let mut map = String::new();
+            for room in self.rooms.values() {
+                let doors: Vec<String> = room
+                    .doors
+                    .iter()
+                    .map(|d| {
+                        let to = self.edges.get(&(room.name.clone(), d.clone()));
+                        format!("{d} -> {}", to.map_or("?", String::as_str))
+                    })
+                    .collect();
+                writeln!(
+                    map,
+                    "{}: {} {}",
+                    room.name,
+                    doors.join(", "),
+                    self.items_str(room)
+                )
+                .unwrap();
+            }
+            return map;
+        };
+
+        // Wide enough for all the texts, and odd to center the doors.
+        let width = grid
+            .values()
+            .flat_map(|room| [room.name.len(), self.items_str(room).len()])
+            .max()
+            .unwrap()
+            + 2;
+        let width = width | 1;
+        let min_x = grid.keys().map(|(_, x)| *x).min().unwrap();
+        let max_x = grid.keys().map(|(_, x)| *x).max().unwrap();
+        let min_y = grid.keys().map(|(y, _)| *y).min().unwrap();
+        let max_y = grid.keys().map(|(y, _)| *y).max().unwrap();
+        let border = |has_door: bool| {
+            let side = "-".repeat((width + 2 - 5) / 2);
+            let middle = if has_door { "|   |" } else { "-----" };
+            format!("{side}{middle}{side}")
+        };
+
+        let mut map = String::new();
+        for y in min_y..=max_y {
+            let mut lines = vec![String::new(); 4];
+            for x in min_x..=max_x {
+                if let Some(room) = grid.get(&(y, x)) {
+                    let has = |d: &str| room.doors.iter().any(|door| door == d);
+                    let connector = if has("east") { "< >" } else { "   " };
+                    lines[0] += &border(has("north"));
+                    write!(lines[1], "|{:^width$}|{connector}", room.name).unwrap();
+                    write!(lines[2], "|{:^width$}|{connector}", self.items_str(room)).unwrap();
+                    lines[3] += &border(has("south"));
+                    lines[0] += "   ";
+                    lines[3] += "   ";
+                } else {
+                    for line in &mut lines {
+                        *line += &" ".repeat(width + 5);
+                    }
+                }
+            }
+            for line in lines {
+                map += line.trim_end();
+                map += "\n";
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct MockRoom {
+        name: &'static str,
+        doors: Vec<(&'static str, usize)>,
+        items: Vec<&'static str>,
+    }
+
+    const FLOOR: usize = 5;
+
+    // A small game world:
+    //
+    //   Hot Place - Kitchen        Floor
+    //                  |             |
+    //             Hull Breach - Lab - Checkpoint
+    #[derive(Debug, Clone)]
+    struct MockGame {
+        rooms: Vec<MockRoom>,
+        current: usize,
+        carried: BTreeSet<&'static str>,
+    }
+
+    impl MockGame {
+        fn new() -> Self {
+            let room = |name, doors, items| MockRoom { name, doors, items };
+            Self {
+                rooms: vec![
+                    room("Hull Breach", vec![("north", 1), ("east", 3)], vec![]),
+                    room(
+                        "Kitchen",
+                        vec![("south", 0), ("west", 2)],
+                        vec!["wreath", "infinite loop"],
+                    ),
+                    room("Hot Place", vec![("east", 1)], vec!["molten lava"]),
+                    room(
+                        "Lab",
+                        vec![("west", 0), ("east", 4)],
+                        vec!["giant electromagnet", "coin", "jam"],
+                    ),
+                    room(
+                        "Security Checkpoint",
+                        vec![("west", 3), ("north", FLOOR)],
+                        vec![],
+                    ),
+                    room("Pressure-Sensitive Floor", vec![("south", 4)], vec![]),
+                ],
+                current: 0,
+                carried: BTreeSet::new(),
+            }
+        }
+
+        fn describe(&self, index: usize) -> String {
+            let room = &self.rooms[index];
+            let mut s = format!("\n\n\n== {} ==\nA room.\n\nDoors here lead:\n", room.name);
+            for (door, _) in &room.doors {
+                writeln!(s, "- {door}").unwrap();
+            }
+            if !room.items.is_empty() {
+                s += "\nItems here:\n";
+                for item in &room.items {
+                    writeln!(s, "- {item}").unwrap();
+                }
+            }
+            s
+        }
+
+        fn response(output: String) -> Response {
+            Response {
+                output: output + "\nCommand?\n",
+                state: GameState::Running,
+            }
+        }
+    }
+
+    impl Game for MockGame {
+        fn start(&mut self) -> Response {
+            Self::response(self.describe(self.current))
+        }
+
+        fn send(&mut self, command: &str) -> Response {
+            if let Some(item) = command.strip_prefix("take ") {
+                let room = &mut self.rooms[self.current];
+                let pos = room.items.iter().position(|i| *i == item).unwrap();
+                let item = room.items.remove(pos);
+                match item {
+                    "molten lava" => {
+                        return Response {
+                            output: "You melt!".to_string(),
+                            state: GameState::Over,
+                        }
+                    }
+                    "infinite loop" => {
+                        return Response {
+                            output: String::new(),
+                            state: GameState::Stuck,
+                        }
+                    }
+                    _ => {}
+                }
+                self.carried.insert(item);
+                return Self::response(format!("You take the {item}.\n"));
+            }
+            if let Some(item) = command.strip_prefix("drop ") {
+                let item = self.carried.take(item).unwrap();
+                self.rooms[self.current].items.push(item);
+                return Self::response(format!("You drop the {item}.\n"));
+            }
+            if self.carried.contains("giant electromagnet") {
+                return Self::response(
+                    "The giant electromagnet is stuck to you.  You can't move!!\n".to_string(),
+                );
+            }
+            let (_, to) = *self.rooms[self.current]
+                .doors
+                .iter()
+                .find(|(d, _)| *d == command)
+                .unwrap();
+            if to == FLOOR {
+                if self.carried == BTreeSet::from(["coin", "wreath"]) {
+                    return Response {
+                        output: self.describe(FLOOR)
+                            + "You should be able to get in by typing 1234 on the keypad.\n",
+                        state: GameState::Over,
+                    };
+                }
+                return Self::response(
+                    self.describe(FLOOR)
+                        + "\nAlert! You are ejected back to the checkpoint.\n"
+                        + &self.describe(self.current),
+                );
+            }
+            self.current = to;
+            Self::response(self.describe(to))
+        }
+    }
+
+    #[test]
+    fn test_parse_rooms() {
+        let output = "\n\n\n== Hull Breach ==\nYou got in through a hole in the floor here.\n\nDoors here lead:\n- north\n- east\n\nItems here:\n- mutex\n\nCommand?\n";
+        assert_eq!(
+            parse_rooms(output),
+            [Room {
+                name: "Hull Breach".to_string(),
+                description: "You got in through a hole in the floor here.".to_string(),
+                doors: vec!["north".to_string(), "east".to_string()],
+                items: vec!["mutex".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_script() {
+        assert_eq!(
+            parse_script("north\n# Kitchen\ntake jam"),
+            ["north", "take jam"]
+        );
+    }
+
+    #[test]
+    fn test_explore() {
+        let mut adventure = Adventure::new(MockGame::new(), &[]);
+        adventure.explore();
+        assert_eq!(adventure.rooms().len(), 6);
+        let mut inventory = adventure.inventory().to_vec();
+        inventory.sort();
+        assert_eq!(inventory, ["coin", "jam", "wreath"]);
+        assert_eq!(
+            adventure.avoided_items(),
+            &BTreeSet::from([
+                "giant electromagnet".to_string(),
+                "infinite loop".to_string(),
+                "molten lava".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_solve_checkpoint() {
+        let mut adventure = Adventure::new(MockGame::new(), &DEADLY_ITEMS);
+        adventure.explore();
+        let output = adventure.solve_checkpoint().unwrap();
+        assert!(output.contains("typing 1234"));
+    }
+
+    #[test]
+    fn test_map() {
+        let mut adventure = Adventure::new(MockGame::new(), &DEADLY_ITEMS);
+        adventure.explore();
+        let map = adventure.map();
+        let lines: Vec<&str> = map.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[1].contains("Hot Place"));
+        assert!(lines[1].contains("Kitchen"));
+        assert!(lines[1].contains("Pressure-Sensitive Floor"));
+        assert!(lines[2].contains("[-molten lava-]"));
+        assert!(lines[2].contains("(wreath) [-infinite loop-]"));
+        assert!(lines[5].contains("Hull Breach"));
+        assert!(lines[5].contains("< >"));
+    }
+}
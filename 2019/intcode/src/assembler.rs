@@ -0,0 +1,476 @@
+//! Assembler of Intcode programs.
+//!
+//! A program has one instruction or data directive per line, optionally preceded by labels:
+//!
+//! ```text
+//! ; Prints the string.
+//!         arb #msg
+//! loop:   jf [rb+0], end
+//!         out [rb+0]
+//!         arb #1
+//!         jt #1, loop
+//! end:    hlt
+//! msg:    .data "Hello\n", 0
+//! ```
+//!
+//! The mnemonics are `add`, `mul`, `in`, `out`, `jt`, `jf`, `lt`, `eq`, `arb` and `hlt`, in any case.
+//! Parameters are `#5` in immediate mode, `[100]` in position mode and `[rb+3]` in relative mode.
+//! Values can be numbers, labels, or labels with an offset like `msg+2`, and a bare value is immediate.
+//! Comments start with `;`.
+//!
+//! The output of the disassembler is accepted as well: `JNZ`/`JZ`, `->` before the written parameter,
+//! `DATA` directives and address prefixes like `12:`, which are checked.
+use std::{collections::HashMap, fmt};
+
+use crate::{Instruction, Param};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyError {
+    /// Line number, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+// A number, or a label plus an offset.
+#[derive(Debug, Clone)]
+struct Value {
+    label: Option<String>,
+    offset: i64,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Position(Value),
+    Immediate(Value),
+    Relative(Value),
+}
+
+enum Statement {
+    // Opcode and operands.
+    Instruction(i64, Vec<Operand>),
+    Data(Vec<Value>),
+}
+
+impl Statement {
+    fn length(&self) -> usize {
+        match self {
+            Statement::Instruction(_, operands) => operands.len() + 1,
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+// Opcode, number of parameters, and whether the last one is written to.
+fn mnemonic(name: &str) -> Option<(i64, usize, bool)> {
+    Some(match name.to_lowercase().as_str() {
+        "add" => (1, 3, true),
+        "mul" => (2, 3, true),
+        "in" => (3, 1, true),
+        "out" => (4, 1, false),
+        "jt" | "jnz" => (5, 2, false),
+        "jf" | "jz" => (6, 2, false),
+        "lt" => (7, 3, true),
+        "eq" => (8, 3, true),
+        "arb" => (9, 1, false),
+        "hlt" => (99, 0, false),
+        _ => return None,
+    })
+}
+
+fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !s.eq_ignore_ascii_case("rb")
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+    let s = s.trim();
+    if let Ok(offset) = s.parse() {
+        return Ok(Value {
+            label: None,
+            offset,
+        });
+    }
+    let (label, offset) = match s.find(['+', '-']) {
+        Some(i) => {
+            let offset: i64 = s[i + 1..]
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid value '{s}'"))?;
+            let offset = if s[i..].starts_with('-') {
+                -offset
+            } else {
+                offset
+            };
+            (s[..i].trim(), offset)
+        }
+        None => (s, 0),
+    };
+    if !is_label(label) {
+        return Err(format!("Invalid value '{s}'"));
+    }
+    Ok(Value {
+        label: Some(label.to_string()),
+        offset,
+    })
+}
+
+fn parse_operand(s: &str) -> Result<Operand, String> {
+    let s = s.trim();
+    if let Some(value) = s.strip_prefix('#') {
+        Ok(Operand::Immediate(parse_value(value)?))
+    } else if let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let inner = inner.trim();
+        // Labels can start with "rb" too, like "rbuf".
+        let rb = inner.get(..2).filter(|p| {
+            p.eq_ignore_ascii_case("rb")
+                && !inner[2..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        });
+        match rb.map(|_| inner[2..].trim()) {
+            Some("") => Ok(Operand::Relative(parse_value("0")?)),
+            Some(offset) if offset.starts_with('+') => {
+                Ok(Operand::Relative(parse_value(&offset[1..])?))
+            }
+            Some(offset) if offset.starts_with('-') => {
+                let mut value = parse_value(&offset[1..])?;
+                if value.label.is_some() {
+                    return Err(format!("Invalid relative parameter '{s}'"));
+                }
+                value.offset = -value.offset;
+                Ok(Operand::Relative(value))
+            }
+            Some(_) => Err(format!("Invalid relative parameter '{s}'")),
+            None => Ok(Operand::Position(parse_value(inner)?)),
+        }
+    } else {
+        Ok(Operand::Immediate(parse_value(s)?))
+    }
+}
+
+// Parses a string literal at the start of `s`, returning its characters and the rest of `s`.
+fn parse_string(s: &str) -> Result<(Vec<Value>, &str), String> {
+    let mut values = Vec::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        let c = match c {
+            '"' => return Ok((values, &s[i + 1..])),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some(c @ ('\\' | '"' | '\'')) => c,
+                Some(c) => return Err(format!("Unknown escape '\\{c}'")),
+                None => break,
+            },
+            c => c,
+        };
+        values.push(Value {
+            label: None,
+            offset: i64::from(u32::from(c)),
+        });
+    }
+    Err("Unterminated string".to_string())
+}
+
+// Values separated by commas, strings included.
+fn parse_data(mut s: &str) -> Result<Vec<Value>, String> {
+    let mut values = Vec::new();
+    loop {
+        s = s.trim_start();
+        if s.starts_with('"') {
+            let (chars, rest) = parse_string(s)?;
+            values.extend(chars);
+            s = rest.trim_start();
+        } else {
+            let end = s.find(',').unwrap_or(s.len());
+            values.push(parse_value(&s[..end])?);
+            s = &s[end..];
+        }
+        match s.strip_prefix(',') {
+            Some(rest) => s = rest,
+            None if s.is_empty() => return Ok(values),
+            None => return Err(format!("Expected ',' before '{s}'")),
+        }
+    }
+}
+
+// Removes the comment, outside of strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+enum Prefix {
+    Label(String),
+    Address(usize),
+}
+
+// Splits the labels and addresses at the start of the line from the rest.
+fn split_prefixes(mut line: &str) -> (Vec<Prefix>, &str) {
+    let mut prefixes = Vec::new();
+    while let Some((name, rest)) = line.split_once(':') {
+        let name = name.trim();
+        if let Ok(addr) = name.parse() {
+            prefixes.push(Prefix::Address(addr));
+        } else if is_label(name) {
+            prefixes.push(Prefix::Label(name.to_string()));
+        } else {
+            break;
+        }
+        line = rest;
+    }
+    (prefixes, line.trim())
+}
+
+fn parse_statement(s: &str) -> Result<Statement, String> {
+    let (name, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+    if name.eq_ignore_ascii_case(".data") || name.eq_ignore_ascii_case("data") {
+        return parse_data(rest).map(Statement::Data);
+    }
+    let (opcode, count, writes) =
+        mnemonic(name).ok_or_else(|| format!("Unknown instruction '{name}'"))?;
+    // The written parameter can come after `->` instead of a comma.
+    let (read, written) = rest.split_once("->").unwrap_or((rest, ""));
+    let operands = [read, written]
+        .iter()
+        .filter(|part| !part.trim().is_empty())
+        .flat_map(|part| part.split(','))
+        .map(parse_operand)
+        .collect::<Result<Vec<_>, _>>()?;
+    if operands.len() != count {
+        return Err(format!(
+            "'{name}' expects {count} parameters, found {}",
+            operands.len()
+        ));
+    }
+    if writes && matches!(operands.last(), Some(Operand::Immediate(_))) {
+        return Err(format!("'{name}' cannot write to an immediate parameter"));
+    }
+    Ok(Statement::Instruction(opcode, operands))
+}
+
+fn resolve(value: &Value, labels: &HashMap<String, usize>) -> Result<i64, String> {
+    let base = match &value.label {
+        Some(label) => labels
+            .get(label)
+            .map(|addr| i64::try_from(*addr).unwrap())
+            .ok_or_else(|| format!("Unknown label '{label}'"))?,
+        None => 0,
+    };
+    Ok(base + value.offset)
+}
+
+fn encode(
+    opcode: i64,
+    operands: &[Operand],
+    labels: &HashMap<String, usize>,
+) -> Result<Vec<i64>, String> {
+    let params = operands
+        .iter()
+        .map(|operand| {
+            Ok(match operand {
+                Operand::Position(v) => {
+                    let addr = resolve(v, labels)?;
+                    Param::Position(
+                        usize::try_from(addr).map_err(|_| format!("Negative address {addr}"))?,
+                    )
+                }
+                Operand::Immediate(v) => Param::Immediate(resolve(v, labels)?),
+                Operand::Relative(v) => Param::Relative(resolve(v, labels)?),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let p = |i: usize| params[i];
+    let ins = match opcode {
+        1 => Instruction::Add(p(0), p(1), p(2)),
+        2 => Instruction::Mult(p(0), p(1), p(2)),
+        3 => Instruction::Input(p(0)),
+        4 => Instruction::Output(p(0)),
+        5 => Instruction::JumpIfTrue(p(0), p(1)),
+        6 => Instruction::JumpIfFalse(p(0), p(1)),
+        7 => Instruction::LessThan(p(0), p(1), p(2)),
+        8 => Instruction::Equal(p(0), p(1), p(2)),
+        9 => Instruction::ChangeRelativeBase(p(0)),
+        _ => Instruction::Halt,
+    };
+    Ok(ins.encode())
+}
+
+/// Assembles the program into the values of the Intcode memory.
+///
+/// # Errors
+///
+/// Returns an error for the first invalid line: syntax error, unknown instruction or label,
+/// label defined twice, writing to an immediate parameter, or address prefix not matching.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssemblyError> {
+    // First pass: the address of each label.
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut addr = 0;
+    for (i, line) in source.lines().enumerate() {
+        let error = |message| AssemblyError {
+            line: i + 1,
+            message,
+        };
+        let (prefixes, rest) = split_prefixes(strip_comment(line));
+        for prefix in prefixes {
+            match prefix {
+                Prefix::Label(label) => {
+                    if labels.insert(label.clone(), addr).is_some() {
+                        return Err(error(format!("Label '{label}' defined twice")));
+                    }
+                }
+                Prefix::Address(a) if a != addr => {
+                    return Err(error(format!("Address {a} expected, but it's {addr}")));
+                }
+                Prefix::Address(_) => {}
+            }
+        }
+        if !rest.is_empty() {
+            let statement = parse_statement(rest).map_err(error)?;
+            addr += statement.length();
+            statements.push((i + 1, statement));
+        }
+    }
+
+    // Second pass: the values.
+    let mut values = Vec::new();
+    for (line, statement) in statements {
+        let error = |message| AssemblyError { line, message };
+        match statement {
+            Statement::Instruction(opcode, operands) => {
+                values.extend(encode(opcode, &operands, &labels).map_err(error)?);
+            }
+            Statement::Data(data) => {
+                for value in &data {
+                    values.push(resolve(value, &labels).map_err(error)?);
+                }
+            }
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassemble;
+
+    fn build(code: &str) -> Vec<i64> {
+        code.split(',').map(|v| v.parse().unwrap()).collect()
+    }
+
+    fn error(source: &str) -> String {
+        assemble(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_assemble() {
+        let source = r"
+            in [9]          ; read
+            eq [9], [10] -> [9]
+            out [9]
+            hlt
+            .data -1, 8";
+        assert_eq!(assemble(source), Ok(build("3,9,8,9,10,9,4,9,99,-1,8")));
+    }
+
+    #[test]
+    fn test_modes() {
+        assert_eq!(assemble("ADD #1, [2] -> [rb-3]"), Ok(build("20101,1,2,-3")));
+        assert_eq!(assemble("mul [rb], #-2, [rb+4]"), Ok(build("21202,0,-2,4")));
+        assert_eq!(assemble("arb 5"), Ok(build("109,5")));
+    }
+
+    #[test]
+    fn test_labels() {
+        let source = r"
+            start: jt #1, end
+            value: .data 7
+            end:   out [value]
+                   jf #0, start
+                   out value+1";
+        assert_eq!(assemble(source), Ok(build("1105,1,4,7,4,3,1106,0,0,104,4")));
+        assert_eq!(
+            assemble("out [rbuf]\nhlt\nrbuf: .data 5"),
+            Ok(build("4,3,99,5"))
+        );
+        assert_eq!(assemble("out [RB_1]\nRB_1: hlt"), Ok(build("4,2,99")));
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(
+            assemble(r#"msg: .data "Hi; \"x\"\n", 0 ; comment"#),
+            Ok(vec![72, 105, 59, 32, 34, 120, 34, 10, 0])
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(error("nop"), "Line 1: Unknown instruction 'nop'");
+        assert_eq!(
+            error("\nadd #1, #2"),
+            "Line 2: 'add' expects 3 parameters, found 2"
+        );
+        assert_eq!(
+            error("in #5"),
+            "Line 1: 'in' cannot write to an immediate parameter"
+        );
+        assert_eq!(error("jt #1, nowhere"), "Line 1: Unknown label 'nowhere'");
+        assert_eq!(error("a: hlt\na: hlt"), "Line 2: Label 'a' defined twice");
+        assert_eq!(error("out [-1]"), "Line 1: Negative address -1");
+        assert_eq!(error(".data \"abc"), "Line 1: Unterminated string");
+        assert_eq!(
+            error("out [rb*2]"),
+            "Line 1: Invalid relative parameter '[rb*2]'"
+        );
+        assert_eq!(
+            error("hlt\n3: hlt"),
+            "Line 2: Address 3 expected, but it's 1"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for code in [
+            "3,9,8,9,10,9,4,9,99,-1,8",
+            "3,12,1006,12,15,21101,0,7,1,1105,1,17,-1,0,1,99,0,204,-3,99",
+            "109,30,21101,9,0,0,1105,1,12,104,42,99,2105,1,0,0",
+            "4,3,99,72,101,108,108,111,10,0,5",
+            "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99",
+        ] {
+            let mem = build(code);
+            assert_eq!(assemble(&disassemble(&mem)), Ok(mem), "{code}");
+        }
+    }
+
+    #[test]
+    fn test_round_trip_non_canonical() {
+        // Halt with a mode for a parameter it doesn't have.
+        let mem = build("104,1,10099");
+        assert_eq!(assemble(&disassemble(&mem)), Ok(mem));
+    }
+}
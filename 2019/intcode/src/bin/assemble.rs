@@ -0,0 +1,17 @@
+use intcode::assemble;
+use itertools::Itertools;
+
+// Assembles the program from the file passed as argument, printing the Intcode.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let program = &args[1];
+
+    let source = std::fs::read_to_string(program).expect("Unable to read program file");
+    match assemble(&source) {
+        Ok(values) => println!("{}", values.iter().join(",")),
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
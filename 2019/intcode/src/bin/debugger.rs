@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use intcode::{Access, Debugger, InputOutput, IntcodeComputer, Snapshot, Stop};
+
+const HELP: &str = "Commands:
+  s [n]              Step n instructions (default 1)
+  back [n]           Step back n instructions
+  c                  Continue until a breakpoint, watchpoint, input or halt
+  b <addr>           Add a breakpoint
+  db <addr>          Delete a breakpoint
+  w <addr> [r|w|rw]  Add a watchpoint (default rw)
+  dw <addr>          Delete a watchpoint
+  l                  List breakpoints and watchpoints
+  i <v> [v ...]      Add input values
+  m <addr> [n]       Show n memory values (default 1)
+  set <addr> <v>     Write a value to memory
+  t [n]              Show the last n traced instructions (default 10)
+  save <name>        Save a snapshot of the state
+  load <name>        Restore a snapshot
+  h                  This help
+  q                  Quit";
+
+fn print_state(debugger: &Debugger<InputOutput>) {
+    println!(
+        "ip={} rb={}  {}",
+        debugger.ip(),
+        debugger.relative_base(),
+        debugger.current_instruction()
+    );
+}
+
+fn print_stop(stop: &Stop) {
+    match stop {
+        Stop::Stepped => {}
+        Stop::Breakpoint(ip) => println!("Breakpoint at {ip}"),
+        Stop::Watchpoint { ip, addr, access } => {
+            println!("Watchpoint: {access:?} of [{addr}] by instruction at {ip}");
+        }
+        Stop::WaitingForInput => println!("Waiting for input"),
+        Stop::Halted => println!("Halted"),
+        Stop::Error(e) => println!("Error: {e}"),
+    }
+}
+
+fn parse<T: std::str::FromStr>(arg: Option<&&str>, default: Option<T>) -> Option<T> {
+    arg.map_or(default, |a| a.parse().ok())
+}
+
+fn parse_access(arg: Option<&str>) -> Option<Access> {
+    match arg {
+        Some("r") => Some(Access::Read),
+        Some("w") => Some(Access::Write),
+        None | Some("rw") => Some(Access::ReadWrite),
+        _ => None,
+    }
+}
+
+// Executes a command of the user. Returns false if the command is invalid.
+fn run_command(
+    debugger: &mut Debugger<InputOutput>,
+    snapshots: &mut HashMap<String, Snapshot<InputOutput>>,
+    cmd: &str,
+    args: &[&str],
+) -> bool {
+    match (cmd, args.len()) {
+        ("s", 0 | 1) => {
+            if let Some(n) = parse(args.first(), Some(1)) {
+                for _ in 0..n {
+                    let stop = debugger.step();
+                    print_stop(&stop);
+                    if stop != Stop::Stepped {
+                        break;
+                    }
+                }
+            }
+        }
+        ("back", 0 | 1) => {
+            if let Some(n) = parse(args.first(), Some(1)) {
+                if !(0..n).all(|_| debugger.step_back()) {
+                    println!("No more history");
+                }
+            }
+        }
+        ("c", 0) => print_stop(&debugger.run()),
+        ("b", 1) => {
+            if let Some(addr) = parse(args.first(), None) {
+                debugger.add_breakpoint(addr);
+            }
+        }
+        ("db", 1) => {
+            if let Some(addr) = parse(args.first(), None) {
+                debugger.remove_breakpoint(addr);
+            }
+        }
+        ("w", 1 | 2) => {
+            if let (Some(addr), Some(access)) = (
+                parse(args.first(), None),
+                parse_access(args.get(1).copied()),
+            ) {
+                debugger.add_watchpoint(addr, access);
+            }
+        }
+        ("dw", 1) => {
+            if let Some(addr) = parse(args.first(), None) {
+                debugger.remove_watchpoint(addr);
+            }
+        }
+        ("l", 0) => {
+            println!("Breakpoints: {:?}", debugger.breakpoints());
+            println!("Watchpoints: {:?}", debugger.watchpoints());
+        }
+        ("i", _) => {
+            for v in args.iter().filter_map(|a| a.parse().ok()) {
+                debugger.computer.io.add_input(v);
+            }
+        }
+        ("m", 1 | 2) => {
+            if let (Some(addr), Some(n)) = (
+                parse::<usize>(args.first(), None),
+                parse(args.get(1), Some(1)),
+            ) {
+                for (a, v) in debugger.memory().iter().enumerate().skip(addr).take(n) {
+                    println!("[{a}] = {v}");
+                }
+            }
+        }
+        ("set", 2) => {
+            if let (Some(addr), Some(v)) = (parse(args.first(), None), parse(args.get(1), None)) {
+                debugger.computer.write_mem(addr, v);
+            }
+        }
+        ("t", 0 | 1) => {
+            if let Some(n) = parse(args.first(), Some(10)) {
+                let trace = debugger.trace();
+                for e in trace.iter().skip(trace.len().saturating_sub(n)) {
+                    println!("{:>6} rb={:<6} {}", e.ip, e.relative_base, e.instruction);
+                }
+            }
+        }
+        ("save", 1) => {
+            snapshots.insert(args[0].to_string(), debugger.snapshot());
+        }
+        ("load", 1) => {
+            if let Some(snapshot) = snapshots.get(args[0]) {
+                debugger.restore(snapshot);
+            } else {
+                println!("No snapshot {}", args[0]);
+            }
+        }
+        ("h", 0) => println!("{HELP}"),
+        _ => return false,
+    }
+    true
+}
+
+// Interactive debugger for the Intcode program passed as argument.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let program = &args[1];
+
+    let intcode = std::fs::read_to_string(program).expect("Unable to read program file");
+    let mut debugger = Debugger::new(IntcodeComputer::build(intcode.trim()));
+    let mut snapshots: HashMap<String, Snapshot<InputOutput>> = HashMap::new();
+
+    println!("{HELP}");
+    print_state(&debugger);
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let Some(cmd) = parts.first() else {
+            continue;
+        };
+        let args = &parts[1..];
+        match (*cmd, args.len()) {
+            ("q", 0) => break,
+            _ => {
+                if !run_command(&mut debugger, &mut snapshots, cmd, args) {
+                    println!("Invalid command, h for help");
+                }
+            }
+        }
+
+        let output: Vec<String> = std::iter::from_fn(|| debugger.computer.io.get_output())
+            .map(|v| v.to_string())
+            .collect();
+        if !output.is_empty() {
+            println!("Output: {}", output.join(","));
+        }
+        print_state(&debugger);
+    }
+}
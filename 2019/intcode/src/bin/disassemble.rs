@@ -0,0 +1,12 @@
+use intcode::IntcodeComputer;
+
+// Disassembles the Intcode from the file passed as argument.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let program = &args[1];
+
+    let intcode = std::fs::read_to_string(program).expect("Unable to read program file");
+    let computer = IntcodeComputer::build(intcode.trim());
+
+    print!("{}", computer.dump_memory_annotated());
+}
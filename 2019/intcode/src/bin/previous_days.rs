@@ -1,4 +1,6 @@
-/// All previous days tasks and tests.
+//! All previous days tasks, on the real inputs.
+//!
+//! The example programs of these days are tested in the `previous_days` module of the library.
 
 fn get_input_results(day: &str) -> (String, String, String) {
     let input = std::fs::read_to_string(format!("../{day}/resources/input"))
@@ -19,24 +21,6 @@ mod day02 {
 
     use crate::get_input_results;
 
-    #[test]
-    fn test_exec() {
-        fn exec(code: &str) -> String {
-            let mut computer = IntcodeComputer::build(code);
-            computer.exec();
-            computer.dump_memory()
-        }
-
-        assert_eq!(
-            exec("1,9,10,3,2,3,11,0,99,30,40,50"),
-            "3500,9,10,70,2,3,11,0,99,30,40,50"
-        );
-        assert_eq!(exec("1,0,0,0,99"), "2,0,0,0,99");
-        assert_eq!(exec("2,3,0,3,99"), "2,3,0,6,99");
-        assert_eq!(exec("2,4,4,5,99,0"), "2,4,4,5,99,9801");
-        assert_eq!(exec("1,1,1,4,99,5,6,0,99"), "30,1,1,4,2,5,6,0,99");
-    }
-
     fn run_noun_verb(computer: &IntcodeComputer, noun: i64, verb: i64) -> i64 {
         let mut computer = computer.clone();
         computer.write_mem(1, noun);
@@ -75,46 +59,6 @@ mod day05 {
 
     use crate::get_input_results;
 
-    #[cfg(test)]
-    fn run_io(code: &str, input: i64) -> i64 {
-        let mut computer = IntcodeComputer::build(code);
-        computer.run(input)
-    }
-
-    #[test]
-    fn test_cmp() {
-        let c = "3,9,8,9,10,9,4,9,99,-1,8";
-        assert_eq!(run_io(c, 8), 1);
-        assert_eq!(run_io(c, 3), 0);
-        let c = "3,9,7,9,10,9,4,9,99,-1,8";
-        assert_eq!(run_io(c, 3), 1);
-        assert_eq!(run_io(c, 9), 0);
-        let c = "3,3,1108,-1,8,3,4,3,99";
-        assert_eq!(run_io(c, 8), 1);
-        assert_eq!(run_io(c, 3), 0);
-        let c = "3,3,1107,-1,8,3,4,3,99";
-        assert_eq!(run_io(c, 3), 1);
-        assert_eq!(run_io(c, 9), 0);
-    }
-
-    #[test]
-    fn test_jump() {
-        let c = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
-        assert_eq!(run_io(c, 0), 0);
-        assert_eq!(run_io(c, 4), 1);
-        let c = "3,3,1105,-1,9,1101,0,0,12,4,12,99,1";
-        assert_eq!(run_io(c, 0), 0);
-        assert_eq!(run_io(c, 4), 1);
-    }
-
-    #[test]
-    fn test_larger_program() {
-        let c = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
-        assert_eq!(run_io(c, 1), 999);
-        assert_eq!(run_io(c, 8), 1000);
-        assert_eq!(run_io(c, 45), 1001);
-    }
-
     pub(crate) fn real_input() {
         let (input, result1, result2) = get_input_results("day05");
         let computer = IntcodeComputer::build(&input);
@@ -199,31 +143,6 @@ mod day07 {
             .unwrap()
     }
 
-    #[test]
-    fn test_max_thruster_signal() {
-        let computer = IntcodeComputer::build("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0");
-        assert_eq!(max_thruster_signal(&computer), 43210);
-
-        let computer = IntcodeComputer::build(
-            "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
-        );
-        assert_eq!(max_thruster_signal(&computer), 54321);
-
-        let computer = IntcodeComputer::build("3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0");
-        assert_eq!(max_thruster_signal(&computer), 65210);
-    }
-
-    #[test]
-    fn test_max_thruster_signal_with_feedback() {
-        let computer = IntcodeComputer::build(
-            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
-        );
-        assert_eq!(max_thruster_signal_with_feedback(&computer), 139629729);
-
-        let computer = IntcodeComputer::build("3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10");
-        assert_eq!(max_thruster_signal_with_feedback(&computer), 18216);
-    }
-
     pub(crate) fn real_input() {
         let (input, result1, result2) = get_input_results("day07");
         let computer = IntcodeComputer::build(&input);
@@ -237,32 +156,10 @@ mod day07 {
 }
 
 mod day09 {
-    use intcode::IntcodeComputer;
+    use intcode::{FastIntcodeComputer, IntcodeComputer};
 
     use crate::get_input_results;
 
-    #[test]
-    fn test_large_numbers() {
-        let mut computer = IntcodeComputer::build("1102,34915192,34915192,7,4,7,99,0");
-        computer.exec();
-        assert_eq!(computer.io.get_output().unwrap(), 1219070632396864);
-
-        let mut computer = IntcodeComputer::build("104,1125899906842624,99");
-        computer.exec();
-        assert_eq!(computer.io.get_output().unwrap(), 1125899906842624);
-    }
-
-    #[test]
-    fn test_relative_mode() {
-        let mut computer =
-            IntcodeComputer::build("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
-        computer.exec();
-        assert_eq!(
-            computer.io.dump_output(),
-            "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99"
-        );
-    }
-
     fn get_boost_keycode(computer: &IntcodeComputer) -> i64 {
         let mut computer = computer.clone();
         computer.run(1)
@@ -282,6 +179,11 @@ mod day09 {
 
         let part2 = get_distress_signal_coords(&computer);
         assert_eq!(part2.to_string(), result2.trim());
+
+        // The fast computer must give the same results.
+        let fast = FastIntcodeComputer::from(computer);
+        assert_eq!(fast.clone().run(1), part1);
+        assert_eq!(fast.clone().run(2), part2);
     }
 }
 
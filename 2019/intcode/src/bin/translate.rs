@@ -0,0 +1,27 @@
+use intcode::translate::{check_writes, to_c, to_rust_program};
+
+// Translates the Intcode from the file passed as argument into C, or into Rust with --rust,
+// printing the code. The writes into the code found are reported on stderr.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let rust = args.iter().any(|arg| arg == "--rust");
+    let program = args
+        .iter()
+        .skip(1)
+        .find(|arg| !arg.starts_with("--"))
+        .expect("Missing program file");
+
+    let intcode = std::fs::read_to_string(program).expect("Unable to read program file");
+    let mem: Vec<i64> = intcode
+        .trim()
+        .split(',')
+        .map(|v| v.parse().expect("Invalid program"))
+        .collect();
+
+    eprint!("{}", check_writes(&mem));
+    if rust {
+        print!("{}", to_rust_program(&mem));
+    } else {
+        print!("{}", to_c(&mem));
+    }
+}
@@ -0,0 +1,372 @@
+//! Input/Output of the Intcode computer.
+//!
+use itertools::Itertools;
+use std::{
+    collections::VecDeque,
+    io::Read,
+    sync::mpsc::{Receiver, Sender},
+};
+
+/// Trait that abstracts Input/Output support in the computer.
+pub trait Bus {
+    /// Called by the input instruction.
+    /// Returns None if there is no input available, in which case the computer interrupts
+    /// its execution, and will retry reading on next `exec()`.
+    fn read(&mut self) -> Option<i64>;
+
+    /// Called by the output instruction.
+    fn write(&mut self, v: i64);
+}
+
+/// Simple vector based implementation of Bus trait.
+#[derive(Debug, Clone, Default)]
+pub struct InputOutput {
+    input: VecDeque<i64>,
+    output: VecDeque<i64>,
+}
+
+impl InputOutput {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Multiple inputs are added in the order the computer reads them (first add the first one the computer reads).
+    pub fn add_input(&mut self, input: i64) {
+        self.input.push_back(input);
+    }
+
+    pub fn extend_input(&mut self, input: &[i64]) {
+        self.input.extend(input);
+    }
+
+    // Returns first the oldest output of the computer.
+    pub fn get_output(&mut self) -> Option<i64> {
+        self.output.pop_front()
+    }
+
+    #[must_use]
+    pub fn dump_input(&self) -> String {
+        self.input.iter().join(",")
+    }
+
+    #[must_use]
+    pub fn dump_output(&self) -> String {
+        self.output.iter().join(",")
+    }
+}
+
+impl Bus for InputOutput {
+    fn read(&mut self) -> Option<i64> {
+        self.input.pop_front()
+    }
+
+    fn write(&mut self, v: i64) {
+        self.output.push_back(v);
+    }
+}
+
+/// Stdin/stdout based implementation of Bus trait.
+#[derive(Debug, Default)]
+pub struct ASCIIInputOutput {}
+
+impl ASCIIInputOutput {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Bus for ASCIIInputOutput {
+    fn read(&mut self) -> Option<i64> {
+        let mut buf = [0; 1];
+        std::io::stdin()
+            .read_exact(&mut buf)
+            .ok()
+            .map(|()| i64::from(buf[0]))
+    }
+
+    fn write(&mut self, v: i64) {
+        // Convert the value to ASCII.
+        let c = char::from_u32(u32::try_from(v).unwrap()).unwrap();
+        print!("{c}");
+    }
+}
+
+/// Bus calling closures for input and output.
+pub struct FnBus<R, W>
+where
+    R: FnMut() -> Option<i64>,
+    W: FnMut(i64),
+{
+    read_fn: R,
+    write_fn: W,
+}
+
+impl<R, W> FnBus<R, W>
+where
+    R: FnMut() -> Option<i64>,
+    W: FnMut(i64),
+{
+    pub fn new(read_fn: R, write_fn: W) -> Self {
+        Self { read_fn, write_fn }
+    }
+}
+
+impl<R, W> Bus for FnBus<R, W>
+where
+    R: FnMut() -> Option<i64>,
+    W: FnMut(i64),
+{
+    fn read(&mut self) -> Option<i64> {
+        (self.read_fn)()
+    }
+
+    fn write(&mut self, v: i64) {
+        (self.write_fn)(v);
+    }
+}
+
+/// Bus using `std::sync::mpsc` channels, to run computers in separate threads.
+///
+/// Reading blocks until a value is received. If the sender is gone, there is no input.
+/// Writing ignores the value if the receiver is gone.
+#[derive(Debug)]
+pub struct ChannelBus {
+    receiver: Receiver<i64>,
+    sender: Sender<i64>,
+}
+
+impl ChannelBus {
+    #[must_use]
+    pub fn new(receiver: Receiver<i64>, sender: Sender<i64>) -> Self {
+        Self { receiver, sender }
+    }
+}
+
+impl Bus for ChannelBus {
+    fn read(&mut self) -> Option<i64> {
+        self.receiver.recv().ok()
+    }
+
+    fn write(&mut self, v: i64) {
+        let _ = self.sender.send(v);
+    }
+}
+
+/// Bus for programs using the Aft Scaffolding Control and Information Interface (ASCII).
+///
+/// Input is given as strings, output is collected as lines.
+/// Values that aren't ASCII characters are kept separately.
+#[derive(Debug, Clone, Default)]
+pub struct ASCIIBus {
+    input: VecDeque<i64>,
+    lines: VecDeque<String>,
+    current_line: String,
+    pub non_ascii_output: Vec<i64>,
+}
+
+impl ASCIIBus {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_str(&mut self, s: &str) {
+        self.input.extend(s.bytes().map(i64::from));
+    }
+
+    /// Adds the string to the input, followed by a new line.
+    pub fn add_line(&mut self, line: &str) {
+        self.add_str(line);
+        self.input.push_back(10);
+    }
+
+    /// Returns the oldest complete line output by the computer, without the new line character.
+    pub fn get_line(&mut self) -> Option<String> {
+        self.lines.pop_front()
+    }
+
+    /// Returns all the text output so far, including the last line if it isn't complete.
+    pub fn take_output(&mut self) -> String {
+        let mut text: String = self.lines.drain(..).map(|l| l + "\n").collect();
+        text.push_str(&std::mem::take(&mut self.current_line));
+        text
+    }
+}
+
+impl Bus for ASCIIBus {
+    fn read(&mut self) -> Option<i64> {
+        self.input.pop_front()
+    }
+
+    fn write(&mut self, v: i64) {
+        match u8::try_from(v) {
+            Ok(10) => self.lines.push_back(std::mem::take(&mut self.current_line)),
+            Ok(c) if c.is_ascii() => self.current_line.push(char::from(c)),
+            _ => self.non_ascii_output.push(v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Input(i64),
+    Output(i64),
+}
+
+/// Bus that records all the I/O going through another bus.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder<B: Bus> {
+    pub bus: B,
+    transcript: Vec<Event>,
+}
+
+impl<B: Bus> Recorder<B> {
+    pub fn new(bus: B) -> Self {
+        Self {
+            bus,
+            transcript: Vec::new(),
+        }
+    }
+
+    /// All the values read and written, in order.
+    #[must_use]
+    pub fn transcript(&self) -> &[Event] {
+        &self.transcript
+    }
+}
+
+impl<B: Bus> Bus for Recorder<B> {
+    fn read(&mut self) -> Option<i64> {
+        let v = self.bus.read();
+        if let Some(v) = v {
+            self.transcript.push(Event::Input(v));
+        }
+        v
+    }
+
+    fn write(&mut self, v: i64) {
+        self.transcript.push(Event::Output(v));
+        self.bus.write(v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc::channel, thread::spawn};
+
+    use super::*;
+    use crate::IntcodeComputer;
+
+    // Reads one value and outputs it.
+    const ECHO: &str = "3,0,4,0,99";
+    // Reads values and outputs them, until reading 0.
+    const ECHO_UNTIL_ZERO: &str = "3,20,4,20,1005,20,0,99";
+
+    #[test]
+    fn test_fn_bus() {
+        let mut input = vec![3, 2, 1, 0];
+        let mut output = Vec::new();
+        let bus = FnBus::new(|| input.pop(), |v| output.push(v));
+        let mut computer = IntcodeComputer::build_with_bus(ECHO_UNTIL_ZERO, bus);
+        computer.exec();
+        assert!(computer.is_halted());
+        assert_eq!(output, [0]);
+
+        let mut input = vec![0, 1, 2, 3];
+        let bus = FnBus::new(|| input.pop(), |v| output.push(v));
+        let mut computer = IntcodeComputer::build_with_bus(ECHO_UNTIL_ZERO, bus);
+        computer.exec();
+        assert_eq!(output, [0, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_channel_bus() {
+        let (in_sender, in_receiver) = channel();
+        let (out_sender, out_receiver) = channel();
+        let handle = spawn(move || {
+            let bus = ChannelBus::new(in_receiver, out_sender);
+            let mut computer = IntcodeComputer::build_with_bus(ECHO_UNTIL_ZERO, bus);
+            computer.exec();
+            computer.is_halted()
+        });
+        for i in [5, 6, 7] {
+            in_sender.send(i).unwrap();
+            assert_eq!(out_receiver.recv().unwrap(), i);
+        }
+        in_sender.send(0).unwrap();
+        assert_eq!(out_receiver.recv().unwrap(), 0);
+        assert!(handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_channel_bus_sender_dropped() {
+        let (in_sender, in_receiver) = channel::<i64>();
+        let (out_sender, _out_receiver) = channel();
+        drop(in_sender);
+        let bus = ChannelBus::new(in_receiver, out_sender);
+        let mut computer = IntcodeComputer::build_with_bus(ECHO, bus);
+        computer.exec();
+        assert!(!computer.is_halted());
+    }
+
+    #[test]
+    fn test_ascii_bus() {
+        let mut bus = ASCIIBus::new();
+        bus.add_line("Hi");
+        bus.add_str("!");
+        bus.write(i64::from(b'a'));
+        bus.write(10);
+        bus.write(i64::from(b'b'));
+        bus.write(12345);
+
+        let input: Vec<i64> = std::iter::from_fn(|| bus.read()).collect();
+        assert_eq!(input, [72, 105, 10, 33]);
+        assert_eq!(bus.get_line(), Some("a".to_string()));
+        assert_eq!(bus.get_line(), None);
+        assert_eq!(bus.take_output(), "b");
+        assert_eq!(bus.non_ascii_output, [12345]);
+    }
+
+    #[test]
+    fn test_ascii_bus_computer() {
+        let mut bus = ASCIIBus::new();
+        bus.add_line("ok");
+        let mut computer = IntcodeComputer::build_with_bus(ECHO_UNTIL_ZERO, bus);
+        computer.exec();
+        assert!(!computer.is_halted());
+        assert_eq!(computer.io.take_output(), "ok\n");
+    }
+
+    #[test]
+    fn test_recorder() {
+        let mut io = InputOutput::new();
+        io.extend_input(&[4, 0]);
+        let mut computer = IntcodeComputer::build_with_bus(ECHO_UNTIL_ZERO, Recorder::new(io));
+        computer.exec();
+        assert_eq!(
+            computer.io.transcript(),
+            [
+                Event::Input(4),
+                Event::Output(4),
+                Event::Input(0),
+                Event::Output(0)
+            ]
+        );
+        assert_eq!(computer.io.bus.dump_output(), "4,0");
+    }
+
+    #[test]
+    fn test_with_bus() {
+        let mut computer = IntcodeComputer::build(ECHO_UNTIL_ZERO);
+        computer.io.add_input(8);
+        computer.exec();
+        assert_eq!(computer.io.get_output(), Some(8));
+
+        let mut computer = computer.with_bus(ASCIIBus::new());
+        computer.io.add_str("A");
+        computer.exec();
+        assert_eq!(computer.io.take_output(), "A");
+    }
+}
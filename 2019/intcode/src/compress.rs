@@ -0,0 +1,165 @@
+//! Compression of a sequence into a main routine calling a few functions,
+//! like the movement functions of the vacuum robot of day 17.
+//!
+//! Routines and functions are sent to ASCII programs as their tokens separated by commas,
+//! and each of them must fit in a maximum number of characters.
+use std::fmt::Display;
+
+/// A main routine, made of indexes into the functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compression<T> {
+    pub main: Vec<usize>,
+    pub functions: Vec<Vec<T>>,
+}
+
+impl<T: Clone> Compression<T> {
+    /// The main routine as a string, naming the functions A, B, C, etc.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there are more than 26 functions.
+    #[must_use]
+    pub fn main_str(&self) -> String {
+        self.main
+            .iter()
+            .map(|i| char::from(b'A' + u8::try_from(*i).unwrap()).to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The original sequence.
+    #[must_use]
+    pub fn expand(&self) -> Vec<T> {
+        self.main
+            .iter()
+            .flat_map(|i| self.functions[*i].iter().cloned())
+            .collect()
+    }
+}
+
+/// Length of the tokens once separated by commas.
+#[must_use]
+pub fn encoded_len<T: Display>(tokens: &[T]) -> usize {
+    tokens.iter().map(|t| t.to_string().len()).sum::<usize>() + tokens.len().saturating_sub(1)
+}
+
+// Depth first search, completing the main routine from `pos` in the tokens.
+fn search<'a, T: Display + PartialEq>(
+    tokens: &'a [T],
+    pos: usize,
+    max_functions: usize,
+    max_len: usize,
+    functions: &mut Vec<&'a [T]>,
+    main: &mut Vec<usize>,
+) -> bool {
+    if pos == tokens.len() {
+        return true;
+    }
+    // Adding a call to the main routine adds the function name and a comma.
+    if 2 * main.len() + 1 > max_len {
+        return false;
+    }
+
+    let rest = &tokens[pos..];
+    for i in 0..functions.len() {
+        let function = functions[i];
+        if rest.starts_with(function) {
+            main.push(i);
+            if search(
+                tokens,
+                pos + function.len(),
+                max_functions,
+                max_len,
+                functions,
+                main,
+            ) {
+                return true;
+            }
+            main.pop();
+        }
+    }
+
+    if functions.len() < max_functions {
+        // Longest functions first, they make shorter main routines.
+        let max_end = (pos + 1..=tokens.len())
+            .take_while(|end| encoded_len(&tokens[pos..*end]) <= max_len)
+            .last()
+            .unwrap_or(pos);
+        for end in (pos + 1..=max_end).rev() {
+            let function = &tokens[pos..end];
+            main.push(functions.len());
+            functions.push(function);
+            if search(tokens, end, max_functions, max_len, functions, main) {
+                return true;
+            }
+            functions.pop();
+            main.pop();
+        }
+    }
+    false
+}
+
+/// Splits the tokens into a main routine calling at most `max_functions` functions,
+/// with the main routine and each function fitting in `max_len` characters.
+///
+/// Returns None if it's not possible.
+pub fn compress<T>(tokens: &[T], max_functions: usize, max_len: usize) -> Option<Compression<T>>
+where
+    T: Display + PartialEq + Clone,
+{
+    let mut functions = Vec::new();
+    let mut main = Vec::new();
+    search(tokens, 0, max_functions, max_len, &mut functions, &mut main).then(|| Compression {
+        main,
+        functions: functions.into_iter().map(<[T]>::to_vec).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(s: &str) -> Vec<String> {
+        s.split(' ').map(ToString::to_string).collect()
+    }
+
+    fn check(path: &[String], max_functions: usize, max_len: usize) -> Compression<String> {
+        let compression = compress(path, max_functions, max_len).unwrap();
+        assert_eq!(compression.expand(), path);
+        assert!(compression.functions.len() <= max_functions);
+        assert!(compression.main_str().len() <= max_len);
+        for f in &compression.functions {
+            assert!(encoded_len(f) <= max_len);
+        }
+        compression
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        assert_eq!(encoded_len(&tokens("R,8 L,10 R,4")), 12);
+        assert_eq!(encoded_len::<String>(&[]), 0);
+    }
+
+    #[test]
+    fn test_compress() {
+        // Example of day 17.
+        let path = tokens("R,8 R,8 R,4 R,4 R,8 L,6 L,2 R,4 R,4 R,8 R,8 R,8 L,6 L,2");
+        check(&path, 3, 20);
+    }
+
+    #[test]
+    fn test_compress_single_function() {
+        let path = tokens("L,4 R,6 L,4 R,6 L,4 R,6");
+        let compression = check(&path, 1, 20);
+        assert_eq!(compression.main_str(), "A,A,A");
+    }
+
+    #[test]
+    fn test_compress_impossible() {
+        // 16 different tokens, but 3 functions can hold only 4 of them each.
+        let path: Vec<String> = (10..26).map(|n| format!("L,{n}")).collect();
+        assert_eq!(compress(&path, 3, 20), None);
+        // Fine with a longer limit.
+        check(&path, 3, 40);
+    }
+}
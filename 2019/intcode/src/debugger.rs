@@ -0,0 +1,395 @@
+//! Debugger for Intcode programs.
+//!
+//! Supports stepping, breakpoints, memory watchpoints, an instruction trace,
+//! and snapshots of the full computer state for stepping back.
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    disassembler::instruction_str,
+    Bus, ExecState, Instruction, IntcodeBase, IntcodeComputer, IntcodeError, Param,
+};
+
+/// Kind of memory access that triggers a watchpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    fn matches(self, access: Access) -> bool {
+        self == Access::ReadWrite || self == access
+    }
+}
+
+/// Why the debugger stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stop {
+    /// A single instruction was executed.
+    Stepped,
+    /// The instruction pointer reached a breakpoint. The instruction there isn't executed yet.
+    Breakpoint(usize),
+    /// The instruction at `ip` accessed a watched address.
+    Watchpoint {
+        ip: usize,
+        addr: usize,
+        access: Access,
+    },
+    WaitingForInput,
+    Halted,
+    Error(IntcodeError),
+}
+
+/// One executed instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub ip: usize,
+    pub relative_base: i64,
+    pub instruction: String,
+}
+
+/// Full state of the computer, memory, registers and I/O.
+#[derive(Debug, Clone)]
+pub struct Snapshot<B: Bus + Clone>(IntcodeComputer<B>);
+
+impl From<ExecState> for Stop {
+    fn from(state: ExecState) -> Self {
+        match state {
+            ExecState::Running => Stop::Stepped,
+            ExecState::Halted => Stop::Halted,
+            ExecState::NeedsInput => Stop::WaitingForInput,
+            ExecState::Error(e) => Stop::Error(e),
+        }
+    }
+}
+
+// Memory addresses the instruction reads from and writes to.
+fn accesses(base: &IntcodeBase, ins: Instruction) -> (Vec<usize>, Option<usize>) {
+    let addr = |p: Param| match p {
+        Param::Position(addr) => Some(addr),
+        Param::Immediate(_) => None,
+        Param::Relative(offset) => usize::try_from(base.relative_base + offset).ok(),
+    };
+    let reads = |params: &[Param]| params.iter().filter_map(|p| addr(*p)).collect();
+    match ins {
+        Instruction::Add(a, b, c)
+        | Instruction::Mult(a, b, c)
+        | Instruction::LessThan(a, b, c)
+        | Instruction::Equal(a, b, c) => (reads(&[a, b]), addr(c)),
+        Instruction::Input(a) => (Vec::new(), addr(a)),
+        Instruction::Output(a) | Instruction::ChangeRelativeBase(a) => (reads(&[a]), None),
+        Instruction::JumpIfTrue(a, b) | Instruction::JumpIfFalse(a, b) => (reads(&[a, b]), None),
+        Instruction::Halt => (Vec::new(), None),
+    }
+}
+
+/// Debugger controlling an Intcode computer.
+pub struct Debugger<B: Bus + Clone> {
+    pub computer: IntcodeComputer<B>,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeMap<usize, Access>,
+    trace: VecDeque<TraceEntry>,
+    trace_limit: usize,
+    // States before each of the last steps, to step back.
+    history: VecDeque<Snapshot<B>>,
+    history_limit: usize,
+}
+
+impl<B: Bus + Clone> Debugger<B> {
+    /// Creates a debugger, keeping by default the last 1000 instructions in the trace
+    /// and the states of the last 100 steps.
+    #[must_use]
+    pub fn new(computer: IntcodeComputer<B>) -> Self {
+        Self {
+            computer,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeMap::new(),
+            trace: VecDeque::new(),
+            trace_limit: 1000,
+            history: VecDeque::new(),
+            history_limit: 100,
+        }
+    }
+
+    pub fn set_trace_limit(&mut self, limit: usize) {
+        self.trace_limit = limit;
+        // The latest entries are at the back.
+        let excess = self.trace.len().saturating_sub(limit);
+        self.trace.drain(..excess);
+    }
+
+    /// Sets how many steps can be undone. Each step saves a snapshot of the computer, sharing
+    /// the memory pages with it: only the pages written afterwards get copied.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, ip: usize) {
+        self.breakpoints.insert(ip);
+    }
+
+    pub fn remove_breakpoint(&mut self, ip: usize) -> bool {
+        self.breakpoints.remove(&ip)
+    }
+
+    #[must_use]
+    pub fn breakpoints(&self) -> &BTreeSet<usize> {
+        &self.breakpoints
+    }
+
+    pub fn add_watchpoint(&mut self, addr: usize, access: Access) {
+        self.watchpoints.insert(addr, access);
+    }
+
+    pub fn remove_watchpoint(&mut self, addr: usize) -> bool {
+        self.watchpoints.remove(&addr).is_some()
+    }
+
+    #[must_use]
+    pub fn watchpoints(&self) -> &BTreeMap<usize, Access> {
+        &self.watchpoints
+    }
+
+    /// The executed instructions, oldest first.
+    #[must_use]
+    pub fn trace(&self) -> &VecDeque<TraceEntry> {
+        &self.trace
+    }
+
+    #[must_use]
+    pub fn ip(&self) -> usize {
+        self.computer.base.ip
+    }
+
+    #[must_use]
+    pub fn relative_base(&self) -> i64 {
+        self.computer.base.relative_base
+    }
+
+    /// Copy of the memory, without the sparse high addresses.
+    #[must_use]
+    pub fn memory(&self) -> Vec<i64> {
+        self.computer.base.mem.to_vec()
+    }
+
+    /// Disassembly of the instruction at the instruction pointer.
+    #[must_use]
+    pub fn current_instruction(&self) -> String {
+        let mem = &self.computer.base.mem;
+        Instruction::try_decode(|addr| mem.read(addr), self.ip()).map_or_else(
+            |_| format!("Invalid instruction {}", mem.read(self.ip())),
+            |ins| instruction_str(ins, mem.len()),
+        )
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<B> {
+        Snapshot(self.computer.clone())
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot<B>) {
+        self.computer = snapshot.0.clone();
+    }
+
+    /// Executes one instruction.
+    pub fn step(&mut self) -> Stop {
+        let base = &self.computer.base;
+        let ins = match (
+            &base.state,
+            Instruction::try_decode(|addr| base.mem.read(addr), base.ip),
+        ) {
+            (ExecState::Halted, _) => return Stop::Halted,
+            (ExecState::Error(e), _) => return Stop::Error(e.clone()),
+            (_, Ok(ins)) => ins,
+            // Let the computer get into the error state.
+            (_, Err(_)) => return self.computer.step().into(),
+        };
+        let ip = base.ip;
+        let entry = TraceEntry {
+            ip,
+            relative_base: base.relative_base,
+            instruction: instruction_str(ins, base.mem.len()),
+        };
+        let (reads, write) = accesses(base, ins);
+
+        let snapshot = (self.history_limit > 0).then(|| self.snapshot());
+        let state = self.computer.step();
+        if matches!(state, ExecState::NeedsInput | ExecState::Error(_)) {
+            return state.into();
+        }
+
+        if let Some(snapshot) = snapshot {
+            if self.history.len() == self.history_limit {
+                self.history.pop_front();
+            }
+            self.history.push_back(snapshot);
+        }
+        if self.trace_limit > 0 {
+            if self.trace.len() == self.trace_limit {
+                self.trace.pop_front();
+            }
+            self.trace.push_back(entry);
+        }
+        if state == ExecState::Halted {
+            return Stop::Halted;
+        }
+
+        let accessed = reads
+            .into_iter()
+            .map(|addr| (addr, Access::Read))
+            .chain(write.map(|addr| (addr, Access::Write)));
+        for (addr, access) in accessed {
+            if self
+                .watchpoints
+                .get(&addr)
+                .is_some_and(|w| w.matches(access))
+            {
+                return Stop::Watchpoint { ip, addr, access };
+            }
+        }
+        Stop::Stepped
+    }
+
+    /// Undoes the last step. Returns false if there is no step to undo.
+    pub fn step_back(&mut self) -> bool {
+        if let Some(snapshot) = self.history.pop_back() {
+            self.computer = snapshot.0;
+            self.trace.pop_back();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Executes the instructions until a breakpoint or watchpoint is hit, more input is needed,
+    /// or the program halts.
+    /// The instruction at the current position is always executed, even if it has a breakpoint.
+    pub fn run(&mut self) -> Stop {
+        let mut stop = self.step();
+        while stop == Stop::Stepped {
+            if self.breakpoints.contains(&self.ip()) {
+                return Stop::Breakpoint(self.ip());
+            }
+            stop = self.step();
+        }
+        stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Outputs the numbers from 1 to 3, counting in address 20.
+    const COUNT: &str = "1001,20,1,20,4,20,1007,20,3,21,1005,21,0,99";
+
+    #[test]
+    fn test_step() {
+        let mut debugger = Debugger::new(IntcodeComputer::build(COUNT));
+        assert_eq!(debugger.current_instruction(), "ADD [20], #1 -> [20]");
+        assert_eq!(debugger.step(), Stop::Stepped);
+        assert_eq!(debugger.ip(), 4);
+        assert_eq!(debugger.memory()[20], 1);
+        assert_eq!(debugger.current_instruction(), "OUT [20]");
+
+        let debugger = Debugger::new(IntcodeComputer::build("42"));
+        assert_eq!(debugger.current_instruction(), "Invalid instruction 42");
+    }
+
+    #[test]
+    fn test_breakpoint() {
+        let mut debugger = Debugger::new(IntcodeComputer::build(COUNT));
+        debugger.add_breakpoint(4);
+        assert_eq!(debugger.run(), Stop::Breakpoint(4));
+        assert_eq!(debugger.run(), Stop::Breakpoint(4));
+        assert_eq!(debugger.computer.io.dump_output(), "1");
+        assert!(debugger.remove_breakpoint(4));
+        assert_eq!(debugger.run(), Stop::Halted);
+        assert_eq!(debugger.computer.io.dump_output(), "1,2,3");
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let mut debugger = Debugger::new(IntcodeComputer::build(COUNT));
+        debugger.add_watchpoint(21, Access::Write);
+        let stop = debugger.run();
+        assert_eq!(
+            stop,
+            Stop::Watchpoint {
+                ip: 6,
+                addr: 21,
+                access: Access::Write
+            }
+        );
+        debugger.add_watchpoint(21, Access::Read);
+        assert_eq!(
+            debugger.run(),
+            Stop::Watchpoint {
+                ip: 10,
+                addr: 21,
+                access: Access::Read
+            }
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let mut debugger = Debugger::new(IntcodeComputer::build("3,0,4,0,99"));
+        assert_eq!(debugger.run(), Stop::WaitingForInput);
+        debugger.computer.io.add_input(7);
+        assert_eq!(debugger.run(), Stop::Halted);
+        assert_eq!(debugger.computer.io.get_output(), Some(7));
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut debugger = Debugger::new(IntcodeComputer::build(COUNT));
+        debugger.set_trace_limit(2);
+        debugger.run();
+        let trace: Vec<_> = debugger.trace().iter().map(|e| e.ip).collect();
+        assert_eq!(trace, [10, 13]);
+        assert_eq!(debugger.trace()[1].instruction, "HLT");
+    }
+
+    #[test]
+    fn test_lower_trace_limit() {
+        let mut debugger = Debugger::new(IntcodeComputer::build(COUNT));
+        debugger.run();
+        assert_eq!(debugger.trace().len(), 13);
+        debugger.set_trace_limit(2);
+        let trace: Vec<_> = debugger.trace().iter().map(|e| e.ip).collect();
+        assert_eq!(trace, [10, 13]);
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut debugger = Debugger::new(IntcodeComputer::build(COUNT));
+        debugger.step();
+        debugger.step();
+        assert_eq!(debugger.computer.io.dump_output(), "1");
+        assert!(debugger.step_back());
+        assert_eq!(debugger.ip(), 4);
+        assert_eq!(debugger.computer.io.dump_output(), "");
+        assert!(debugger.step_back());
+        assert_eq!(debugger.ip(), 0);
+        assert_eq!(debugger.computer.dump_memory(), COUNT);
+        assert!(!debugger.step_back());
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut debugger = Debugger::new(IntcodeComputer::build(COUNT));
+        debugger.add_breakpoint(4);
+        debugger.run();
+        let snapshot = debugger.snapshot();
+        assert_eq!(debugger.run(), Stop::Breakpoint(4));
+        assert_eq!(debugger.memory()[20], 2);
+        debugger.restore(&snapshot);
+        assert_eq!(debugger.memory()[20], 1);
+        assert_eq!(debugger.ip(), 4);
+    }
+}
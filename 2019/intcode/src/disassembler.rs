@@ -0,0 +1,298 @@
+//! Disassembler of Intcode programs.
+//!
+//! The code is found by following the control flow from address 0. Everything not reached is data.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Write},
+};
+
+use itertools::Itertools;
+
+use crate::{Instruction, Param};
+
+// Minimum length of a run of printable characters to show it as a string.
+const MIN_STRING_LEN: usize = 4;
+// Maximum number of values per data line.
+const DATA_PER_LINE: usize = 8;
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Param::Position(addr) => write!(f, "[{addr}]"),
+            Param::Immediate(val) => write!(f, "#{val}"),
+            Param::Relative(offset) if *offset < 0 => write!(f, "[rb{offset}]"),
+            Param::Relative(offset) => write!(f, "[rb+{offset}]"),
+        }
+    }
+}
+
+// Decodes the instruction at this address, if it's a valid one.
+// Instructions with modes for parameters they don't have are treated as data,
+// so that assembling the output gives back the same memory.
+pub(crate) fn decode(mem: &[i64], addr: usize) -> Option<Instruction> {
+    let ins = Instruction::try_new(mem, addr).ok()?;
+    let end = addr + ins.length();
+    (end <= mem.len() && ins.encode() == mem[addr..end]).then_some(ins)
+}
+
+// Address of a jump target, if it's known statically.
+fn static_target(target: Param, len: usize) -> Option<usize> {
+    if let Param::Immediate(t) = target {
+        usize::try_from(t).ok().filter(|t| *t < len)
+    } else {
+        None
+    }
+}
+
+fn label(addr: usize) -> String {
+    format!("L{addr}")
+}
+
+fn jump_target_str(target: Param, len: usize) -> String {
+    static_target(target, len).map_or_else(|| target.to_string(), label)
+}
+
+pub(crate) fn instruction_str(ins: Instruction, len: usize) -> String {
+    match ins {
+        Instruction::Add(a, b, c) => format!("ADD {a}, {b} -> {c}"),
+        Instruction::Mult(a, b, c) => format!("MUL {a}, {b} -> {c}"),
+        Instruction::Input(a) => format!("IN -> {a}"),
+        Instruction::Output(a) => format!("OUT {a}"),
+        Instruction::JumpIfTrue(a, b) => format!("JNZ {a}, {}", jump_target_str(b, len)),
+        Instruction::JumpIfFalse(a, b) => format!("JZ {a}, {}", jump_target_str(b, len)),
+        Instruction::LessThan(a, b, c) => format!("LT {a}, {b} -> {c}"),
+        Instruction::Equal(a, b, c) => format!("EQ {a}, {b} -> {c}"),
+        Instruction::ChangeRelativeBase(a) => format!("ARB {a}"),
+        Instruction::Halt => "HLT".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Analysis {
+    // Instructions indexed by their address.
+    pub(crate) code: BTreeMap<usize, Instruction>,
+    pub(crate) labels: BTreeSet<usize>,
+}
+
+// Follows the control flow from address 0.
+//
+// Jumps with a target only known at runtime cannot be followed. As these are mainly used to return
+// from functions, constants computed from immediate values (like `ADD #ret, #0 -> [rb+1]`)
+// are also tried as code addresses, once all the known paths have been explored.
+pub(crate) fn analyze(mem: &[i64]) -> Analysis {
+    let mut analysis = Analysis::default();
+    let mut covered = vec![false; mem.len()];
+    let mut todo: Vec<usize> = vec![0];
+    let mut return_candidates: Vec<usize> = Vec::new();
+
+    while let Some(start) = todo.pop().or_else(|| return_candidates.pop()) {
+        let mut addr = start;
+        while let Some(ins) = decode(mem, addr) {
+            if analysis.code.contains_key(&addr)
+                || covered[addr..addr + ins.length()].contains(&true)
+            {
+                break;
+            }
+            if addr == start && start != 0 {
+                analysis.labels.insert(addr);
+            }
+            covered[addr..addr + ins.length()].fill(true);
+            analysis.code.insert(addr, ins);
+            addr += ins.length();
+
+            match ins {
+                Instruction::JumpIfTrue(cond, target) | Instruction::JumpIfFalse(cond, target) => {
+                    if let Some(t) = static_target(target, mem.len()) {
+                        analysis.labels.insert(t);
+                        todo.push(t);
+                    }
+                    let always_jumps = match (ins, cond) {
+                        (Instruction::JumpIfTrue(..), Param::Immediate(v)) => v != 0,
+                        (Instruction::JumpIfFalse(..), Param::Immediate(v)) => v == 0,
+                        _ => false,
+                    };
+                    if always_jumps {
+                        break;
+                    }
+                }
+                Instruction::Add(Param::Immediate(a), Param::Immediate(b), _) => {
+                    if let Some(sum) = a.checked_add(b) {
+                        return_candidates.extend(static_target(Param::Immediate(sum), mem.len()));
+                    }
+                }
+                Instruction::Halt => break,
+                _ => {}
+            }
+        }
+    }
+    analysis
+}
+
+// Formats a data region, detecting the strings in it.
+fn data_lines(values: &[i64]) -> Vec<(usize, String)> {
+    let is_printable = |v: &i64| *v == 10 || (32..127).contains(v);
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < values.len() {
+        let string_len = values[i..].iter().take_while(|v| is_printable(v)).count();
+        if string_len >= MIN_STRING_LEN {
+            let s: String = values[i..i + string_len]
+                .iter()
+                .map(|v| char::from(u8::try_from(*v).unwrap()))
+                .collect();
+            lines.push((i, format!("DATA {s:?}")));
+            i += string_len;
+        } else {
+            // Numbers until the next string.
+            let mut end = i + 1;
+            while end < values.len()
+                && end - i < DATA_PER_LINE
+                && values[end..].iter().take_while(|v| is_printable(v)).count() < MIN_STRING_LEN
+            {
+                end += 1;
+            }
+            lines.push((i, format!("DATA {}", values[i..end].iter().join(", "))));
+            i = end;
+        }
+    }
+    lines
+}
+
+/// Disassembles the Intcode program, returning one line per instruction, like
+///
+/// ```text
+/// L12:
+///   12: ADD [rb+3], #5 -> [100]
+///   16: JNZ [100], L12
+/// ```
+///
+/// Jump targets are labeled, and memory that isn't reached by the code is shown as data,
+/// with the strings of ASCII characters detected.
+#[must_use]
+pub fn disassemble(mem: &[i64]) -> String {
+    let analysis = analyze(mem);
+    let width = mem.len().saturating_sub(1).to_string().len();
+
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    let mut addr = 0;
+    while addr < mem.len() {
+        if let Some(ins) = analysis.code.get(&addr) {
+            lines.push((addr, instruction_str(*ins, mem.len())));
+            addr += ins.length();
+        } else {
+            // Data goes until the next instruction or label.
+            let end = (addr + 1..mem.len())
+                .find(|a| analysis.code.contains_key(a) || analysis.labels.contains(a))
+                .unwrap_or(mem.len());
+            lines.extend(
+                data_lines(&mem[addr..end])
+                    .into_iter()
+                    .map(|(offset, line)| (addr + offset, line)),
+            );
+            addr = end;
+        }
+    }
+
+    let mut result = String::new();
+    for (addr, line) in lines {
+        if analysis.labels.contains(&addr) {
+            writeln!(result, "{}:", label(addr)).unwrap();
+        }
+        writeln!(result, "  {addr:>width$}: {line}").unwrap();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(code: &str) -> Vec<i64> {
+        code.split(',').map(|v| v.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_param_display() {
+        assert_eq!(Param::Position(100).to_string(), "[100]");
+        assert_eq!(Param::Immediate(-5).to_string(), "#-5");
+        assert_eq!(Param::Relative(3).to_string(), "[rb+3]");
+        assert_eq!(Param::Relative(-2).to_string(), "[rb-2]");
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let mem = build("3,9,8,9,10,9,4,9,99,-1,8");
+        assert_eq!(
+            disassemble(&mem),
+            r"   0: IN -> [9]
+   2: EQ [9], [10] -> [9]
+   6: OUT [9]
+   8: HLT
+   9: DATA -1, 8
+"
+        );
+    }
+
+    #[test]
+    fn test_disassemble_jumps() {
+        let mem = build("3,12,1006,12,15,21101,0,7,1,1105,1,17,-1,0,1,99,0,204,-3,99");
+        assert_eq!(
+            disassemble(&mem),
+            r"   0: IN -> [12]
+   2: JZ [12], L15
+   5: ADD #0, #7 -> [rb+1]
+   9: JNZ #1, L17
+  12: DATA -1, 0, 1
+L15:
+  15: HLT
+  16: DATA 0
+L17:
+  17: OUT [rb-3]
+  19: HLT
+"
+        );
+    }
+
+    #[test]
+    fn test_disassemble_return_address() {
+        // Calls the function at 12, which returns to 9 with a jump on a relative address.
+        let mem = build("109,30,21101,9,0,0,1105,1,12,104,42,99,2105,1,0,0");
+        assert_eq!(
+            disassemble(&mem),
+            r"   0: ARB #30
+   2: ADD #9, #0 -> [rb+0]
+   6: JNZ #1, L12
+L9:
+   9: OUT #42
+  11: HLT
+L12:
+  12: JNZ #1, [rb+0]
+  15: DATA 0
+"
+        );
+    }
+
+    #[test]
+    fn test_disassemble_overflowing_add() {
+        let mem = build("1101,9223372036854775807,1,0,99");
+        assert_eq!(
+            disassemble(&mem),
+            r"  0: ADD #9223372036854775807, #1 -> [0]
+  4: HLT
+"
+        );
+    }
+
+    #[test]
+    fn test_disassemble_strings() {
+        let mem = build("4,3,99,72,101,108,108,111,10,0,5");
+        assert_eq!(
+            disassemble(&mem),
+            r#"   0: OUT [3]
+   2: HLT
+   3: DATA "Hello\n"
+   9: DATA 0, 5
+"#
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Errors of the Intcode computer.
+//!
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntcodeError {
+    /// The value at `ip` isn't a known instruction.
+    InvalidOpcode { ip: usize, opcode: i64 },
+    /// A parameter of the instruction at `ip` has an unknown mode.
+    InvalidMode { ip: usize, mode: i64 },
+    /// The instruction at `ip` accesses or jumps to a negative address.
+    NegativeAddress { ip: usize, addr: i64 },
+    /// The instruction at `ip` writes to a parameter in immediate mode.
+    WriteToImmediate { ip: usize },
+    /// The program value at `position` (its index in the list) isn't an integer.
+    ParseError { position: usize, value: String },
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::InvalidOpcode { ip, opcode } => {
+                write!(f, "Unknown opcode {opcode} at {ip}")
+            }
+            IntcodeError::InvalidMode { ip, mode } => {
+                write!(f, "Invalid parameter mode {mode} at {ip}")
+            }
+            IntcodeError::NegativeAddress { ip, addr } => {
+                write!(f, "Negative address {addr} used at {ip}")
+            }
+            IntcodeError::WriteToImmediate { ip } => {
+                write!(f, "Cannot write to immediate mode value at {ip}")
+            }
+            IntcodeError::ParseError { position, value } => {
+                write!(f, "Invalid value '{value}' at position {position}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntcodeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExecState, IntcodeComputer};
+
+    fn exec_error(code: &str) -> IntcodeError {
+        let mut computer = IntcodeComputer::build(code);
+        computer.try_exec().unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            IntcodeComputer::try_build("1,2,x,99").unwrap_err(),
+            IntcodeError::ParseError {
+                position: 2,
+                value: "x".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_exec_errors() {
+        assert_eq!(
+            exec_error("1101,1,1,5,42,0"),
+            IntcodeError::InvalidOpcode { ip: 4, opcode: 42 }
+        );
+        assert_eq!(
+            exec_error("301,0,0,0,99"),
+            IntcodeError::InvalidMode { ip: 0, mode: 3 }
+        );
+        assert_eq!(
+            exec_error("1,-1,0,0,99"),
+            IntcodeError::NegativeAddress { ip: 0, addr: -1 }
+        );
+        assert_eq!(
+            exec_error("109,-5,204,0,99"),
+            IntcodeError::NegativeAddress { ip: 2, addr: -5 }
+        );
+        assert_eq!(
+            exec_error("1105,1,-3"),
+            IntcodeError::NegativeAddress { ip: 0, addr: -3 }
+        );
+        assert_eq!(
+            exec_error("10001,0,0,0,99"),
+            IntcodeError::WriteToImmediate { ip: 0 }
+        );
+    }
+
+    #[test]
+    fn test_exec_state() {
+        let mut computer = IntcodeComputer::build("3,0,4,0,99");
+        assert_eq!(computer.try_exec(), Ok(ExecState::NeedsInput));
+        computer.io.add_input(5);
+        assert_eq!(computer.try_exec(), Ok(ExecState::Halted));
+        assert_eq!(computer.io.get_output(), Some(5));
+
+        // Errors are kept.
+        let mut computer = IntcodeComputer::build("42");
+        let error = IntcodeError::InvalidOpcode { ip: 0, opcode: 42 };
+        assert_eq!(computer.try_exec(), Err(error.clone()));
+        assert_eq!(computer.state(), &ExecState::Error(error.clone()));
+        assert_eq!(computer.try_exec(), Err(error));
+    }
+
+    #[test]
+    fn test_overflow() {
+        // Arithmetic wraps around, like in the translated code.
+        let mut computer = IntcodeComputer::build("1101,9223372036854775807,1,0,4,0,99");
+        assert_eq!(computer.try_exec(), Ok(ExecState::Halted));
+        assert_eq!(computer.io.get_output(), Some(i64::MIN));
+
+        let mut computer = IntcodeComputer::build("1102,4611686018427387904,2,0,4,0,99");
+        assert_eq!(computer.try_exec(), Ok(ExecState::Halted));
+        assert_eq!(computer.io.get_output(), Some(i64::MIN));
+
+        // The relative base too, and so does the address computed from it.
+        let mut computer =
+            IntcodeComputer::build("109,9223372036854775807,109,2,204,9223372036854775807,99");
+        assert_eq!(computer.try_exec(), Ok(ExecState::Halted));
+        assert_eq!(computer.io.get_output(), Some(109));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown opcode 42 at 0")]
+    fn test_exec_panics() {
+        IntcodeComputer::build("42").exec();
+    }
+}
@@ -0,0 +1,409 @@
+//! Faster Intcode computer, for long running programs.
+//!
+//! Decoding an instruction takes several divisions, but programs execute the same
+//! instructions again and again. The decoded instructions are kept per address,
+//! and forgotten when the program writes over them, as programs can modify themselves.
+use itertools::Itertools;
+
+use crate::{
+    memory::Memory, Bus, ExecState, InputOutput, Instruction, IntcodeBase, IntcodeComputer,
+    IntcodeError, Param,
+};
+
+// Length of the longest instruction.
+const MAX_INSTRUCTION_LENGTH: usize = 4;
+
+/// Intcode computer caching the decoded instructions, and using a paged memory
+/// for sparse high addresses.
+///
+/// It behaves exactly like `IntcodeComputer`, including for invalid programs.
+#[derive(Debug, Clone)]
+pub struct FastIntcodeComputer<B: Bus = InputOutput> {
+    mem: Memory,
+    // Decoded instruction at each address of the contiguous memory.
+    cache: Vec<Option<Instruction>>,
+    ip: usize,
+    relative_base: i64,
+    state: ExecState,
+    pub io: B,
+}
+
+impl FastIntcodeComputer {
+    /// Builds a Intcode computer from a list of integers separated by commas.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if input is invalid.
+    #[must_use]
+    pub fn build(code: &str) -> Self {
+        Self::build_with_bus(code, InputOutput::new())
+    }
+
+    /// Builds a Intcode computer from a list of integers separated by commas.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IntcodeError::ParseError` if a value isn't an integer.
+    pub fn try_build(code: &str) -> Result<Self, IntcodeError> {
+        Self::try_build_with_bus(code, InputOutput::new())
+    }
+
+    // Execute the program with given integer as input, returning last integer from output.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there is no output.
+    #[must_use]
+    pub fn run(&mut self, input: i64) -> i64 {
+        self.io.add_input(input);
+        self.exec();
+        self.io.get_output().unwrap()
+    }
+}
+
+impl<B: Bus> From<IntcodeComputer<B>> for FastIntcodeComputer<B> {
+    fn from(computer: IntcodeComputer<B>) -> Self {
+        Self::from_base(computer.base, computer.io)
+    }
+}
+
+impl<B: Bus> FastIntcodeComputer<B> {
+    fn from_base(base: IntcodeBase, io: B) -> Self {
+        Self {
+            mem: base.mem,
+            cache: Vec::new(),
+            ip: base.ip,
+            relative_base: base.relative_base,
+            state: base.state,
+            io,
+        }
+    }
+
+    /// Builds a Intcode computer from a list of integers separated by commas, using the specified bus.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if input is invalid.
+    #[must_use]
+    pub fn build_with_bus(code: &str, bus: B) -> Self {
+        Self::from_base(IntcodeBase::build(code), bus)
+    }
+
+    /// Builds a Intcode computer from a list of integers separated by commas, using the specified bus.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IntcodeError::ParseError` if a value isn't an integer.
+    pub fn try_build_with_bus(code: &str, bus: B) -> Result<Self, IntcodeError> {
+        Ok(Self::from_base(IntcodeBase::try_build(code)?, bus))
+    }
+
+    // Converts the value to an address.
+    fn address(&self, addr: i64) -> Result<usize, IntcodeError> {
+        usize::try_from(addr).map_err(|_| IntcodeError::NegativeAddress { ip: self.ip, addr })
+    }
+
+    fn get(&self, p: Param) -> Result<i64, IntcodeError> {
+        Ok(match p {
+            Param::Position(addr) => self.mem.read(addr),
+            Param::Immediate(val) => val,
+            Param::Relative(offset) => self
+                .mem
+                .read(self.address(self.relative_base.wrapping_add(offset))?),
+        })
+    }
+
+    fn get_address(&self, p: Param) -> Result<usize, IntcodeError> {
+        self.address(self.get(p)?)
+    }
+
+    fn write(&mut self, addr: usize, val: i64) {
+        self.mem.write(addr, val);
+        // Forget the instructions that include this address.
+        let start = addr.saturating_sub(MAX_INSTRUCTION_LENGTH - 1);
+        let end = (addr + 1).min(self.cache.len());
+        if start < end {
+            self.cache[start..end].fill(None);
+        }
+    }
+
+    fn set(&mut self, p: Param, val: i64) -> Result<(), IntcodeError> {
+        let addr = match p {
+            Param::Position(addr) => addr,
+            Param::Immediate(_) => return Err(IntcodeError::WriteToImmediate { ip: self.ip }),
+            Param::Relative(offset) => self.address(self.relative_base.wrapping_add(offset))?,
+        };
+        self.write(addr, val);
+        Ok(())
+    }
+
+    // Decodes the instruction at the instruction pointer, or gets it from the cache.
+    fn decode(&mut self) -> Result<Instruction, IntcodeError> {
+        let ip = self.ip;
+        if let Some(Some(ins)) = self.cache.get(ip) {
+            return Ok(*ins);
+        }
+        let ins = Instruction::try_decode(|addr| self.mem.read(addr), ip)?;
+        // Only instructions in the contiguous memory are cached.
+        if ip < self.mem.len() {
+            if self.cache.len() < self.mem.len() {
+                self.cache.resize(self.mem.len(), None);
+            }
+            self.cache[ip] = Some(ins);
+        }
+        Ok(ins)
+    }
+
+    fn try_step(&mut self) -> Result<ExecState, IntcodeError> {
+        let ins = self.decode()?;
+        match ins {
+            Instruction::Add(a, b, c) => {
+                let val = self.get(a)?.wrapping_add(self.get(b)?);
+                self.set(c, val)?;
+            }
+            Instruction::Mult(a, b, c) => {
+                let val = self.get(a)?.wrapping_mul(self.get(b)?);
+                self.set(c, val)?;
+            }
+            Instruction::Input(a) => {
+                let Some(val) = self.io.read() else {
+                    return Ok(ExecState::NeedsInput);
+                };
+                self.set(a, val)?;
+            }
+            Instruction::Output(a) => {
+                let val = self.get(a)?;
+                self.io.write(val);
+            }
+            Instruction::JumpIfTrue(a, b) => {
+                if self.get(a)? != 0 {
+                    self.ip = self.get_address(b)?;
+                    return Ok(ExecState::Running);
+                }
+            }
+            Instruction::JumpIfFalse(a, b) => {
+                if self.get(a)? == 0 {
+                    self.ip = self.get_address(b)?;
+                    return Ok(ExecState::Running);
+                }
+            }
+            Instruction::LessThan(a, b, c) => {
+                let val = i64::from(self.get(a)? < self.get(b)?);
+                self.set(c, val)?;
+            }
+            Instruction::Equal(a, b, c) => {
+                let val = i64::from(self.get(a)? == self.get(b)?);
+                self.set(c, val)?;
+            }
+            Instruction::ChangeRelativeBase(a) => {
+                self.relative_base = self.relative_base.wrapping_add(self.get(a)?);
+            }
+            Instruction::Halt => return Ok(ExecState::Halted),
+        }
+        self.ip += ins.length();
+        Ok(ExecState::Running)
+    }
+
+    /// Executes only the next instruction.
+    pub fn step(&mut self) -> ExecState {
+        if matches!(self.state, ExecState::Halted | ExecState::Error(_)) {
+            return self.state.clone();
+        }
+        self.state = match self.try_step() {
+            Ok(state) => state,
+            Err(e) => ExecState::Error(e),
+        };
+        self.state.clone()
+    }
+
+    /// Executes the instructions, like `exec()`, returning the state the computer stopped in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program is invalid. The computer then stays in the `Error` state.
+    pub fn try_exec(&mut self) -> Result<ExecState, IntcodeError> {
+        match &self.state {
+            ExecState::Halted => return Ok(ExecState::Halted),
+            ExecState::Error(e) => return Err(e.clone()),
+            _ => {}
+        }
+        loop {
+            match self.try_step() {
+                Ok(ExecState::Running) => {}
+                Ok(state) => {
+                    self.state = state.clone();
+                    return Ok(state);
+                }
+                Err(e) => {
+                    self.state = ExecState::Error(e.clone());
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Executes the instructions.
+    /// This function returns when reaching the end of the program (a Halt instruction),
+    /// or if trying to get some input, but the bus has none.
+    /// The difference can be checked with the `is_halted()` function.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the computer is halted, or if the program is invalid.
+    pub fn exec(&mut self) {
+        assert!(!self.is_halted(), "Computer isn't running");
+        if let Err(e) = self.try_exec() {
+            panic!("{e}");
+        }
+    }
+
+    #[must_use]
+    pub fn state(&self) -> &ExecState {
+        &self.state
+    }
+
+    #[must_use]
+    pub fn is_halted(&self) -> bool {
+        self.state == ExecState::Halted
+    }
+
+    /// The contiguous memory, which doesn't include the sparse high addresses.
+    #[must_use]
+    pub fn dump_memory(&self) -> String {
+        self.mem.to_vec().iter().join(",")
+    }
+
+    #[must_use]
+    pub fn read_mem(&self, addr: usize) -> i64 {
+        self.mem.read(addr)
+    }
+
+    pub fn write_mem(&mut self, addr: usize, val: i64) {
+        self.write(addr, val);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs the program on both computers, with the same inputs, checking they behave the same.
+    fn compare(code: &str, inputs: &[i64]) -> Vec<i64> {
+        let mut reference = IntcodeComputer::build(code);
+        let mut fast = FastIntcodeComputer::build(code);
+        for input in inputs {
+            reference.io.add_input(*input);
+            fast.io.add_input(*input);
+        }
+        assert_eq!(fast.try_exec(), reference.try_exec(), "{code}");
+        let outputs: Vec<i64> = std::iter::from_fn(|| reference.io.get_output()).collect();
+        assert_eq!(
+            std::iter::from_fn(|| fast.io.get_output()).collect::<Vec<_>>(),
+            outputs,
+            "{code}"
+        );
+        for addr in 0..code.split(',').count() + 100 {
+            assert_eq!(fast.read_mem(addr), reference.read_mem(addr), "{code}");
+        }
+        outputs
+    }
+
+    #[test]
+    fn test_previous_days() {
+        // Day 2.
+        compare("1,9,10,3,2,3,11,0,99,30,40,50", &[]);
+        compare("1,1,1,4,99,5,6,0,99", &[]);
+        // Day 5.
+        let c = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+        assert_eq!(compare(c, &[1]), [999]);
+        assert_eq!(compare(c, &[8]), [1000]);
+        assert_eq!(compare(c, &[45]), [1001]);
+        compare("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9", &[0]);
+        compare("3,3,1105,-1,9,1101,0,0,12,4,12,99,1", &[4]);
+        // Day 9.
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        assert_eq!(compare(quine, &[]).iter().join(","), quine);
+        compare("1102,34915192,34915192,7,4,7,99,0", &[]);
+        compare("104,1125899906842624,99", &[]);
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // Outputs 1, then replaces this output instruction by a halt, and jumps back to it.
+        assert_eq!(compare("104,1,1101,0,99,0,1105,1,0", &[]), [1]);
+        // Outputs its own parameter, incrementing it until it's 3.
+        let code = "104,1,1001,1,1,1,1008,1,3,20,1006,20,0,99";
+        assert_eq!(compare(code, &[]), [1, 2]);
+    }
+
+    #[test]
+    fn test_errors() {
+        for code in [
+            "1101,1,1,5,42,0",
+            "301,0,0,0,99",
+            "1,-1,0,0,99",
+            "109,-5,204,0,99",
+            "1105,1,-3",
+            "10001,0,0,0,99",
+            // Overflows wrap around.
+            "1101,9223372036854775807,1,0,4,0,99",
+            "1102,4611686018427387904,2,0,4,0,99",
+            "109,9223372036854775807,109,2,204,9223372036854775807,99",
+        ] {
+            compare(code, &[]);
+        }
+    }
+
+    #[test]
+    fn test_sparse_memory() {
+        // Sets the relative base very high, writes 7 there and outputs it.
+        let mut computer = FastIntcodeComputer::build("109,1000000000000,21101,3,4,0,204,0,99");
+        computer.exec();
+        assert_eq!(computer.io.get_output(), Some(7));
+        assert_eq!(computer.read_mem(1_000_000_000_000), 7);
+    }
+
+    #[test]
+    fn test_random_programs() {
+        // Small programs of random values, executed step by step on both computers.
+        let mut seed: u64 = 42;
+        let mut next = |max: u64| {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 33) % max
+        };
+        let opcodes = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+        // Values making the arithmetic and the relative base overflow.
+        let extremes = [i64::MAX, i64::MIN, i64::MAX / 2 + 1, -i64::MAX / 2 - 1];
+        for _ in 0..200 {
+            let code = (0..40)
+                .map(|_| {
+                    if next(2) == 0 {
+                        let modes = next(3) * 100 + next(3) * 1_000 + next(3) * 10_000;
+                        (modes + opcodes[usize::try_from(next(10)).unwrap()]).to_string()
+                    } else if next(20) == 0 {
+                        extremes[usize::try_from(next(4)).unwrap()].to_string()
+                    } else {
+                        (i64::try_from(next(50)).unwrap() - 5).to_string()
+                    }
+                })
+                .join(",");
+            let mut reference = IntcodeComputer::build(&code);
+            let mut fast = FastIntcodeComputer::build(&code);
+            for _ in 0..500 {
+                reference.io.add_input(1);
+                fast.io.add_input(1);
+                let state = reference.step();
+                assert_eq!(fast.step(), state, "{code}");
+                assert_eq!(fast.io.get_output(), reference.io.get_output(), "{code}");
+                if state != ExecState::Running {
+                    break;
+                }
+            }
+            for addr in 0..60 {
+                assert_eq!(fast.read_mem(addr), reference.read_mem(addr), "{code}");
+            }
+        }
+    }
+}
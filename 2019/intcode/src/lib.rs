@@ -1,7 +1,31 @@
 //! The Intcode computer.
 //!
 use itertools::Itertools;
-use std::{collections::VecDeque, io::Read};
+
+pub mod adventure;
+mod assembler;
+mod bus;
+pub mod compress;
+mod debugger;
+mod disassembler;
+mod error;
+mod fast;
+mod memory;
+pub mod network;
+#[cfg(test)]
+mod opcodes;
+pub mod pipeline;
+#[cfg(test)]
+mod previous_days;
+pub mod translate;
+
+pub use assembler::{assemble, AssemblyError};
+pub use bus::*;
+pub use debugger::*;
+pub use disassembler::disassemble;
+pub use error::IntcodeError;
+pub use fast::FastIntcodeComputer;
+use memory::Memory;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Param {
@@ -16,12 +40,15 @@ impl Param {
     const IMMEDIATE: i64 = 1;
     const RELATIVE: i64 = 2;
 
-    fn new(program: &[i64], loc: usize, mode: i64) -> Self {
+    // Builds a parameter of the instruction at `ip`, from its value in memory.
+    fn try_new(val: i64, ip: usize, mode: i64) -> Result<Self, IntcodeError> {
         match mode {
-            Self::POSITION => Position(program[loc].try_into().unwrap()),
-            Self::IMMEDIATE => Immediate(program[loc]),
-            Self::RELATIVE => Relative(program[loc]),
-            _ => panic!("Invalid parameter mode {mode}"),
+            Self::POSITION => usize::try_from(val)
+                .map(Position)
+                .map_err(|_| IntcodeError::NegativeAddress { ip, addr: val }),
+            Self::IMMEDIATE => Ok(Immediate(val)),
+            Self::RELATIVE => Ok(Relative(val)),
+            _ => Err(IntcodeError::InvalidMode { ip, mode }),
         }
     }
 }
@@ -35,7 +62,7 @@ impl From<usize> for Param {
 // To flag which params are the ones we write to.
 type WriteParam = Param;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum Instruction {
     Add(Param, Param, WriteParam),
     Mult(Param, Param, WriteParam),
@@ -64,38 +91,49 @@ impl Instruction {
         )
     }
 
-    // Builds the instruction that starts at index 0 of this program.
-    fn new(program: &[i64]) -> Self {
+    // Builds the instruction that starts at index `ip` of the memory.
+    // Memory past the end of the program is zero.
+    fn try_new(mem: &[i64], ip: usize) -> Result<Self, IntcodeError> {
+        Self::try_decode(|addr| mem.get(addr).copied().unwrap_or_default(), ip)
+    }
+
+    // Builds the instruction that starts at `ip`, reading the memory with `read`.
+    fn try_decode<F>(read: F, ip: usize) -> Result<Self, IntcodeError>
+    where
+        F: Fn(usize) -> i64,
+    {
         use Instruction::*;
-        let (opcode, modes) = Self::get_opcode_mode(program[0]);
+        let (opcode, modes) = Self::get_opcode_mode(read(ip));
 
         let mut i = 0;
         let next_p = |index: &mut usize| {
-            let p = Param::new(program, *index + 1, modes[*index]);
+            let p = Param::try_new(read(ip + *index + 1), ip, modes[*index]);
             *index += 1;
             p
         };
         let next_a = |index: &mut usize| {
             let mode = modes[*index];
-            assert_ne!(mode, Param::IMMEDIATE);
-            let p = Param::new(program, *index + 1, mode);
+            if mode == Param::IMMEDIATE {
+                return Err(IntcodeError::WriteToImmediate { ip });
+            }
+            let p = Param::try_new(read(ip + *index + 1), ip, mode);
             *index += 1;
             p
         };
 
-        match opcode {
-            1 => Add(next_p(&mut i), next_p(&mut i), next_a(&mut i)),
-            2 => Mult(next_p(&mut i), next_p(&mut i), next_a(&mut i)),
-            3 => Input(next_a(&mut i)),
-            4 => Output(next_p(&mut i)),
-            5 => JumpIfTrue(next_p(&mut i), next_p(&mut i)),
-            6 => JumpIfFalse(next_p(&mut i), next_p(&mut i)),
-            7 => LessThan(next_p(&mut i), next_p(&mut i), next_a(&mut i)),
-            8 => Equal(next_p(&mut i), next_p(&mut i), next_a(&mut i)),
-            9 => ChangeRelativeBase(next_p(&mut i)),
+        Ok(match opcode {
+            1 => Add(next_p(&mut i)?, next_p(&mut i)?, next_a(&mut i)?),
+            2 => Mult(next_p(&mut i)?, next_p(&mut i)?, next_a(&mut i)?),
+            3 => Input(next_a(&mut i)?),
+            4 => Output(next_p(&mut i)?),
+            5 => JumpIfTrue(next_p(&mut i)?, next_p(&mut i)?),
+            6 => JumpIfFalse(next_p(&mut i)?, next_p(&mut i)?),
+            7 => LessThan(next_p(&mut i)?, next_p(&mut i)?, next_a(&mut i)?),
+            8 => Equal(next_p(&mut i)?, next_p(&mut i)?, next_a(&mut i)?),
+            9 => ChangeRelativeBase(next_p(&mut i)?),
             99 => Halt,
-            _ => panic!("Unknown opcode {opcode}"),
-        }
+            _ => return Err(IntcodeError::InvalidOpcode { ip, opcode }),
+        })
     }
 
     fn param_count(self) -> usize {
@@ -111,252 +149,385 @@ impl Instruction {
     fn length(self) -> usize {
         self.param_count() + 1
     }
+
+    // The values of the instruction in memory, the reverse of `try_decode`.
+    fn encode(self) -> Vec<i64> {
+        use Instruction::*;
+        let (opcode, params) = match self {
+            Add(a, b, c) => (1, vec![a, b, c]),
+            Mult(a, b, c) => (2, vec![a, b, c]),
+            Input(a) => (3, vec![a]),
+            Output(a) => (4, vec![a]),
+            JumpIfTrue(a, b) => (5, vec![a, b]),
+            JumpIfFalse(a, b) => (6, vec![a, b]),
+            LessThan(a, b, c) => (7, vec![a, b, c]),
+            Equal(a, b, c) => (8, vec![a, b, c]),
+            ChangeRelativeBase(a) => (9, vec![a]),
+            Halt => (99, vec![]),
+        };
+        let mut values = vec![opcode];
+        let mut factor = 100;
+        for p in params {
+            let (mode, val) = match p {
+                Position(addr) => (Param::POSITION, i64::try_from(addr).unwrap()),
+                Immediate(val) => (Param::IMMEDIATE, val),
+                Relative(offset) => (Param::RELATIVE, offset),
+            };
+            values[0] += mode * factor;
+            factor *= 10;
+            values.push(val);
+        }
+        values
+    }
 }
 
-// Trait that abstracts Input/Output support in the base computer.
-trait Bus {
-    fn read(&mut self) -> Option<i64>;
-    fn write(&mut self, v: i64);
+/// State of the computer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecState {
+    /// Not started, or the last instruction executed normally.
+    Running,
+    /// Reached the end of the program (a Halt instruction).
+    Halted,
+    /// The current instruction is an input one, but there is no input. It wasn't executed.
+    NeedsInput,
+    /// The program is invalid. Execution cannot continue.
+    Error(IntcodeError),
 }
 
 #[derive(Debug, Clone)]
 struct IntcodeBase {
-    mem: Vec<i64>,
+    mem: Memory,
     ip: usize,
     relative_base: i64,
-    halted: bool,
+    state: ExecState,
 }
 
 impl IntcodeBase {
     /// Builds a Intcode computer from a list of integers separated by commas.
-    fn build(code: &str) -> Self {
-        Self {
-            mem: code.split(',').map(|v| v.parse().unwrap()).collect(),
+    fn try_build(code: &str) -> Result<Self, IntcodeError> {
+        let values: Vec<i64> = code
+            .split(',')
+            .enumerate()
+            .map(|(position, v)| {
+                v.parse().map_err(|_| IntcodeError::ParseError {
+                    position,
+                    value: v.to_string(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            mem: Memory::new(&values),
             ip: 0,
             relative_base: 0,
-            halted: false,
-        }
+            state: ExecState::Running,
+        })
     }
 
-    fn ensure_mem_capacity(&mut self, capacity: usize) {
-        if self.mem.len() <= capacity {
-            self.mem.resize(capacity + 1, 0);
-        }
+    fn build(code: &str) -> Self {
+        Self::try_build(code).unwrap_or_else(|e| panic!("{e}"))
     }
 
-    fn get_mem(&mut self, addr: usize) -> i64 {
-        self.ensure_mem_capacity(addr);
-        self.mem[addr]
+    fn get_mem(&self, addr: usize) -> i64 {
+        self.mem.read(addr)
     }
 
     fn set_mem(&mut self, addr: usize, val: i64) {
-        self.ensure_mem_capacity(addr);
-        self.mem[addr] = val;
+        self.mem.write(addr, val);
     }
 
-    fn get(&mut self, p: &Param) -> i64 {
-        match p {
+    // Converts the value to an address.
+    fn address(&self, addr: i64) -> Result<usize, IntcodeError> {
+        usize::try_from(addr).map_err(|_| IntcodeError::NegativeAddress { ip: self.ip, addr })
+    }
+
+    fn get(&mut self, p: &Param) -> Result<i64, IntcodeError> {
+        Ok(match p {
             Position(addr) => self.get_mem(*addr),
             Immediate(val) => *val,
-            Relative(addr) => self.get_mem((self.relative_base + *addr).try_into().unwrap()),
-        }
+            Relative(addr) => self.get_mem(self.address(self.relative_base.wrapping_add(*addr))?),
+        })
     }
 
-    fn get_address(&mut self, p: &Param) -> usize {
-        self.get(p).try_into().unwrap()
+    fn get_address(&mut self, p: &Param) -> Result<usize, IntcodeError> {
+        let addr = self.get(p)?;
+        self.address(addr)
     }
 
-    fn set(&mut self, p: &Param, val: i64) {
+    fn set(&mut self, p: &Param, val: i64) -> Result<(), IntcodeError> {
         let addr: usize = match p {
             Position(addr) => *addr,
-            Immediate(_) => panic!("Cannot write to immediate mode value"),
-            Relative(addr) => (self.relative_base + *addr).try_into().unwrap(),
+            Immediate(_) => return Err(IntcodeError::WriteToImmediate { ip: self.ip }),
+            Relative(addr) => self.address(self.relative_base.wrapping_add(*addr))?,
         };
         self.set_mem(addr, val);
+        Ok(())
     }
 
-    /// Executes the instructions.
-    ///
-    /// This function returns when reaching the end of the program (a Halt instruction),
-    /// or if trying to get some input, but there isn't any.
-    /// The difference can be checked with the `is_halted()` function.
-    fn exec<B: Bus>(&mut self, bus: &mut B) {
-        assert!(!self.is_halted(), "Computer isn't running");
-        loop {
-            let ins = Instruction::new(&self.mem[self.ip..]);
-            match ins {
-                Instruction::Add(a, b, c) => {
-                    let a = self.get(&a);
-                    let b = self.get(&b);
-                    self.set(&c, a + b);
-                    self.ip += ins.length();
-                }
-                Instruction::Mult(a, b, c) => {
-                    let a = self.get(&a);
-                    let b = self.get(&b);
-                    self.set(&c, a * b);
-                    self.ip += ins.length();
-                }
-                Instruction::Input(a) => {
-                    if let Some(val) = bus.read() {
-                        self.set(&a, val);
-                        self.ip += ins.length();
-                    } else {
-                        // Interrupt the execution loop. Program isn't halted, we are just waiting for more input.
-                        // Since ip is a self variable, program will continue at right instruction.
-                        break;
-                    }
-                }
-                Instruction::Output(a) => {
-                    let a = self.get(&a);
-                    bus.write(a);
-                    self.ip += ins.length();
-                }
-                Instruction::JumpIfTrue(a, b) => {
-                    if self.get(&a) != 0 {
-                        self.ip = self.get_address(&b);
-                    } else {
-                        self.ip += ins.length();
-                    }
-                }
-                Instruction::JumpIfFalse(a, b) => {
-                    if self.get(&a) == 0 {
-                        self.ip = self.get_address(&b);
-                    } else {
-                        self.ip += ins.length();
-                    }
-                }
-                Instruction::LessThan(a, b, c) => {
-                    let a = self.get(&a);
-                    let b = self.get(&b);
-                    self.set(&c, i64::from(a < b));
+    /// Executes the instruction at the instruction pointer, returning the new state.
+    fn step<B: Bus>(&mut self, bus: &mut B) -> ExecState {
+        if matches!(self.state, ExecState::Halted | ExecState::Error(_)) {
+            return self.state.clone();
+        }
+        self.state = match self.try_step(bus) {
+            Ok(state) => state,
+            Err(e) => ExecState::Error(e),
+        };
+        self.state.clone()
+    }
+
+    fn try_step<B: Bus>(&mut self, bus: &mut B) -> Result<ExecState, IntcodeError> {
+        let ins = Instruction::try_decode(|addr| self.mem.read(addr), self.ip)?;
+        match ins {
+            Instruction::Add(a, b, c) => {
+                let a = self.get(&a)?;
+                let b = self.get(&b)?;
+                self.set(&c, a.wrapping_add(b))?;
+                self.ip += ins.length();
+            }
+            Instruction::Mult(a, b, c) => {
+                let a = self.get(&a)?;
+                let b = self.get(&b)?;
+                self.set(&c, a.wrapping_mul(b))?;
+                self.ip += ins.length();
+            }
+            Instruction::Input(a) => {
+                if let Some(val) = bus.read() {
+                    self.set(&a, val)?;
                     self.ip += ins.length();
+                } else {
+                    // Program isn't halted, we are just waiting for more input.
+                    // Since ip is a self variable, program will continue at right instruction.
+                    return Ok(ExecState::NeedsInput);
                 }
-                Instruction::Equal(a, b, c) => {
-                    let a = self.get(&a);
-                    let b = self.get(&b);
-                    self.set(&c, i64::from(a == b));
+            }
+            Instruction::Output(a) => {
+                let a = self.get(&a)?;
+                bus.write(a);
+                self.ip += ins.length();
+            }
+            Instruction::JumpIfTrue(a, b) => {
+                if self.get(&a)? != 0 {
+                    self.ip = self.get_address(&b)?;
+                } else {
                     self.ip += ins.length();
                 }
-                Instruction::Halt => {
-                    self.halted = true;
-                    break;
-                }
-                Instruction::ChangeRelativeBase(a) => {
-                    let val = self.get(&a);
-                    self.relative_base += val;
+            }
+            Instruction::JumpIfFalse(a, b) => {
+                if self.get(&a)? == 0 {
+                    self.ip = self.get_address(&b)?;
+                } else {
                     self.ip += ins.length();
                 }
             }
+            Instruction::LessThan(a, b, c) => {
+                let a = self.get(&a)?;
+                let b = self.get(&b)?;
+                self.set(&c, i64::from(a < b))?;
+                self.ip += ins.length();
+            }
+            Instruction::Equal(a, b, c) => {
+                let a = self.get(&a)?;
+                let b = self.get(&b)?;
+                self.set(&c, i64::from(a == b))?;
+                self.ip += ins.length();
+            }
+            Instruction::Halt => {
+                return Ok(ExecState::Halted);
+            }
+            Instruction::ChangeRelativeBase(a) => {
+                let val = self.get(&a)?;
+                self.relative_base = self.relative_base.wrapping_add(val);
+                self.ip += ins.length();
+            }
+        }
+        Ok(ExecState::Running)
+    }
+
+    /// Executes the instructions.
+    ///
+    /// This function returns when reaching the end of the program (a Halt instruction),
+    /// or if trying to get some input, but there isn't any.
+    fn try_exec<B: Bus>(&mut self, bus: &mut B) -> Result<ExecState, IntcodeError> {
+        loop {
+            match self.step(bus) {
+                ExecState::Running => {}
+                ExecState::Error(e) => return Err(e),
+                state => return Ok(state),
+            }
+        }
+    }
+
+    fn exec<B: Bus>(&mut self, bus: &mut B) {
+        assert!(!self.is_halted(), "Computer isn't running");
+        if let Err(e) = self.try_exec(bus) {
+            panic!("{e}");
         }
     }
 
     fn is_halted(&self) -> bool {
-        self.halted
+        self.state == ExecState::Halted
     }
 
     fn dump_memory(&self) -> String {
-        self.mem.iter().join(",")
+        self.mem.to_vec().iter().join(",")
     }
 
     fn read_mem(&mut self, addr: usize) -> i64 {
-        self.mem[addr]
+        self.get_mem(addr)
     }
 
     fn write_mem(&mut self, addr: usize, val: i64) {
-        self.mem[addr] = val;
+        self.set_mem(addr, val);
     }
 }
 
-/// Simple vector based implementation of Bus trait.
+/// Intcode computer, doing its I/O through a bus.
+/// By default it uses two vectors for I/O.
 #[derive(Debug, Clone)]
-pub struct InputOutput {
-    input: VecDeque<i64>,
-    output: VecDeque<i64>,
+pub struct IntcodeComputer<B: Bus = InputOutput> {
+    base: IntcodeBase,
+    pub io: B,
 }
 
-impl InputOutput {
-    fn new() -> Self {
-        Self {
-            input: VecDeque::new(),
-            output: VecDeque::new(),
-        }
-    }
-
-    // Multiple inputs are added in the order the computer reads them (first add the first one the computer reads).
-    pub fn add_input(&mut self, input: i64) {
-        self.input.push_back(input);
-    }
-
-    pub fn extend_input(&mut self, input: &[i64]) {
-        self.input.extend(input);
+impl IntcodeComputer {
+    /// Builds a Intcode computer from a list of integers separated by commas.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if input is invalid.
+    #[must_use]
+    pub fn build(code: &str) -> Self {
+        Self::build_with_bus(code, InputOutput::new())
     }
 
-    // Returns first the oldest output of the computer.
-    pub fn get_output(&mut self) -> Option<i64> {
-        self.output.pop_front()
+    /// Builds a Intcode computer from a list of integers separated by commas.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IntcodeError::ParseError` if a value isn't an integer.
+    pub fn try_build(code: &str) -> Result<Self, IntcodeError> {
+        Self::try_build_with_bus(code, InputOutput::new())
     }
 
+    // Execute the program with given integer as input, returning last integer from output.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there is no output.
     #[must_use]
-    pub fn dump_input(&self) -> String {
-        self.input.iter().join(",")
+    pub fn run(&mut self, input: i64) -> i64 {
+        self.io.add_input(input);
+        self.exec();
+        self.io.get_output().unwrap()
     }
 
+    /// Runs a copy of the computer with these inputs, until it halts or needs more input,
+    /// and returns all the values it output. The computer itself doesn't change,
+    /// so it can be used as a pristine image for many queries.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the computer is halted, or if the program is invalid.
     #[must_use]
-    pub fn dump_output(&self) -> String {
-        self.output.iter().join(",")
+    pub fn query(&self, inputs: &[i64]) -> Vec<i64> {
+        let mut computer = self.fork();
+        computer.io.extend_input(inputs);
+        computer.exec();
+        std::iter::from_fn(|| computer.io.get_output()).collect()
     }
 }
 
-impl Bus for InputOutput {
-    fn read(&mut self) -> Option<i64> {
-        self.input.pop_front()
-    }
-
-    fn write(&mut self, v: i64) {
-        self.output.push_back(v);
-    }
-}
-
-/// Intcode computer that uses two vectors for I/O.
-#[derive(Debug, Clone)]
-pub struct IntcodeComputer {
-    base: IntcodeBase,
-    pub io: InputOutput,
-}
-
-impl IntcodeComputer {
-    /// Builds a Intcode computer from a list of integers separated by commas.
+impl<B: Bus> IntcodeComputer<B> {
+    /// Builds a Intcode computer from a list of integers separated by commas, using the specified bus.
     ///
     /// # Panics
     ///
     /// Will panic if input is invalid.
     #[must_use]
-    pub fn build(code: &str) -> Self {
+    pub fn build_with_bus(code: &str, bus: B) -> Self {
         Self {
             base: IntcodeBase::build(code),
-            io: InputOutput::new(),
+            io: bus,
+        }
+    }
+
+    /// Builds a Intcode computer from a list of integers separated by commas, using the specified bus.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IntcodeError::ParseError` if a value isn't an integer.
+    pub fn try_build_with_bus(code: &str, bus: B) -> Result<Self, IntcodeError> {
+        Ok(Self {
+            base: IntcodeBase::try_build(code)?,
+            io: bus,
+        })
+    }
+
+    /// Builds a Intcode computer continuing a program from the specified state, like where
+    /// the translated code stopped on an instruction it can't execute (see `translate`).
+    #[must_use]
+    pub fn resume(mem: &[i64], ip: usize, relative_base: i64, bus: B) -> Self {
+        Self {
+            base: IntcodeBase {
+                mem: Memory::new(mem),
+                ip,
+                relative_base,
+                state: ExecState::Running,
+            },
+            io: bus,
+        }
+    }
+
+    /// Converts the computer to one using another bus, keeping its state.
+    #[must_use]
+    pub fn with_bus<C: Bus>(self, bus: C) -> IntcodeComputer<C> {
+        IntcodeComputer {
+            base: self.base,
+            io: bus,
         }
     }
 
+    /// Copy of the computer, for exploring several paths from the same state.
+    ///
+    /// It's cheap: the memory is shared, each page being copied only when one of
+    /// the computers writes to it.
+    #[must_use]
+    pub fn fork(&self) -> Self
+    where
+        B: Clone,
+    {
+        self.clone()
+    }
+
     /// Executes the instructions.
     /// This function returns when reaching the end of the program (a Halt instruction),
-    /// or if trying to get some input, but the `input` vector is empty.
+    /// or if trying to get some input, but the bus has none.
     /// The difference can be checked with the `is_halted()` function.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the computer is halted, or if the program is invalid.
     pub fn exec(&mut self) {
         self.base.exec(&mut self.io);
     }
 
-    // Execute the program with given integer as input, returning last integer from output.
+    /// Executes the instructions, like `exec()`, returning the state the computer stopped in.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Will panic if there is no output.
+    /// Returns an error if the program is invalid. The computer then stays in the `Error` state.
+    pub fn try_exec(&mut self) -> Result<ExecState, IntcodeError> {
+        self.base.try_exec(&mut self.io)
+    }
+
+    /// Executes only the next instruction.
+    pub fn step(&mut self) -> ExecState {
+        self.base.step(&mut self.io)
+    }
+
     #[must_use]
-    pub fn run(&mut self, input: i64) -> i64 {
-        self.io.add_input(input);
-        self.exec();
-        self.io.get_output().unwrap()
+    pub fn state(&self) -> &ExecState {
+        &self.base.state
     }
 
     #[must_use]
@@ -369,6 +540,12 @@ impl IntcodeComputer {
         self.base.dump_memory()
     }
 
+    /// Disassembles the current memory of the computer, see `disassemble()`.
+    #[must_use]
+    pub fn dump_memory_annotated(&self) -> String {
+        disassemble(&self.base.mem.to_vec())
+    }
+
     #[must_use]
     pub fn read_mem(&mut self, addr: usize) -> i64 {
         self.base.read_mem(addr)
@@ -379,31 +556,6 @@ impl IntcodeComputer {
     }
 }
 
-/// Stdin/stdout based implementation of Bus trait.
-pub struct ASCIIInputOutput {}
-
-impl ASCIIInputOutput {
-    fn new() -> Self {
-        Self {}
-    }
-}
-
-impl Bus for ASCIIInputOutput {
-    fn read(&mut self) -> Option<i64> {
-        std::io::stdin()
-            .bytes()
-            .next()
-            .and_then(std::result::Result::ok)
-            .map(i64::from)
-    }
-
-    fn write(&mut self, v: i64) {
-        // Convert the value to ASCII.
-        let c = char::from_u32(u32::try_from(v).unwrap()).unwrap();
-        print!("{c}");
-    }
-}
-
 /// ASCII Intcode computer.
 pub struct ASCIIIntcodeComputer {
     base: IntcodeBase,
@@ -0,0 +1,168 @@
+//! Memory of the Intcode computers.
+//!
+//! The memory is split in pages, shared between a computer and its copies until one of them
+//! writes to it (copy-on-write), so copying a computer is cheap.
+//! The program and the addresses right after it are stored in a vector of pages.
+//! High addresses, that programs use sparsely, are stored in a map of pages, allocated on first write.
+use std::{collections::HashMap, sync::Arc};
+
+const PAGE_SIZE: usize = 1024;
+// Addresses below this are stored contiguously.
+const DENSE_LIMIT: usize = 1 << 20;
+
+type Page = [i64; PAGE_SIZE];
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Memory {
+    pages: Vec<Arc<Page>>,
+    // Number of values of the contiguous part.
+    len: usize,
+    sparse: HashMap<usize, Arc<Page>>,
+}
+
+impl Memory {
+    pub(crate) fn new(values: &[i64]) -> Self {
+        let pages = values
+            .chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = [0; PAGE_SIZE];
+                page[..chunk.len()].copy_from_slice(chunk);
+                Arc::new(page)
+            })
+            .collect();
+        Self {
+            pages,
+            len: values.len(),
+            sparse: HashMap::new(),
+        }
+    }
+
+    /// Size of the contiguous part of the memory, starting at address 0.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Values of the contiguous part of the memory.
+    pub(crate) fn to_vec(&self) -> Vec<i64> {
+        self.pages
+            .iter()
+            .flat_map(|page| page.iter())
+            .take(self.len)
+            .copied()
+            .collect()
+    }
+
+    /// Reads a value. Memory never written is zero.
+    pub(crate) fn read(&self, addr: usize) -> i64 {
+        if addr < self.len {
+            self.pages[addr / PAGE_SIZE][addr % PAGE_SIZE]
+        } else if addr < self.len.max(DENSE_LIMIT) {
+            0
+        } else {
+            self.sparse
+                .get(&(addr / PAGE_SIZE))
+                .map_or(0, |page| page[addr % PAGE_SIZE])
+        }
+    }
+
+    pub(crate) fn write(&mut self, addr: usize, val: i64) {
+        let page = if addr < self.len.max(DENSE_LIMIT) {
+            if addr >= self.len {
+                self.pages
+                    .resize_with(addr / PAGE_SIZE + 1, || Arc::new([0; PAGE_SIZE]));
+                self.len = addr + 1;
+            }
+            &mut self.pages[addr / PAGE_SIZE]
+        } else {
+            self.sparse
+                .entry(addr / PAGE_SIZE)
+                .or_insert_with(|| Arc::new([0; PAGE_SIZE]))
+        };
+        // Copies the page if it's shared.
+        Arc::make_mut(page)[addr % PAGE_SIZE] = val;
+    }
+
+    /// Number of pages shared with another memory.
+    #[cfg(test)]
+    pub(crate) fn shared_pages(&self) -> usize {
+        self.pages
+            .iter()
+            .chain(self.sparse.values())
+            .filter(|page| Arc::strong_count(page) > 1)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense() {
+        let mut mem = Memory::new(&[1, 2, 3]);
+        assert_eq!(mem.read(1), 2);
+        assert_eq!(mem.read(10), 0);
+        mem.write(10, 5);
+        assert_eq!(mem.read(10), 5);
+        assert_eq!(mem.to_vec(), [1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 5]);
+        mem.write(3000, 6);
+        assert_eq!(mem.len(), 3001);
+        assert_eq!(mem.read(3000), 6);
+    }
+
+    #[test]
+    fn test_sparse() {
+        let mut mem = Memory::new(&[1, 2, 3]);
+        let addr = 1_000_000_000_000;
+        assert_eq!(mem.read(addr), 0);
+        mem.write(addr, 7);
+        mem.write(addr + 1, 8);
+        assert_eq!(mem.read(addr), 7);
+        assert_eq!(mem.read(addr + 1), 8);
+        assert_eq!(mem.read(addr + PAGE_SIZE), 0);
+        assert_eq!(mem.sparse.len(), 1);
+        assert_eq!(mem.len(), 3);
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let values: Vec<i64> = (0..3000).collect();
+        let mut mem = Memory::new(&values);
+        mem.write(2_000_000, 1);
+        let mut copy = mem.clone();
+        assert_eq!(copy.shared_pages(), 4);
+
+        copy.write(5, 50);
+        assert_eq!(copy.shared_pages(), 3);
+        assert_eq!(copy.read(5), 50);
+        assert_eq!(mem.read(5), 5);
+
+        copy.write(2_000_001, 2);
+        assert_eq!(copy.shared_pages(), 2);
+        assert_eq!(mem.read(2_000_001), 0);
+        assert_eq!(mem.read(2_000_000), 1);
+        assert_eq!(copy.read(2_000_000), 1);
+    }
+
+    #[test]
+    fn test_fork() {
+        let computer = crate::IntcodeComputer::build("3,0,4,0,99");
+        let mut fork = computer.fork();
+        assert_eq!(fork.base.mem.shared_pages(), 1);
+        fork.io.add_input(5);
+        fork.exec();
+        assert_eq!(fork.io.get_output(), Some(5));
+        assert_eq!(fork.base.mem.shared_pages(), 0);
+        assert_eq!(computer.dump_memory(), "3,0,4,0,99");
+    }
+
+    #[test]
+    fn test_query() {
+        // Outputs the sum and the product of its two inputs.
+        let computer =
+            crate::IntcodeComputer::build("3,20,3,21,1,20,21,22,4,22,2,20,21,22,4,22,99");
+        assert_eq!(computer.query(&[3, 4]), [7, 12]);
+        assert_eq!(computer.query(&[5, 6]), [11, 30]);
+        assert!(!computer.is_halted());
+    }
+}
@@ -0,0 +1,309 @@
+//! Network of Intcode computers exchanging packets, as in day 23.
+//!
+//! Each computer first reads its address. It then sends packets by outputting three values,
+//! the destination address, X and Y, and receives them as two input values, X and Y.
+//! When it has no packet to receive, it reads -1.
+use std::collections::VecDeque;
+
+use crate::{Bus, IntcodeComputer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Packet {
+    #[must_use]
+    pub fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A computer sent a packet.
+    Sent {
+        from: usize,
+        to: usize,
+        packet: Packet,
+    },
+    /// The handler delivered a packet to a computer.
+    Injected { to: usize, packet: Packet },
+    /// No computer had anything to do in the last round.
+    Idle,
+}
+
+/// What the handler wants the network to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    /// Deliver a packet to the computer at this address.
+    Deliver(usize, Packet),
+    /// Stop the network, with this result.
+    Stop(i64),
+}
+
+/// Handler of the addresses that don't belong to a computer of the network, like the NAT.
+pub trait Handler {
+    /// Called when a packet is sent to an address outside the network.
+    fn on_packet(&mut self, addr: usize, packet: Packet) -> Action;
+
+    /// Called when the network is idle.
+    fn on_idle(&mut self) -> Action;
+}
+
+// Bus of a computer of the network.
+#[derive(Debug, Clone, Default)]
+struct NodeBus {
+    input: VecDeque<i64>,
+    output: Vec<i64>,
+    // If the computer has read -1 in this round.
+    read_empty: bool,
+}
+
+impl Bus for NodeBus {
+    fn read(&mut self) -> Option<i64> {
+        if let Some(v) = self.input.pop_front() {
+            Some(v)
+        } else if self.read_empty {
+            // Let the other computers run.
+            None
+        } else {
+            self.read_empty = true;
+            Some(-1)
+        }
+    }
+
+    fn write(&mut self, v: i64) {
+        self.output.push(v);
+    }
+}
+
+/// A network of computers, scheduled in a deterministic round-robin way.
+pub struct Network {
+    nodes: Vec<IntcodeComputer<NodeBus>>,
+    events: Vec<Event>,
+}
+
+impl Network {
+    /// Creates a network of `size` copies of the computer, with the addresses 0 to `size - 1`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the size is too big for the addresses to be Intcode values.
+    #[must_use]
+    pub fn new<B: Bus + Clone>(computer: &IntcodeComputer<B>, size: usize) -> Self {
+        let nodes = (0..size)
+            .map(|addr| {
+                let mut bus = NodeBus::default();
+                bus.input.push_back(i64::try_from(addr).unwrap());
+                computer.clone().with_bus(bus)
+            })
+            .collect();
+        Self {
+            nodes,
+            events: Vec::new(),
+        }
+    }
+
+    /// All that happened in the network so far.
+    #[must_use]
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    fn deliver(&mut self, to: usize, packet: Packet) {
+        let input = &mut self.nodes[to].io.input;
+        input.push_back(packet.x);
+        input.push_back(packet.y);
+    }
+
+    // Executes a computer until it waits for input, and routes the packets it sent.
+    // Returns the result if the handler stops the network.
+    fn run_node<H: Handler>(&mut self, from: usize, handler: &mut H) -> Option<i64> {
+        let node = &mut self.nodes[from];
+        node.exec();
+        // The values of a packet not fully sent yet stay in the output.
+        let complete = node.io.output.len() / 3 * 3;
+        let output: Vec<_> = node.io.output.drain(..complete).collect();
+        for chunk in output.chunks_exact(3) {
+            let to = usize::try_from(chunk[0]).unwrap();
+            let packet = Packet::new(chunk[1], chunk[2]);
+            self.events.push(Event::Sent { from, to, packet });
+            if to < self.nodes.len() {
+                self.deliver(to, packet);
+            } else if let Some(result) = self.apply(handler.on_packet(to, packet)) {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    // Applies the action of the handler. Returns the result if the network must stop.
+    fn apply(&mut self, action: Action) -> Option<i64> {
+        match action {
+            Action::Continue => None,
+            Action::Deliver(to, packet) => {
+                self.events.push(Event::Injected { to, packet });
+                self.deliver(to, packet);
+                None
+            }
+            Action::Stop(result) => Some(result),
+        }
+    }
+
+    /// Runs the network until the handler stops it, returning its result.
+    /// Returns None if all the computers halted.
+    ///
+    /// The network is idle when all the computers read -1 in a round, none sent a packet,
+    /// and all the input queues are empty.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if a program is invalid or sends a packet to a negative address.
+    pub fn run<H: Handler>(&mut self, handler: &mut H) -> Option<i64> {
+        loop {
+            if self.nodes.iter().all(IntcodeComputer::is_halted) {
+                return None;
+            }
+
+            let events_count = self.events.len();
+            for addr in 0..self.nodes.len() {
+                self.nodes[addr].io.read_empty = false;
+                if self.nodes[addr].is_halted() {
+                    continue;
+                }
+                if let Some(result) = self.run_node(addr, handler) {
+                    return Some(result);
+                }
+            }
+
+            let idle = self.events.len() == events_count
+                && self
+                    .nodes
+                    .iter()
+                    .all(|n| n.is_halted() || (n.io.read_empty && n.io.input.is_empty()));
+            if idle {
+                self.events.push(Event::Idle);
+                if let Some(result) = self.apply(handler.on_idle()) {
+                    return Some(result);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each computer forwards the packets it receives to the next address, adding 1 to Y.
+    // The last one sends them to 255.
+    const FORWARD: &str = "3,100,3,101,1008,101,-1,103,1005,103,2,3,102,1001,100,1,104,\
+        1008,104,3,105,1006,105,28,1101,255,0,104,4,104,4,101,1001,102,1,102,4,102,1105,1,2";
+
+    // Injects a packet when the network is idle, stops on the first packet to 255.
+    struct Tester {
+        injected: bool,
+    }
+
+    impl Handler for Tester {
+        fn on_packet(&mut self, addr: usize, packet: Packet) -> Action {
+            assert_eq!(addr, 255);
+            Action::Stop(packet.y)
+        }
+
+        fn on_idle(&mut self) -> Action {
+            assert!(!self.injected);
+            self.injected = true;
+            Action::Deliver(0, Packet::new(7, 10))
+        }
+    }
+
+    #[test]
+    fn test_network() {
+        let computer = IntcodeComputer::build(FORWARD);
+        let mut network = Network::new(&computer, 3);
+        let result = network.run(&mut Tester { injected: false });
+        assert_eq!(result, Some(13));
+        assert_eq!(
+            network.events(),
+            [
+                Event::Idle,
+                Event::Injected {
+                    to: 0,
+                    packet: Packet::new(7, 10)
+                },
+                Event::Sent {
+                    from: 0,
+                    to: 1,
+                    packet: Packet::new(7, 11)
+                },
+                Event::Sent {
+                    from: 1,
+                    to: 2,
+                    packet: Packet::new(7, 12)
+                },
+                Event::Sent {
+                    from: 2,
+                    to: 255,
+                    packet: Packet::new(7, 13)
+                },
+            ]
+        );
+    }
+
+    // Sends back to the first computer all the packets to outside addresses.
+    struct Returner;
+
+    impl Handler for Returner {
+        fn on_packet(&mut self, _addr: usize, packet: Packet) -> Action {
+            Action::Deliver(0, packet)
+        }
+
+        fn on_idle(&mut self) -> Action {
+            Action::Continue
+        }
+    }
+
+    #[test]
+    fn test_several_outside_packets() {
+        // Sends two packets to 255 and halts.
+        let computer = IntcodeComputer::build("104,255,104,1,104,2,104,255,104,3,104,4,99");
+        let mut network = Network::new(&computer, 1);
+        assert_eq!(network.run(&mut Returner), None);
+        let injected: Vec<_> = network
+            .events()
+            .iter()
+            .filter_map(|e| match e {
+                Event::Injected { packet, .. } => Some(*packet),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(injected, [Packet::new(1, 2), Packet::new(3, 4)]);
+    }
+
+    #[test]
+    fn test_packet_sent_in_two_rounds() {
+        // Outputs the address and X, waits for input, then outputs Y.
+        let computer = IntcodeComputer::build("3,100,104,255,104,5,3,101,3,101,104,6,99");
+        let mut network = Network::new(&computer, 1);
+        assert_eq!(network.run(&mut Tester { injected: false }), Some(6));
+        assert_eq!(
+            network.events().last(),
+            Some(&Event::Sent {
+                from: 0,
+                to: 255,
+                packet: Packet::new(5, 6)
+            })
+        );
+    }
+
+    #[test]
+    fn test_halted_network() {
+        let computer = IntcodeComputer::build("3,0,99");
+        let mut network = Network::new(&computer, 2);
+        assert_eq!(network.run(&mut Tester { injected: false }), None);
+    }
+}
@@ -0,0 +1,161 @@
+//! Tests of each opcode, with programs written in assembly, on both computers.
+use itertools::Itertools;
+
+use crate::{assemble, disassemble, FastIntcodeComputer, IntcodeComputer};
+
+// Runs the program with the inputs on both computers, checking they output the same.
+// Also checks the program round-trips through the disassembler.
+fn run(source: &str, inputs: &[i64]) -> Vec<i64> {
+    let values = assemble(source).unwrap();
+    assert_eq!(assemble(&disassemble(&values)), Ok(values.clone()));
+    let code = values.iter().join(",");
+    let output = IntcodeComputer::build(&code).query(inputs);
+
+    let mut fast = FastIntcodeComputer::build(&code);
+    fast.io.extend_input(inputs);
+    fast.exec();
+    let fast_output: Vec<i64> = std::iter::from_fn(|| fast.io.get_output()).collect();
+    assert_eq!(output, fast_output);
+    output
+}
+
+// Applies the operation to the two inputs, in each combination of modes.
+const BINARY_OPERATION: &str = r"
+        in [a]
+        in [b]
+        OP [a], [b] -> [c]
+        out [c]
+        OP [a], #3 -> [c]
+        out [c]
+        arb #a
+        OP #3, [rb+1] -> [rb+2]
+        out [rb+2]
+        hlt
+a:      .data 0
+b:      .data 0
+c:      .data 0
+";
+
+fn binary_operation(op: &str, a: i64, b: i64) -> Vec<i64> {
+    run(&BINARY_OPERATION.replace("OP", op), &[a, b])
+}
+
+#[test]
+fn test_add() {
+    assert_eq!(binary_operation("add", 5, 7), [12, 8, 10]);
+    assert_eq!(binary_operation("add", -5, 2), [-3, -2, 5]);
+}
+
+#[test]
+fn test_mul() {
+    assert_eq!(binary_operation("mul", 5, 7), [35, 15, 21]);
+    assert_eq!(binary_operation("mul", -4, 0), [0, -12, 0]);
+}
+
+#[test]
+fn test_lt() {
+    assert_eq!(binary_operation("lt", 2, 3), [1, 1, 0]);
+    assert_eq!(binary_operation("lt", 3, 3), [0, 0, 0]);
+    assert_eq!(binary_operation("lt", 4, 5), [1, 0, 1]);
+}
+
+#[test]
+fn test_eq() {
+    assert_eq!(binary_operation("eq", 3, 3), [1, 1, 1]);
+    assert_eq!(binary_operation("eq", 3, -3), [0, 1, 0]);
+}
+
+#[test]
+fn test_in_out() {
+    let source = r"
+        in [x]
+        out [x]
+        out #-8
+        arb #x
+        in [rb+0]
+        out [rb]
+        hlt
+x:      .data 0
+";
+    assert_eq!(run(source, &[4, 9]), [4, -8, 9]);
+    // Waits for the second input.
+    assert_eq!(run(source, &[4]), [4, -8]);
+}
+
+#[test]
+fn test_jt_jf() {
+    // Outputs 1 if the input isn't zero, then 2 if it's zero.
+    let source = r"
+        in [x]
+        jt [x], nonzero
+        jf #0, next
+nonzero:
+        out #1
+next:
+        jf [x], zero
+        hlt
+zero:
+        out #2
+        ; Jump to an address read from memory.
+        jt #1, [target]
+        out #3
+end:    hlt
+x:      .data 0
+target: .data end
+";
+    assert_eq!(run(source, &[5]), [1]);
+    assert_eq!(run(source, &[0]), [2]);
+}
+
+#[test]
+fn test_arb() {
+    // Sums the values of the array, going through it with the relative base.
+    let source = r"
+        arb #array
+loop:   jf [rb+0], end
+        add [sum], [rb+0] -> [sum]
+        arb #1
+        jt #1, loop
+end:    arb #-2
+        out [rb-1]
+        out [rb+1]
+        out [sum]
+        hlt
+sum:    .data 0
+array:  .data 3, 5, 9, 0
+";
+    assert_eq!(run(source, &[]), [3, 9, 17]);
+}
+
+#[test]
+fn test_hlt() {
+    assert_eq!(run("out #1\nhlt\nout #2", &[]), [1]);
+}
+
+#[test]
+fn test_strings() {
+    let source = r#"
+        arb #msg
+loop:   jf [rb+0], end
+        out [rb+0]
+        arb #1
+        jt #1, loop
+end:    hlt
+msg:    .data "Hi!\n", 0
+"#;
+    assert_eq!(run(source, &[]), [72, 105, 33, 10]);
+}
+
+#[test]
+fn test_self_modifying() {
+    // Increments the value of its own output instruction, until it's 3.
+    let source = r"
+patch:  out #1
+        add #1, [patch+1] -> [patch+1]
+        eq [patch+1], #3 -> [done]
+        jf [done], patch
+        hlt
+done:   .data 0
+";
+    assert_eq!(run(source, &[]), [1, 2]);
+}
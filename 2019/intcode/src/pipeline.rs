@@ -0,0 +1,182 @@
+//! Computers connected by their input and output, like the amplifiers of day 7.
+//!
+use std::{num::NonZeroUsize, thread};
+
+use itertools::Itertools;
+
+use crate::IntcodeComputer;
+
+/// A directed graph of computers, where the outputs of a computer are sent as input
+/// to all the computers it's connected to.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    nodes: Vec<IntcodeComputer>,
+    // For each computer, the computers its output goes to.
+    edges: Vec<Vec<usize>>,
+    last_outputs: Vec<Option<i64>>,
+}
+
+impl Pipeline {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Computers in series, each one getting its phase setting as first input.
+    /// The first computer input and the last computer output are not connected.
+    #[must_use]
+    pub fn chain(computer: &IntcodeComputer, phase_settings: &[i64]) -> Self {
+        let mut pipeline = Self::new();
+        for phase_setting in phase_settings {
+            let node = pipeline.add(computer.clone());
+            pipeline.add_input(node, *phase_setting);
+            if node > 0 {
+                pipeline.connect(node - 1, node);
+            }
+        }
+        pipeline
+    }
+
+    /// Computers in series, with the output of the last one going to the first one.
+    #[must_use]
+    pub fn feedback_loop(computer: &IntcodeComputer, phase_settings: &[i64]) -> Self {
+        let mut pipeline = Self::chain(computer, phase_settings);
+        if !phase_settings.is_empty() {
+            pipeline.connect(phase_settings.len() - 1, 0);
+        }
+        pipeline
+    }
+
+    /// Adds a computer, returning its index.
+    pub fn add(&mut self, computer: IntcodeComputer) -> usize {
+        self.nodes.push(computer);
+        self.edges.push(Vec::new());
+        self.last_outputs.push(None);
+        self.nodes.len() - 1
+    }
+
+    /// Sends the output of computer `from` to the input of computer `to`.
+    pub fn connect(&mut self, from: usize, to: usize) {
+        self.edges[from].push(to);
+    }
+
+    pub fn add_input(&mut self, node: usize, input: i64) {
+        self.nodes[node].io.add_input(input);
+    }
+
+    /// Last value output by this computer.
+    #[must_use]
+    pub fn last_output(&self, node: usize) -> Option<i64> {
+        self.last_outputs[node]
+    }
+
+    /// Runs the computers in turn until they all halt, or are all waiting for input.
+    /// Returns the last value output by the last computer added.
+    pub fn run(&mut self) -> Option<i64> {
+        loop {
+            let mut progress = false;
+            for node in 0..self.nodes.len() {
+                let computer = &mut self.nodes[node];
+                if computer.is_halted() {
+                    continue;
+                }
+                computer.exec();
+                progress |= computer.is_halted();
+                while let Some(output) = self.nodes[node].io.get_output() {
+                    progress = true;
+                    self.last_outputs[node] = Some(output);
+                    for to in &self.edges[node] {
+                        self.nodes[*to].io.add_input(output);
+                    }
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+        self.last_outputs.last().copied().flatten()
+    }
+}
+
+/// Tries all the permutations of the values in parallel, returning the one giving the biggest result.
+///
+/// # Panics
+///
+/// Will panic if `f` panics.
+pub fn best_permutation<F>(values: &[i64], f: F) -> Option<(Vec<i64>, i64)>
+where
+    F: Fn(&[i64]) -> i64 + Sync,
+{
+    let permutations: Vec<Vec<i64>> = values.iter().copied().permutations(values.len()).collect();
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_size = permutations.len().div_ceil(threads).max(1);
+    thread::scope(|s| {
+        let handles: Vec<_> = permutations
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(|| {
+                    chunk
+                        .iter()
+                        .map(|p| (p.clone(), f(p)))
+                        .max_by_key(|(_, result)| *result)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|h| h.join().unwrap())
+            .max_by_key(|(_, result)| *result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads a value and outputs it multiplied by 2, until it reads 0.
+    const DOUBLE: &str = "3,20,1002,20,2,21,4,21,1005,20,0,99";
+    // Reads two values and outputs their sum.
+    const SUM: &str = "3,20,3,21,1,20,21,22,4,22,99";
+
+    #[test]
+    fn test_chain() {
+        let computer = IntcodeComputer::build(SUM);
+        let mut pipeline = Pipeline::chain(&computer, &[1, 2, 3]);
+        pipeline.add_input(0, 10);
+        assert_eq!(pipeline.run(), Some(16));
+        assert_eq!(pipeline.last_output(0), Some(11));
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        // Each computer adds its phase setting to what it receives, until reaching 10.
+        let computer =
+            IntcodeComputer::build("3,30,3,31,1,30,31,31,4,31,1007,31,10,32,1005,32,2,99");
+        let mut pipeline = Pipeline::feedback_loop(&computer, &[1, 2]);
+        pipeline.add_input(0, 0);
+        assert_eq!(pipeline.run(), Some(12));
+    }
+
+    #[test]
+    fn test_graph() {
+        // A doubles its input, and sends it to B and C which double it again, D sums them.
+        let mut pipeline = Pipeline::new();
+        let a = pipeline.add(IntcodeComputer::build(DOUBLE));
+        let b = pipeline.add(IntcodeComputer::build(DOUBLE));
+        let c = pipeline.add(IntcodeComputer::build(DOUBLE));
+        let d = pipeline.add(IntcodeComputer::build(SUM));
+        pipeline.connect(a, b);
+        pipeline.connect(a, c);
+        pipeline.connect(b, d);
+        pipeline.connect(c, d);
+        pipeline.add_input(a, 5);
+        assert_eq!(pipeline.run(), Some(40));
+        assert_eq!(pipeline.last_output(b), Some(20));
+    }
+
+    #[test]
+    fn test_best_permutation() {
+        let best = best_permutation(&[1, 2, 3], |p| p[0] * 100 + p[1] * 10 + p[2]);
+        assert_eq!(best, Some((vec![3, 2, 1], 321)));
+    }
+}
@@ -0,0 +1,166 @@
+//! Regression tests with the example programs of the days building the Intcode computer.
+
+mod day02 {
+    use crate::IntcodeComputer;
+
+    fn exec(code: &str) -> String {
+        let mut computer = IntcodeComputer::build(code);
+        computer.exec();
+        computer.dump_memory()
+    }
+
+    #[test]
+    fn test_exec() {
+        assert_eq!(
+            exec("1,9,10,3,2,3,11,0,99,30,40,50"),
+            "3500,9,10,70,2,3,11,0,99,30,40,50"
+        );
+        assert_eq!(exec("1,0,0,0,99"), "2,0,0,0,99");
+        assert_eq!(exec("2,3,0,3,99"), "2,3,0,6,99");
+        assert_eq!(exec("2,4,4,5,99,0"), "2,4,4,5,99,9801");
+        assert_eq!(exec("1,1,1,4,99,5,6,0,99"), "30,1,1,4,2,5,6,0,99");
+    }
+}
+
+mod day05 {
+    use crate::IntcodeComputer;
+
+    fn run_io(code: &str, input: i64) -> i64 {
+        let mut computer = IntcodeComputer::build(code);
+        computer.run(input)
+    }
+
+    #[test]
+    fn test_parameter_modes() {
+        let mut computer = IntcodeComputer::build("1002,4,3,4,33");
+        computer.exec();
+        assert_eq!(computer.dump_memory(), "1002,4,3,4,99");
+
+        let mut computer = IntcodeComputer::build("1101,100,-1,4,0");
+        computer.exec();
+        assert_eq!(computer.dump_memory(), "1101,100,-1,4,99");
+    }
+
+    #[test]
+    fn test_cmp() {
+        let c = "3,9,8,9,10,9,4,9,99,-1,8";
+        assert_eq!(run_io(c, 8), 1);
+        assert_eq!(run_io(c, 3), 0);
+        let c = "3,9,7,9,10,9,4,9,99,-1,8";
+        assert_eq!(run_io(c, 3), 1);
+        assert_eq!(run_io(c, 9), 0);
+        let c = "3,3,1108,-1,8,3,4,3,99";
+        assert_eq!(run_io(c, 8), 1);
+        assert_eq!(run_io(c, 3), 0);
+        let c = "3,3,1107,-1,8,3,4,3,99";
+        assert_eq!(run_io(c, 3), 1);
+        assert_eq!(run_io(c, 9), 0);
+    }
+
+    #[test]
+    fn test_jump() {
+        let c = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
+        assert_eq!(run_io(c, 0), 0);
+        assert_eq!(run_io(c, 4), 1);
+        let c = "3,3,1105,-1,9,1101,0,0,12,4,12,99,1";
+        assert_eq!(run_io(c, 0), 0);
+        assert_eq!(run_io(c, 4), 1);
+    }
+
+    #[test]
+    fn test_larger_program() {
+        let c = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+        assert_eq!(run_io(c, 1), 999);
+        assert_eq!(run_io(c, 8), 1000);
+        assert_eq!(run_io(c, 45), 1001);
+    }
+}
+
+mod day07 {
+    use itertools::Itertools;
+
+    use crate::IntcodeComputer;
+
+    fn build_amps(computer: &IntcodeComputer, phase_settings: &[i64]) -> Vec<IntcodeComputer> {
+        phase_settings
+            .iter()
+            .map(|phase_setting| {
+                let mut amp = computer.clone();
+                amp.io.add_input(*phase_setting);
+                amp
+            })
+            .collect()
+    }
+
+    // Runs the amplifiers in a loop until the last one halts, returning its last output.
+    fn thruster_signal(computer: &IntcodeComputer, phase_settings: &[i64]) -> i64 {
+        let mut amps = build_amps(computer, phase_settings);
+        let mut signal = 0;
+        while !amps.last().unwrap().is_halted() {
+            for amp in &mut amps {
+                signal = amp.run(signal);
+            }
+        }
+        signal
+    }
+
+    fn max_thruster_signal(code: &str, phases: std::ops::RangeInclusive<i64>) -> i64 {
+        let computer = IntcodeComputer::build(code);
+        phases
+            .permutations(5)
+            .map(|phase_settings| thruster_signal(&computer, &phase_settings))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_max_thruster_signal() {
+        assert_eq!(
+            max_thruster_signal("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0", 0..=4),
+            43210
+        );
+        assert_eq!(
+            max_thruster_signal(
+                "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+                0..=4
+            ),
+            54321
+        );
+        assert_eq!(max_thruster_signal("3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0", 0..=4), 65210);
+    }
+
+    #[test]
+    fn test_max_thruster_signal_with_feedback() {
+        assert_eq!(
+            max_thruster_signal(
+                "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+                5..=9
+            ),
+            139_629_729
+        );
+        assert_eq!(max_thruster_signal("3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10", 5..=9), 18216);
+    }
+}
+
+mod day09 {
+    use crate::IntcodeComputer;
+
+    #[test]
+    fn test_large_numbers() {
+        let mut computer = IntcodeComputer::build("1102,34915192,34915192,7,4,7,99,0");
+        computer.exec();
+        assert_eq!(computer.io.get_output().unwrap(), 1_219_070_632_396_864);
+
+        let mut computer = IntcodeComputer::build("104,1125899906842624,99");
+        computer.exec();
+        assert_eq!(computer.io.get_output().unwrap(), 1_125_899_906_842_624);
+    }
+
+    #[test]
+    fn test_quine() {
+        let code = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let mut computer = IntcodeComputer::build(code);
+        computer.exec();
+        assert_eq!(computer.io.dump_output(), code);
+    }
+}
@@ -0,0 +1,1346 @@
+//! Ahead-of-time translation of Intcode programs into Rust or C.
+//!
+//! Once the instructions are known, there is nothing left to decode: each basic block becomes
+//! straight-line code, an arm of a `match` in Rust, or a label reached with `goto` in C.
+//! This only holds if the program doesn't write over its own code. `check_writes()` looks at
+//! every instruction found by following the control flow:
+//! - A write in position mode has a known address, so it's checked statically.
+//! - A write in relative mode depends on the relative base, so the translated code checks its
+//!   address at runtime.
+//!
+//! The translated code stops before any instruction it can't execute: one the program writes
+//! over, a write into the code, a jump to an address that isn't the start of a block, or an
+//! address above `MAX_ADDRESS`. It then returns `Running`, with the instruction pointer on
+//! that instruction, for an interpreter to take over with `IntcodeComputer::resume()`.
+//! The native programs hand their state over on stderr, and `Native` continues them this way.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fmt::{self, Write as _},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use itertools::Itertools;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    disassembler::{analyze, instruction_str, Analysis},
+    Bus, ExecState, Instruction, IntcodeComputer, Param,
+};
+
+/// Addresses the translated code supports. It stops on any access above.
+pub const MAX_ADDRESS: usize = 1 << 20;
+
+// Exit code of the native programs stopping on an instruction they can't execute.
+// They then print on stderr the message, their instruction pointer, relative base and
+// the number of inputs they read on one line, and their memory on the next one.
+const EXIT_UNSUPPORTED: i32 = 3;
+
+/// Instruction writing in position mode into the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeWrite {
+    /// Address of the writing instruction.
+    pub ip: usize,
+    /// Address written.
+    pub addr: usize,
+    /// Address of the instruction that gets modified.
+    pub target: usize,
+}
+
+/// Result of `check_writes()`.
+#[derive(Debug, Clone)]
+pub struct WriteCheck {
+    analysis: Analysis,
+    /// Writes into the code, found statically.
+    pub code_writes: Vec<CodeWrite>,
+    /// Address of the instructions writing in relative mode, checked at runtime.
+    pub relative_writes: Vec<usize>,
+}
+
+// Parameter an instruction writes to.
+fn written(ins: Instruction) -> Option<Param> {
+    match ins {
+        Instruction::Add(_, _, p)
+        | Instruction::Mult(_, _, p)
+        | Instruction::LessThan(_, _, p)
+        | Instruction::Equal(_, _, p)
+        | Instruction::Input(p) => Some(p),
+        _ => None,
+    }
+}
+
+/// Finds the instructions writing into the code of the program.
+///
+/// The code is found like for the disassembler, by following the control flow from address 0.
+#[must_use]
+pub fn check_writes(mem: &[i64]) -> WriteCheck {
+    let analysis = analyze(mem);
+    // Start of the instruction covering each address.
+    let mut owner = vec![None; mem.len()];
+    for (addr, ins) in &analysis.code {
+        owner[*addr..addr + ins.length()].fill(Some(*addr));
+    }
+
+    let mut code_writes = Vec::new();
+    let mut relative_writes = Vec::new();
+    for (ip, ins) in &analysis.code {
+        match written(*ins) {
+            Some(Param::Position(addr)) => {
+                if let Some(Some(target)) = owner.get(addr) {
+                    code_writes.push(CodeWrite {
+                        ip: *ip,
+                        addr,
+                        target: *target,
+                    });
+                }
+            }
+            Some(Param::Relative(_)) => relative_writes.push(*ip),
+            _ => {}
+        }
+    }
+    WriteCheck {
+        analysis,
+        code_writes,
+        relative_writes,
+    }
+}
+
+impl WriteCheck {
+    /// Whether the program is proven not to write into its code in position mode.
+    /// Writes in relative mode can still hit the code, the translated code checks them.
+    #[must_use]
+    pub fn is_safe(&self) -> bool {
+        self.code_writes.is_empty()
+    }
+
+    // Addresses of the instructions the program writes over.
+    fn modified(&self) -> BTreeSet<usize> {
+        self.code_writes.iter().map(|w| w.target).collect()
+    }
+
+    // Ranges of addresses covered by instructions.
+    fn code_ranges(&self) -> Vec<(usize, usize)> {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for (addr, ins) in &self.analysis.code {
+            let end = addr + ins.length() - 1;
+            match ranges.last_mut() {
+                Some(last) if last.1 + 1 == *addr => last.1 = end,
+                _ => ranges.push((*addr, end)),
+            }
+        }
+        ranges
+    }
+}
+
+impl fmt::Display for WriteCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} instructions, {} writing in relative mode (checked at runtime)",
+            self.analysis.code.len(),
+            self.relative_writes.len()
+        )?;
+        if self.is_safe() {
+            writeln!(f, "No write into the code in position mode")
+        } else {
+            writeln!(f, "Writes into the code:")?;
+            for w in &self.code_writes {
+                writeln!(
+                    f,
+                    "  {}: writes at {}, in the instruction at {}",
+                    w.ip, w.addr, w.target
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// Instructions executed in sequence. Only the first one is jumped to.
+#[derive(Debug)]
+struct Block {
+    instructions: Vec<(usize, Instruction)>,
+    // Address to continue at, unless the last instruction always jumps or stops.
+    next: Option<usize>,
+}
+
+fn always_jumps(ins: Instruction) -> bool {
+    match ins {
+        Instruction::JumpIfTrue(Param::Immediate(v), _) => v != 0,
+        Instruction::JumpIfFalse(Param::Immediate(v), _) => v == 0,
+        _ => false,
+    }
+}
+
+// Splits the code into basic blocks, indexed by their address.
+// Blocks start at jump targets and after jumps, as well as after the modified instructions,
+// where the interpreter can give back the control.
+fn blocks(check: &WriteCheck) -> BTreeMap<usize, Block> {
+    let modified = check.modified();
+    let mut leaders = check.analysis.labels.clone();
+    leaders.insert(0);
+    for (addr, ins) in &check.analysis.code {
+        if matches!(
+            ins,
+            Instruction::JumpIfTrue(..) | Instruction::JumpIfFalse(..)
+        ) || modified.contains(addr)
+        {
+            leaders.insert(addr + ins.length());
+        }
+    }
+
+    let mut blocks: BTreeMap<usize, Block> = BTreeMap::new();
+    let mut current: Option<usize> = None;
+    for (addr, ins) in &check.analysis.code {
+        let continues = current
+            .is_some_and(|start| blocks[&start].next == Some(*addr) && !leaders.contains(addr));
+        if !continues {
+            current = Some(*addr);
+            blocks.insert(
+                *addr,
+                Block {
+                    instructions: Vec::new(),
+                    next: None,
+                },
+            );
+        }
+        let block = blocks.get_mut(&current.unwrap()).unwrap();
+        block.instructions.push((*addr, *ins));
+        let stops =
+            matches!(ins, Instruction::Halt) || always_jumps(*ins) || modified.contains(addr);
+        block.next = (!stops).then_some(addr + ins.length());
+    }
+    blocks
+}
+
+// What differs between the languages.
+trait Syntax {
+    fn int(&self, v: i64) -> String;
+    // Sets the variable to the value.
+    fn assign(&self, var: &str, value: &str) -> String;
+    // Stops before the instruction at `ip`, in the state.
+    fn exit(&self, ip: usize, state: &ExecState) -> String;
+    // Sets the variable to the address, stopping if it's negative or too high.
+    fn address(&self, ip: usize, var: &str, value: &str) -> String;
+    fn if_then(&self, cond: &str, code: &str) -> String;
+    // Value in the program image.
+    fn image(&self, addr: usize) -> String;
+    // Value at an address, that may be after the program.
+    fn read(&self, addr: &str) -> String;
+    fn write(&self, addr: &str, value: &str) -> String;
+    fn add(&self, x: &str, y: &str) -> String;
+    fn mul(&self, x: &str, y: &str) -> String;
+    fn bool_to_int(&self, cond: &str) -> String;
+    // Reads the input into the variable, stopping if there is none.
+    fn input(&self, ip: usize, var: &str) -> String;
+    fn output(&self, value: &str) -> String;
+    // Goes to the block at this address.
+    fn goto(&self, target: usize) -> String;
+    // Goes to an address only known at runtime.
+    fn jump(&self, ip: usize, target: &str) -> String;
+    fn comment(&self, s: &str) -> String;
+}
+
+struct Translator<'a, S: Syntax> {
+    syntax: S,
+    blocks: &'a BTreeMap<usize, Block>,
+    modified: BTreeSet<usize>,
+    len: usize,
+}
+
+impl<S: Syntax> Translator<'_, S> {
+    fn rb_plus(&self, offset: i64) -> String {
+        if offset < 0 && offset != i64::MIN {
+            format!("rb - {}", self.syntax.int(-offset))
+        } else {
+            format!("rb + {}", self.syntax.int(offset))
+        }
+    }
+
+    fn goto(&self, ip: usize, target: i64) -> String {
+        match usize::try_from(target) {
+            Ok(t) if self.blocks.contains_key(&t) => self.syntax.goto(t),
+            Ok(t) if t < MAX_ADDRESS => self.syntax.exit(t, &ExecState::Running),
+            // The interpreter reports the error.
+            _ => self.syntax.exit(ip, &ExecState::Running),
+        }
+    }
+
+    // Code setting the variable to the parameter value.
+    fn operand(&self, ip: usize, p: Param, var: &str) -> Vec<String> {
+        let s = &self.syntax;
+        match p {
+            Param::Immediate(v) => vec![s.assign(var, &s.int(v))],
+            Param::Position(addr) if addr < self.len => vec![s.assign(var, &s.image(addr))],
+            Param::Position(addr) if addr < MAX_ADDRESS => {
+                vec![s.assign(var, &s.read(&addr.to_string()))]
+            }
+            Param::Position(_) => vec![s.exit(ip, &ExecState::Running)],
+            Param::Relative(offset) => vec![
+                s.address(ip, "a", &self.rb_plus(offset)),
+                s.assign(var, &s.read("a")),
+            ],
+        }
+    }
+
+    // Code checking the parameter can be written, and code writing the value to it.
+    fn destination(&self, ip: usize, p: Param, value: &str) -> (Vec<String>, String) {
+        let s = &self.syntax;
+        match p {
+            Param::Position(addr) if addr < self.len => {
+                (vec![], format!("{} = {value};", s.image(addr)))
+            }
+            Param::Position(addr) if addr < MAX_ADDRESS => {
+                (vec![], s.write(&addr.to_string(), value))
+            }
+            Param::Relative(offset) => (
+                vec![
+                    s.address(ip, "w", &self.rb_plus(offset)),
+                    s.if_then("is_code(w)", &s.exit(ip, &ExecState::Running)),
+                ],
+                s.write("w", value),
+            ),
+            // Too high, or in immediate mode, which is an error.
+            Param::Position(_) | Param::Immediate(_) => {
+                (vec![s.exit(ip, &ExecState::Running)], String::new())
+            }
+        }
+    }
+
+    fn instruction(&self, ip: usize, ins: Instruction) -> Vec<String> {
+        let s = &self.syntax;
+        let mut code = vec![s.comment(&format!("{ip}: {}", instruction_str(ins, self.len)))];
+        if self.modified.contains(&ip) {
+            code.push(s.exit(ip, &ExecState::Running));
+            return code;
+        }
+        match ins {
+            Instruction::Add(x, y, d)
+            | Instruction::Mult(x, y, d)
+            | Instruction::LessThan(x, y, d)
+            | Instruction::Equal(x, y, d) => {
+                let value = match ins {
+                    Instruction::Add(..) => s.add("x", "y"),
+                    Instruction::Mult(..) => s.mul("x", "y"),
+                    Instruction::LessThan(..) => s.bool_to_int("x < y"),
+                    _ => s.bool_to_int("x == y"),
+                };
+                let (checks, store) = self.destination(ip, d, &value);
+                code.extend(checks);
+                code.extend(self.operand(ip, x, "x"));
+                code.extend(self.operand(ip, y, "y"));
+                code.push(store);
+            }
+            Instruction::Input(d) => {
+                let (checks, store) = self.destination(ip, d, "x");
+                code.extend(checks);
+                code.push(s.input(ip, "x"));
+                code.push(store);
+            }
+            Instruction::Output(x) => {
+                code.extend(self.operand(ip, x, "x"));
+                code.push(s.output("x"));
+            }
+            Instruction::JumpIfTrue(x, t) | Instruction::JumpIfFalse(x, t) => {
+                let jump = if let Param::Immediate(t) = t {
+                    self.goto(ip, t)
+                } else {
+                    let mut jump = self.operand(ip, t, "y");
+                    jump.push(s.jump(ip, "y"));
+                    jump.join(" ")
+                };
+                if always_jumps(ins) {
+                    code.push(jump);
+                } else {
+                    code.extend(self.operand(ip, x, "x"));
+                    let cond = if matches!(ins, Instruction::JumpIfTrue(..)) {
+                        "x != 0"
+                    } else {
+                        "x == 0"
+                    };
+                    code.push(s.if_then(cond, &jump));
+                }
+            }
+            Instruction::ChangeRelativeBase(x) => {
+                code.extend(self.operand(ip, x, "x"));
+                code.push("rb += x;".to_string());
+            }
+            Instruction::Halt => code.push(s.exit(ip, &ExecState::Halted)),
+        }
+        code
+    }
+
+    // Code of the block, one statement per line.
+    fn block(&self, block: &Block) -> Vec<String> {
+        let mut code: Vec<String> = block
+            .instructions
+            .iter()
+            .flat_map(|(ip, ins)| self.instruction(*ip, *ins))
+            .collect();
+        if let Some(next) = block.next {
+            code.push(self.goto(next, i64::try_from(next).unwrap()));
+        }
+        code
+    }
+}
+
+// Condition on the address being in one of the code ranges.
+fn is_code_condition(check: &WriteCheck, var: &str) -> String {
+    let ranges = check.code_ranges();
+    if ranges.is_empty() {
+        return "false".to_string();
+    }
+    ranges
+        .iter()
+        .map(|(start, end)| {
+            if *start == 0 {
+                format!("{var} <= {end}")
+            } else {
+                format!("({var} >= {start} && {var} <= {end})")
+            }
+        })
+        .join(" || ")
+}
+
+struct Rust;
+
+impl Syntax for Rust {
+    fn int(&self, v: i64) -> String {
+        if v == i64::MIN {
+            "i64::MIN".to_string()
+        } else {
+            v.to_string()
+        }
+    }
+
+    fn assign(&self, var: &str, value: &str) -> String {
+        format!("let {var}: i64 = {value};")
+    }
+
+    fn exit(&self, ip: usize, state: &ExecState) -> String {
+        format!("exit!({ip}, ExecState::{state:?})")
+    }
+
+    fn address(&self, ip: usize, var: &str, value: &str) -> String {
+        format!("let {var} = addr!({ip}, {value});")
+    }
+
+    fn if_then(&self, cond: &str, code: &str) -> String {
+        format!("if {cond} {{ {code} }}")
+    }
+
+    fn image(&self, addr: usize) -> String {
+        format!("mem[{addr}]")
+    }
+
+    fn read(&self, addr: &str) -> String {
+        format!("read(mem, {addr})")
+    }
+
+    fn write(&self, addr: &str, value: &str) -> String {
+        format!("write(mem, {addr}, {value});")
+    }
+
+    fn add(&self, x: &str, y: &str) -> String {
+        format!("{x}.wrapping_add({y})")
+    }
+
+    fn mul(&self, x: &str, y: &str) -> String {
+        format!("{x}.wrapping_mul({y})")
+    }
+
+    fn bool_to_int(&self, cond: &str) -> String {
+        format!("i64::from({cond})")
+    }
+
+    fn input(&self, ip: usize, var: &str) -> String {
+        format!("let Some({var}) = bus.read() else {{ exit!({ip}, ExecState::NeedsInput) }};")
+    }
+
+    fn output(&self, value: &str) -> String {
+        format!("bus.write({value});")
+    }
+
+    fn goto(&self, target: usize) -> String {
+        format!("block = {target}; continue;")
+    }
+
+    fn jump(&self, ip: usize, target: &str) -> String {
+        format!("block = addr!({ip}, {target}); continue;")
+    }
+
+    fn comment(&self, s: &str) -> String {
+        format!("// {s}")
+    }
+}
+
+// Start of the Rust function, up to the match on the blocks.
+const RUST_PRELUDE: &str = r"    fn read(mem: &[i64], addr: usize) -> i64 {
+        mem.get(addr).copied().unwrap_or(0)
+    }
+
+    fn write(mem: &mut Vec<i64>, addr: usize, val: i64) {
+        if addr >= mem.len() {
+            mem.resize(addr + 1, 0);
+        }
+        mem[addr] = val;
+    }
+
+    fn is_code(w: usize) -> bool {
+        IS_CODE
+    }
+
+    let mut rb = *relative_base;
+    macro_rules! exit {
+        ($ip:expr, $state:expr) => {{
+            *ip = $ip;
+            *relative_base = rb;
+            return $state;
+        }};
+    }
+    macro_rules! addr {
+        ($ip:expr, $addr:expr) => {
+            match usize::try_from($addr) {
+                Ok(addr) if addr < MAX_ADDRESS => addr,
+                _ => exit!($ip, ExecState::Running),
+            }
+        };
+    }
+
+    let mut block = *ip;
+    loop {
+        match block {
+";
+
+/// Translates the program into a Rust function with this signature:
+///
+/// ```text
+/// fn name<B: Bus>(mem: &mut Vec<i64>, ip: &mut usize, relative_base: &mut i64, bus: &mut B) -> ExecState
+/// ```
+///
+/// The code can be written to a file from a build script, and included with `include!`,
+/// with `Bus` and `ExecState` in scope. `mem` must start as the program, and grows when the
+/// program writes after it. The function runs the program from `ip` until it halts or needs
+/// input, like `exec()`, or returns `Running` on an instruction it can't execute.
+/// `IntcodeComputer::resume()` can then continue from `mem`, `ip` and `relative_base`.
+#[must_use]
+pub fn to_rust(mem: &[i64], name: &str) -> String {
+    let check = check_writes(mem);
+    let blocks = blocks(&check);
+    let translator = Translator {
+        syntax: Rust,
+        blocks: &blocks,
+        modified: check.modified(),
+        len: mem.len(),
+    };
+
+    let mut code = String::from(
+        "#[allow(unused_mut, unused_variables, unused_macros, unreachable_code, dead_code, clippy::all, clippy::pedantic)]\n",
+    );
+    writeln!(
+        code,
+        "pub fn {name}<B: Bus>(mem: &mut Vec<i64>, ip: &mut usize, relative_base: &mut i64, bus: &mut B) -> ExecState {{"
+    )
+    .unwrap();
+    code += &RUST_PRELUDE
+        .replace("IS_CODE", &is_code_condition(&check, "w"))
+        .replace("MAX_ADDRESS", &MAX_ADDRESS.to_string());
+    for (start, block) in &blocks {
+        writeln!(code, "            {start} => {{").unwrap();
+        for line in translator.block(block) {
+            writeln!(code, "                {line}").unwrap();
+        }
+        writeln!(code, "            }}").unwrap();
+    }
+    code += "            _ => exit!(block, ExecState::Running),\n";
+    code += "        }\n";
+    code += "    }\n";
+    code += "}\n";
+    code
+}
+
+// Harness of the Rust programs, with the same types as the library.
+const RUST_MAIN: &str = r#"use std::io::{self, BufWriter, Read, Write};
+
+#[derive(Debug, PartialEq)]
+enum ExecState {
+    Running,
+    Halted,
+    NeedsInput,
+}
+
+trait Bus {
+    fn read(&mut self) -> Option<i64>;
+    fn write(&mut self, v: i64);
+}
+
+struct StdBus<W: Write> {
+    input: Vec<i64>,
+    next: usize,
+    output: W,
+}
+
+impl<W: Write> Bus for StdBus<W> {
+    fn read(&mut self) -> Option<i64> {
+        let v = self.input.get(self.next).copied();
+        self.next += usize::from(v.is_some());
+        v
+    }
+
+    fn write(&mut self, v: i64) {
+        writeln!(self.output, "{v}").unwrap();
+    }
+}
+
+fn main() {
+    let repeat = std::env::args().any(|arg| arg == "--repeat");
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input).unwrap();
+    let mut bus = StdBus {
+        input: input.split_whitespace().map(|v| v.parse().unwrap()).collect(),
+        next: 0,
+        output: BufWriter::new(io::stdout().lock()),
+    };
+    loop {
+        let mut mem = PROGRAM.to_vec();
+        let (mut ip, mut rb) = (0, 0);
+        let state = run(&mut mem, &mut ip, &mut rb, &mut bus);
+        if state == ExecState::Running {
+            bus.output.flush().unwrap();
+            let mut err = BufWriter::new(io::stderr().lock());
+            writeln!(err, "Unsupported instruction at {ip}").unwrap();
+            writeln!(err, "{ip} {rb} {}", bus.next).unwrap();
+            let mem: Vec<String> = mem.iter().map(ToString::to_string).collect();
+            writeln!(err, "{}", mem.join(",")).unwrap();
+            err.flush().unwrap();
+            std::process::exit(EXIT_UNSUPPORTED);
+        }
+        if !(repeat && state == ExecState::Halted && bus.next < bus.input.len()) {
+            break;
+        }
+    }
+}
+"#;
+
+/// Translates the program into a Rust program, running it with the integers read from stdin
+/// as input, and printing the output one value per line.
+///
+/// With the `--repeat` argument, the program starts again from its initial memory each time
+/// it halts, as long as there is input left.
+#[must_use]
+pub fn to_rust_program(mem: &[i64]) -> String {
+    let mut code = RUST_MAIN.replace("EXIT_UNSUPPORTED", &EXIT_UNSUPPORTED.to_string());
+    writeln!(
+        code,
+        "\nconst PROGRAM: &[i64] = &[{}];\n",
+        mem.iter().map(|v| Rust.int(*v)).join(", ")
+    )
+    .unwrap();
+    code += &to_rust(mem, "run");
+    code
+}
+
+struct C;
+
+impl Syntax for C {
+    fn int(&self, v: i64) -> String {
+        if v == i64::MIN {
+            "(-9223372036854775807LL - 1)".to_string()
+        } else {
+            format!("{v}LL")
+        }
+    }
+
+    fn assign(&self, var: &str, value: &str) -> String {
+        format!("{var} = {value};")
+    }
+
+    fn exit(&self, ip: usize, state: &ExecState) -> String {
+        let state = match state {
+            ExecState::Halted => "INTCODE_HALTED",
+            ExecState::NeedsInput => "INTCODE_NEEDS_INPUT",
+            _ => "INTCODE_RUNNING",
+        };
+        format!("EXIT({ip}, {state});")
+    }
+
+    fn address(&self, ip: usize, var: &str, value: &str) -> String {
+        format!("{var} = {value}; CHECK_ADDRESS({ip}, {var});")
+    }
+
+    fn if_then(&self, cond: &str, code: &str) -> String {
+        format!("if ({cond}) {{ {code} }}")
+    }
+
+    fn image(&self, addr: usize) -> String {
+        format!("c->mem[{addr}]")
+    }
+
+    fn read(&self, addr: &str) -> String {
+        format!("intcode_read(c, {addr})")
+    }
+
+    fn write(&self, addr: &str, value: &str) -> String {
+        format!("intcode_write(c, {addr}, {value});")
+    }
+
+    fn add(&self, x: &str, y: &str) -> String {
+        // Signed overflow is undefined in C, but not unsigned one.
+        format!("(long long)((unsigned long long){x} + (unsigned long long){y})")
+    }
+
+    fn mul(&self, x: &str, y: &str) -> String {
+        format!("(long long)((unsigned long long){x} * (unsigned long long){y})")
+    }
+
+    fn bool_to_int(&self, cond: &str) -> String {
+        format!("({cond})")
+    }
+
+    fn input(&self, ip: usize, var: &str) -> String {
+        format!("if (!bus->read(bus->ctx, &{var})) EXIT({ip}, INTCODE_NEEDS_INPUT);")
+    }
+
+    fn output(&self, value: &str) -> String {
+        format!("bus->write(bus->ctx, {value});")
+    }
+
+    fn goto(&self, target: usize) -> String {
+        format!("goto L{target};")
+    }
+
+    fn jump(&self, ip: usize, target: &str) -> String {
+        format!("CHECK_ADDRESS({ip}, {target}); ip = (size_t){target}; goto dispatch;")
+    }
+
+    fn comment(&self, s: &str) -> String {
+        format!("/* {s} */")
+    }
+}
+
+const C_HEADER: &str = r"#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+enum { INTCODE_RUNNING, INTCODE_HALTED, INTCODE_NEEDS_INPUT };
+
+typedef struct {
+    /* Returns 0 if there is no input. */
+    int (*read)(void *ctx, long long *value);
+    void (*write)(void *ctx, long long value);
+    void *ctx;
+} IntcodeBus;
+
+typedef struct {
+    long long *mem;
+    size_t len;
+    size_t capacity;
+    size_t ip;
+    long long relative_base;
+} Intcode;
+";
+
+const C_MEMORY: &str = r"
+/* Initializes the computer with the program. Returns 0 if out of memory. */
+int intcode_init(Intcode *c) {
+    c->len = c->capacity = sizeof(intcode_program) / sizeof(intcode_program[0]);
+    c->mem = malloc(c->capacity * sizeof(long long));
+    if (!c->mem) return 0;
+    memcpy(c->mem, intcode_program, sizeof(intcode_program));
+    c->ip = 0;
+    c->relative_base = 0;
+    return 1;
+}
+
+static long long intcode_read(const Intcode *c, size_t addr) {
+    return addr < c->len ? c->mem[addr] : 0;
+}
+
+static void intcode_write(Intcode *c, size_t addr, long long value) {
+    if (addr >= c->len) {
+        if (addr >= c->capacity) {
+            size_t capacity = c->capacity * 2 > addr + 1 ? c->capacity * 2 : addr + 1;
+            long long *mem = realloc(c->mem, capacity * sizeof(long long));
+            if (!mem) abort();
+            c->mem = mem;
+            c->capacity = capacity;
+        }
+        memset(c->mem + c->len, 0, (addr + 1 - c->len) * sizeof(long long));
+        c->len = addr + 1;
+    }
+    c->mem[addr] = value;
+}
+";
+
+const C_RUN: &str = r"
+static int is_code(long long w) {
+    return IS_CODE;
+}
+
+#define EXIT(at, state) do { c->ip = (at); c->relative_base = rb; return (state); } while (0)
+#define CHECK_ADDRESS(at, addr) if ((addr) < 0 || (addr) >= MAX_ADDRESS) EXIT(at, INTCODE_RUNNING)
+
+int intcode_run(Intcode *c, IntcodeBus *bus) {
+    long long rb = c->relative_base;
+    size_t ip = c->ip;
+    long long x, y, a, w;
+dispatch:
+    switch (ip) {
+";
+
+const C_MAIN: &str = r#"
+#ifdef INTCODE_MAIN
+static long long *inputs;
+static size_t input_count, next_input;
+
+static int stdin_read(void *ctx, long long *value) {
+    (void)ctx;
+    if (next_input == input_count) return 0;
+    *value = inputs[next_input++];
+    return 1;
+}
+
+static void stdout_write(void *ctx, long long value) {
+    (void)ctx;
+    printf("%lld\n", value);
+}
+
+/* Runs the program with the integers read from stdin as input.
+   With --repeat, the program starts again each time it halts, while there is input left. */
+int main(int argc, char **argv) {
+    int repeat = argc > 1 && strcmp(argv[1], "--repeat") == 0;
+    size_t capacity = 16;
+    long long value;
+    inputs = malloc(capacity * sizeof(long long));
+    while (scanf("%lld", &value) == 1) {
+        if (input_count == capacity) {
+            capacity *= 2;
+            inputs = realloc(inputs, capacity * sizeof(long long));
+        }
+        inputs[input_count++] = value;
+    }
+
+    IntcodeBus bus = {stdin_read, stdout_write, NULL};
+    Intcode c;
+    int state;
+    do {
+        if (!intcode_init(&c)) return 1;
+        state = intcode_run(&c, &bus);
+        if (state == INTCODE_RUNNING) {
+            fflush(stdout);
+            setvbuf(stderr, NULL, _IOFBF, 1 << 16);
+            fprintf(stderr, "Unsupported instruction at %zu\n", c.ip);
+            fprintf(stderr, "%zu %lld %zu\n", c.ip, c.relative_base, next_input);
+            for (size_t i = 0; i < c.len; i++) {
+                fprintf(stderr, i ? ",%lld" : "%lld", c.mem[i]);
+            }
+            fprintf(stderr, "\n");
+            return EXIT_UNSUPPORTED;
+        }
+        free(c.mem);
+    } while (repeat && state == INTCODE_HALTED && next_input < input_count);
+    return 0;
+}
+#endif
+"#;
+
+/// Translates the program into C.
+///
+/// `intcode_init()` sets up an `Intcode` computer with the program, and `intcode_run()` runs it
+/// until it halts or needs input, doing its I/O through the callbacks of an `IntcodeBus`.
+/// It returns `INTCODE_RUNNING` on an instruction it can't execute.
+///
+/// Compiled with `INTCODE_MAIN` defined, it's a program reading the input from stdin and printing
+/// the output, like the one of `to_rust_program()`.
+#[must_use]
+pub fn to_c(mem: &[i64]) -> String {
+    let check = check_writes(mem);
+    let blocks = blocks(&check);
+    let translator = Translator {
+        syntax: C,
+        blocks: &blocks,
+        modified: check.modified(),
+        len: mem.len(),
+    };
+
+    let mut code = C_HEADER.to_string();
+    writeln!(code, "\n#define MAX_ADDRESS {MAX_ADDRESS}LL").unwrap();
+    writeln!(code, "#define EXIT_UNSUPPORTED {EXIT_UNSUPPORTED}").unwrap();
+    writeln!(
+        code,
+        "\nstatic const long long intcode_program[] = {{{}}};",
+        mem.iter().map(|v| C.int(*v)).join(", ")
+    )
+    .unwrap();
+    code += C_MEMORY;
+    code += &C_RUN.replace("IS_CODE", &is_code_condition(&check, "w"));
+    for start in blocks.keys() {
+        writeln!(code, "    case {start}: goto L{start};").unwrap();
+    }
+    code += "    default: EXIT(ip, INTCODE_RUNNING);\n";
+    code += "    }\n";
+    for (start, block) in &blocks {
+        writeln!(code, "L{start}:").unwrap();
+        for line in translator.block(block) {
+            writeln!(code, "    {line}").unwrap();
+        }
+    }
+    code += "}\n";
+    code += C_MAIN;
+    code
+}
+
+/// Language to translate to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    C,
+}
+
+// Makes the temporary files of parallel builds unique.
+static BUILD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Directory of the executables, `$XDG_CACHE_HOME/intcode_native` or `~/.cache/intcode_native`,
+// accessible only by the current user so that nobody else can replace them.
+fn cache_dir() -> io::Result<PathBuf> {
+    let dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .ok_or_else(|| io::Error::other("No cache directory, as HOME isn't set"))?
+        .join("intcode_native");
+    prepare_cache_dir(&dir)?;
+    Ok(dir)
+}
+
+// Creates the directory if needed, with access only for the current user.
+// Fails if it exists with other permissions, as others could have written into it.
+#[cfg(unix)]
+fn prepare_cache_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if !dir.exists() {
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match fs::DirBuilder::new().mode(0o700).create(dir) {
+            // Created by a parallel build.
+            Err(err) if err.kind() != io::ErrorKind::AlreadyExists => return Err(err),
+            _ => {}
+        }
+    }
+    let metadata = fs::symlink_metadata(dir)?;
+    if metadata.is_dir() && metadata.permissions().mode() & 0o777 == 0o700 {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} must be a directory only the user can access",
+                dir.display()
+            ),
+        ))
+    }
+}
+
+#[cfg(not(unix))]
+fn prepare_cache_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+// Name of the executable, a hash of everything that affects the compilation.
+// The compiler is identified by its version, as the same command can give another compiler.
+fn cache_key(compiler: &str, flags: &[&str], code: &str) -> io::Result<String> {
+    let version = Command::new(compiler).arg("--version").output()?;
+    let mut hasher = Sha256::new();
+    for part in [
+        compiler.as_bytes(),
+        &version.stdout,
+        flags.join(" ").as_bytes(),
+        code.as_bytes(),
+    ] {
+        // Lengths first, so that the parts can't be confused with each other.
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    let mut key = String::new();
+    for b in hasher.finalize() {
+        write!(key, "{b:02x}").unwrap();
+    }
+    Ok(key)
+}
+
+// Bus of the interpreter continuing a native program, with the inputs it didn't read.
+struct Remaining<'a> {
+    inputs: &'a [i64],
+    next: usize,
+    output: Vec<i64>,
+}
+
+impl Bus for Remaining<'_> {
+    fn read(&mut self) -> Option<i64> {
+        let v = self.inputs.get(self.next).copied();
+        self.next += usize::from(v.is_some());
+        v
+    }
+
+    fn write(&mut self, v: i64) {
+        self.output.push(v);
+    }
+}
+
+// Parses the state handed over by a native program, see `EXIT_UNSUPPORTED`.
+// Returns the instruction pointer, the relative base, the number of inputs read and the memory.
+fn parse_state(s: &str) -> Option<(usize, i64, usize, Vec<i64>)> {
+    let mut lines = s.lines().skip(1);
+    let mut values = lines.next()?.split(' ');
+    let ip = values.next()?.parse().ok()?;
+    let relative_base = values.next()?.parse().ok()?;
+    let next = values.next()?.parse().ok()?;
+    let mem = lines
+        .next()?
+        .split(',')
+        .map(|v| v.parse().ok())
+        .collect::<Option<_>>()?;
+    Some((ip, relative_base, next, mem))
+}
+
+/// A translated program compiled into an executable, doing its I/O with stdin and stdout.
+///
+/// When the program reaches an instruction it can't execute, the interpreter takes over
+/// from its state.
+#[derive(Debug, Clone)]
+pub struct Native {
+    path: PathBuf,
+}
+
+impl Native {
+    /// Translates the program and compiles it, with `rustc -O`, or `cc -O2` for C.
+    /// The compilers can be changed with the `RUSTC` and `CC` environment variables.
+    ///
+    /// Executables are kept in `$XDG_CACHE_HOME/intcode_native` or `~/.cache/intcode_native`,
+    /// and reused for the same code, compiler version and flags.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compiler can't be run, or fails, or if the cache directory
+    /// can't be created or is accessible to other users.
+    pub fn build(mem: &[i64], language: Language) -> io::Result<Self> {
+        let (code, extension, compiler, flags): (_, _, _, &[&str]) = match language {
+            Language::Rust => (
+                to_rust_program(mem),
+                "rs",
+                env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()),
+                &["-O", "--edition", "2021"],
+            ),
+            Language::C => (
+                to_c(mem),
+                "c",
+                env::var("CC").unwrap_or_else(|_| "cc".to_string()),
+                &["-O2", "-DINTCODE_MAIN"],
+            ),
+        };
+        let dir = cache_dir()?;
+        let path = dir.join(format!(
+            "{}_{extension}",
+            cache_key(&compiler, flags, &code)?
+        ));
+        if path.exists() {
+            return Ok(Self { path });
+        }
+
+        let tmp = dir.join(format!(
+            "tmp_{}_{}",
+            std::process::id(),
+            BUILD_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let source = tmp.with_extension(extension);
+        fs::write(&source, code)?;
+        let output = Command::new(&compiler)
+            .args(flags)
+            .arg("-o")
+            .arg(&tmp)
+            .arg(&source)
+            .output()?;
+        fs::remove_file(&source)?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "Compilation failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        fs::rename(&tmp, &path)?;
+        Ok(Self { path })
+    }
+
+    fn exec(&self, inputs: &[i64], repeat: bool) -> io::Result<Vec<i64>> {
+        let mut command = Command::new(&self.path);
+        if repeat {
+            command.arg("--repeat");
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // The program reads all its input before starting.
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(inputs.iter().join("\n").as_bytes())?;
+        drop(stdin);
+
+        let output = child.wait_with_output()?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let unsupported = output.status.code() == Some(EXIT_UNSUPPORTED);
+        if !output.status.success() && !unsupported {
+            return Err(io::Error::other(stderr.trim().to_string()));
+        }
+        let mut values: Vec<i64> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.parse().map_err(io::Error::other))
+            .collect::<io::Result<_>>()?;
+
+        if unsupported {
+            let (ip, relative_base, next, mem) = parse_state(&stderr)
+                .ok_or_else(|| io::Error::other(format!("Invalid state: {}", stderr.trim())))?;
+            let bus = Remaining {
+                inputs: inputs.get(next..).unwrap_or_default(),
+                next: 0,
+                output: Vec::new(),
+            };
+            let mut computer = IntcodeComputer::resume(&mem, ip, relative_base, bus);
+            let state = computer.try_exec().map_err(io::Error::other)?;
+            values.append(&mut computer.io.output);
+            // Once halted, the program starts again natively.
+            let left = &computer.io.inputs[computer.io.next..];
+            if repeat && state == ExecState::Halted && !left.is_empty() {
+                values.extend(self.exec(left, repeat)?);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Runs the program with these inputs, until it halts or needs more input,
+    /// and returns all the values it output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program can't be run, or is invalid.
+    pub fn query(&self, inputs: &[i64]) -> io::Result<Vec<i64>> {
+        self.exec(inputs, false)
+    }
+
+    /// Like `query()`, but starts the program again each time it halts while there is input left,
+    /// to make many queries without starting a process for each one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program can't be run, or is invalid.
+    pub fn query_repeated(&self, inputs: &[i64]) -> io::Result<Vec<i64>> {
+        self.exec(inputs, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assemble, IntcodeComputer};
+
+    fn build(code: &str) -> Vec<i64> {
+        code.split(',').map(|v| v.parse().unwrap()).collect()
+    }
+
+    // Checks the program gives the same output translated to C as with the interpreter.
+    fn check_c(mem: &[i64], inputs: &[i64]) -> Vec<i64> {
+        let expected = IntcodeComputer::build(&mem.iter().join(",")).query(inputs);
+        let native = Native::build(mem, Language::C).unwrap();
+        assert_eq!(native.query(inputs).unwrap(), expected);
+        expected
+    }
+
+    const SUM: &str = r"
+        arb #array
+loop:   jf [rb+0], end
+        add [sum], [rb+0] -> [sum]
+        arb #1
+        jt #1, loop
+end:    out [sum]
+        hlt
+sum:    .data 0
+array:  .data 3, 5, 9, 0
+";
+
+    // Calls a recursive function computing the factorial of the input.
+    const FACTORIAL: &str = r"
+        arb #stack
+        in [rb+1]
+        add #ret, #0 -> [rb+0]
+        jt #1, fact
+ret:    out [rb+2]
+        hlt
+; Takes the argument in [rb+1], returns in [rb+2].
+fact:   arb #3
+        jf [rb-2], one
+        add [rb-2], #-1 -> [rb+1]
+        add #back, #0 -> [rb+0]
+        jt #1, fact
+back:   mul [rb-2], [rb+2] -> [rb-1]
+        arb #-3
+        jt #1, [rb+0]
+one:    add #1, #0 -> [rb-1]
+        arb #-3
+        jt #1, [rb+0]
+stack:  .data 0
+";
+
+    #[test]
+    fn test_check_writes() {
+        let check = check_writes(&assemble(SUM).unwrap());
+        assert!(check.is_safe());
+        assert!(check.relative_writes.is_empty());
+
+        let check = check_writes(&assemble(FACTORIAL).unwrap());
+        assert!(check.is_safe());
+        assert_eq!(check.relative_writes.len(), 6);
+    }
+
+    #[test]
+    fn test_check_writes_self_modifying() {
+        // Writes at 4, over the HLT instruction.
+        let check = check_writes(&build("1,1,1,4,99,5,6,0,99"));
+        assert!(!check.is_safe());
+        assert_eq!(
+            check.code_writes,
+            [CodeWrite {
+                ip: 0,
+                addr: 4,
+                target: 4
+            }]
+        );
+        assert!(check
+            .to_string()
+            .contains("0: writes at 4, in the instruction at 4"));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let check = check_writes(&assemble(SUM).unwrap());
+        let blocks = blocks(&check);
+        // Starting at 0, loop, after the jump out of the loop, and end.
+        assert_eq!(blocks.keys().copied().collect_vec(), [0, 2, 5, 14]);
+        assert_eq!(blocks[&0].next, Some(2));
+        assert_eq!(blocks[&5].instructions.len(), 3);
+        assert_eq!(blocks[&5].next, None);
+        assert_eq!(blocks[&14].next, None);
+    }
+
+    #[test]
+    fn test_to_rust() {
+        let code = to_rust(&assemble(SUM).unwrap(), "sum");
+        assert!(code.contains("pub fn sum<B: Bus>"));
+        assert!(code.contains("            2 => {\n                // 2: JZ [rb+0], L14\n"));
+        assert!(code.contains("block = 14; continue;"));
+    }
+
+    #[test]
+    fn test_c() {
+        assert_eq!(check_c(&assemble(SUM).unwrap(), &[]), [17]);
+        let factorial = assemble(FACTORIAL).unwrap();
+        assert_eq!(check_c(&factorial, &[5]), [120]);
+        assert_eq!(check_c(&factorial, &[0]), [1]);
+        // Compares its input to 8, with jumps.
+        let mem = build(
+            "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,\
+             20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99",
+        );
+        for input in [7, 8, 9] {
+            check_c(&mem, &[input]);
+        }
+        // Quine, with large numbers.
+        check_c(
+            &build("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99"),
+            &[],
+        );
+        check_c(&build("104,1125899906842624,99"), &[]);
+        // Waits for more input.
+        assert_eq!(check_c(&build("3,9,4,9,3,9,4,9,99,0"), &[4]), [4]);
+    }
+
+    #[test]
+    fn test_rust() {
+        let native = Native::build(&assemble(FACTORIAL).unwrap(), Language::Rust).unwrap();
+        assert_eq!(native.query(&[10]).unwrap(), [3_628_800]);
+        assert_eq!(native.query_repeated(&[3, 4, 5]).unwrap(), [6, 24, 120]);
+        assert_eq!(native.query(&[]).unwrap(), []);
+    }
+
+    #[test]
+    fn test_query_repeated() {
+        let native = Native::build(&assemble(FACTORIAL).unwrap(), Language::C).unwrap();
+        assert_eq!(native.query_repeated(&[3, 4, 5]).unwrap(), [6, 24, 120]);
+        assert_eq!(native.query(&[3, 4, 5]).unwrap(), [6]);
+    }
+
+    #[test]
+    fn test_cache() {
+        let mem = assemble(FACTORIAL).unwrap();
+        let native = Native::build(&mem, Language::C).unwrap();
+        let modified = fs::metadata(&native.path).unwrap().modified().unwrap();
+        // Cache hit: the executable isn't built again.
+        let cached = Native::build(&mem, Language::C).unwrap();
+        assert_eq!(cached.path, native.path);
+        assert_eq!(
+            fs::metadata(&cached.path).unwrap().modified().unwrap(),
+            modified
+        );
+
+        let key = cache_key("cc", &["-O2"], "code").unwrap();
+        assert_eq!(key.len(), 64);
+        assert_eq!(cache_key("cc", &["-O2"], "code").unwrap(), key);
+        assert_ne!(cache_key("cc", &["-O0"], "code").unwrap(), key);
+        assert_ne!(cache_key("cc", &["-O2"], "other").unwrap(), key);
+        assert_ne!(cache_key("rustc", &["-O2"], "code").unwrap(), key);
+        assert!(cache_key("no_such_compiler", &[], "code").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cache_dir_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("intcode_native_test_{}", std::process::id()));
+        prepare_cache_dir(&dir).unwrap();
+        assert_eq!(
+            fs::metadata(&dir).unwrap().permissions().mode() & 0o777,
+            0o700
+        );
+        // Accessible to others: refused.
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(prepare_cache_dir(&dir).is_err());
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_hand_over() {
+        // Stops on the MUL the program writes over the HLT at 4, and outputs its result.
+        let mem = build("1,1,1,4,99,5,6,0,4,0,99");
+        assert_eq!(check_c(&mem, &[]), [30]);
+        let native = Native::build(&mem, Language::Rust).unwrap();
+        assert_eq!(native.query(&[]).unwrap(), [30]);
+
+        // Writes into its code in relative mode, checked at runtime.
+        let mem = assemble("out #4\narb #1\nin [rb+0]\nout [1]\nhlt").unwrap();
+        assert_eq!(check_c(&mem, &[7]), [4, 7]);
+        for language in [Language::C, Language::Rust] {
+            let native = Native::build(&mem, language).unwrap();
+            // Going back and forth between the translation and the interpreter.
+            assert_eq!(
+                native.query_repeated(&[7, 8, 9]).unwrap(),
+                [4, 7, 4, 8, 4, 9]
+            );
+        }
+    }
+}
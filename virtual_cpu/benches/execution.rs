@@ -0,0 +1,39 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use virtual_cpu::compiled::CompiledProgram;
+use virtual_cpu::instruction::{execute_all, Instruction};
+use virtual_cpu::registers::Registers;
+use virtual_cpu::run_utils;
+
+// 2016 day 12 part 2, with the classic interpreter and the compiled one.
+fn day2016_12_part2(c: &mut Criterion) {
+    let input_file = run_utils::get_input_file("day2016_12");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    let instructions = Instruction::build_list(&input);
+
+    let mut initial_registers = Registers::new();
+    initial_registers.set('c', 1);
+
+    let mut group = c.benchmark_group("day2016_12_part2");
+    group.sample_size(10);
+    group.bench_function("execute_all", |b| {
+        b.iter(|| {
+            let mut regs = initial_registers.clone();
+            execute_all(&instructions, &mut regs);
+            regs.get('a')
+        });
+    });
+    group.bench_function("compiled", |b| {
+        let program = CompiledProgram::compile(&instructions).unwrap();
+        b.iter(|| {
+            let mut regs = initial_registers.clone();
+            program.execute_registers(&mut regs);
+            regs.get('a')
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, day2016_12_part2);
+criterion_main!(benches);
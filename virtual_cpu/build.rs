@@ -0,0 +1,70 @@
+//! Generates Rust code for some test programs, to check the Rust backend with `cargo test`.
+//!
+//! The build script cannot depend on the crate itself, so the needed modules are included directly.
+
+use std::{env, fs, path::Path};
+
+#[allow(dead_code)]
+#[path = "src/instruction.rs"]
+mod instruction;
+#[allow(dead_code)]
+#[path = "src/intchar.rs"]
+mod intchar;
+#[allow(dead_code)]
+#[path = "src/parsing.rs"]
+mod parsing;
+#[allow(dead_code)]
+#[path = "src/registers.rs"]
+mod registers;
+#[path = "src/rust_code/mod.rs"]
+mod rust_code;
+
+use instruction::Instruction;
+use registers::Registers;
+use rust_code::gen::{get_rust_code, get_rust_code_full};
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/bin/test_input/day2016_12_input_test_1");
+    println!("cargo:rerun-if-changed=src/bin/test_input/day2016_12_input_test_2");
+    for module in [
+        "intchar",
+        "parsing",
+        "registers",
+        "instruction",
+        "rust_code/gen",
+    ] {
+        println!("cargo:rerun-if-changed=src/{}.rs", module);
+    }
+
+    let input = fs::read_to_string("src/bin/test_input/day2016_12_input_test_1").unwrap();
+    let instructions = Instruction::build_list(&input);
+    let mut code = get_rust_code("day2016_12_test", &instructions, &Registers::new(), &['a']);
+    // Replacing the 3 increments / decrements.
+    code += &get_rust_code_full(
+        "day2016_12_test_optimized",
+        &instructions,
+        &Registers::new(),
+        &['a'],
+        &[(1..=3, "a += 1;\n".to_string())],
+    );
+
+    let input = fs::read_to_string("src/bin/test_input/day2016_12_input_test_2").unwrap();
+    let instructions = Instruction::build_list(&input);
+    code += &get_rust_code(
+        "day2016_12_test_loop",
+        &instructions,
+        &Registers::new(),
+        &['a', 'c'],
+    );
+    // Replacing the addition loop, along with the copy before it.
+    code += &get_rust_code_full(
+        "day2016_12_test_loop_optimized",
+        &instructions,
+        &Registers::new(),
+        &['a', 'c'],
+        &[(2..=5, "c = a;\na += b;\nb = 0;\n".to_string())],
+    );
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("day2016_12_test.rs"), code).unwrap();
+}
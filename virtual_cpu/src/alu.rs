@@ -0,0 +1,133 @@
+//! Support for programs reading an input stream, like the 2021 day 24 ALU (Arithmetic Logic Unit).
+
+use std::ops::RangeInclusive;
+
+use fxhash::FxHashSet;
+
+use crate::bus::Channel;
+use crate::instruction::Instruction;
+use crate::interval::{analyze, IntervalRegisters};
+use crate::machine::Machine;
+use crate::registers::Registers;
+
+/// Executes the program, with the input instructions reading from `input`.
+pub fn execute_all(instructions: &[Instruction], regs: &mut Registers<i64>, input: &[i64]) {
+    let mut machine = Machine::new(instructions, regs.clone());
+    machine.run(&mut Channel::new(input));
+    assert!(machine.is_halted(), "Input is empty");
+    *regs = machine.regs;
+}
+
+// Executes the instructions until an input instruction, or the end of the program.
+fn execute_until_input(instructions: &[Instruction], ir: &mut usize, regs: &mut Registers<i64>) {
+    while let Some(ins) = instructions.get(*ir) {
+        if matches!(ins, Instruction::Inp(_)) {
+            break;
+        }
+        ins.execute(ir, regs);
+    }
+}
+
+/// Search of the input that makes a program finish with a register at a specific value.
+///
+/// The search is a depth-first search on each input value. It is pruned by an interval analysis
+/// of the rest of the program, which tells if the expected value can still be reached.
+pub struct InputSearch<'a> {
+    instructions: &'a [Instruction],
+    input_values: RangeInclusive<i64>,
+    target_reg: char,
+    target_value: i64,
+    register_names: Vec<char>,
+    // States (instruction index and register values) from which we know there is no solution.
+    dead_ends: FxHashSet<(usize, Vec<i64>)>,
+}
+
+impl<'a> InputSearch<'a> {
+    pub fn new(
+        instructions: &'a [Instruction],
+        input_values: RangeInclusive<i64>,
+        target_reg: char,
+        target_value: i64,
+    ) -> Self {
+        let mut register_names: Vec<char> = instructions
+            .iter()
+            .flat_map(Instruction::get_register_names)
+            .collect();
+        register_names.sort_unstable();
+        register_names.dedup();
+        Self {
+            instructions,
+            input_values,
+            target_reg,
+            target_value,
+            register_names,
+            dead_ends: FxHashSet::default(),
+        }
+    }
+
+    /// Finds the largest input (compared value by value) that gives the target value.
+    pub fn largest(&mut self) -> Option<Vec<i64>> {
+        let values: Vec<i64> = self.input_values.clone().rev().collect();
+        self.search(&values)
+    }
+
+    /// Finds the smallest input (compared value by value) that gives the target value.
+    pub fn smallest(&mut self) -> Option<Vec<i64>> {
+        let values: Vec<i64> = self.input_values.clone().collect();
+        self.search(&values)
+    }
+
+    fn search(&mut self, values: &[i64]) -> Option<Vec<i64>> {
+        self.dead_ends.clear();
+        let mut input = Vec::new();
+        if self.search_from(0, Registers::new(), values, &mut input) {
+            Some(input)
+        } else {
+            None
+        }
+    }
+
+    // Checks with the interval analysis if the target value can still be reached.
+    fn is_reachable(&self, ir: usize, regs: &Registers<i64>) -> bool {
+        analyze(
+            &self.instructions[ir..],
+            &IntervalRegisters::from(regs),
+            &self.input_values,
+        )
+        .is_none_or(|result| result.get(self.target_reg).contains(self.target_value))
+    }
+
+    fn search_from(
+        &mut self,
+        mut ir: usize,
+        mut regs: Registers<i64>,
+        values: &[i64],
+        input: &mut Vec<i64>,
+    ) -> bool {
+        execute_until_input(self.instructions, &mut ir, &mut regs);
+        let Some(Instruction::Inp(x)) = self.instructions.get(ir) else {
+            return regs.get(self.target_reg) == self.target_value;
+        };
+
+        let state = (
+            ir,
+            self.register_names.iter().map(|r| regs.get(*r)).collect(),
+        );
+        if self.dead_ends.contains(&state) || !self.is_reachable(ir, &regs) {
+            return false;
+        }
+
+        for val in values {
+            let mut next_regs = regs.clone();
+            next_regs.set(*x, *val);
+            input.push(*val);
+            if self.search_from(ir + 1, next_regs, values, input) {
+                return true;
+            }
+            input.pop();
+        }
+
+        self.dead_ends.insert(state);
+        false
+    }
+}
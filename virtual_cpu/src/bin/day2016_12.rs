@@ -1,20 +1,28 @@
 use std::fs;
 
 use virtual_cpu::c_code::gen::get_c_code;
-use virtual_cpu::instruction::{execute_all, Instruction};
+use virtual_cpu::compiled::CompiledProgram;
+use virtual_cpu::instruction::Instruction;
 use virtual_cpu::registers::Registers;
 use virtual_cpu::run_utils;
 
+// Using the compiled interpreter, as part 2 runs for a while.
+fn execute(instructions: &[Instruction], regs: &mut Registers<i64>) {
+    CompiledProgram::compile(instructions)
+        .expect("Program can be compiled")
+        .execute_registers(regs);
+}
+
 fn value_in_reg_a(instructions: &[Instruction]) -> i64 {
     let mut regs = Registers::new();
-    execute_all(instructions, &mut regs);
+    execute(instructions, &mut regs);
     regs.get('a')
 }
 
 fn value_in_reg_a_with_c_at_1(instructions: &[Instruction]) -> i64 {
     let mut regs = Registers::new();
     regs.set('c', 1);
-    execute_all(instructions, &mut regs);
+    execute(instructions, &mut regs);
     regs.get('a')
 }
 
@@ -51,6 +59,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use virtual_cpu::instruction::execute_all;
 
     const INPUT_TEST: &str = include_str!("test_input/day2016_12_input_test_1");
 
@@ -58,4 +67,32 @@ mod tests {
     fn test_part1() {
         assert_eq!(value_in_reg_a(&Instruction::build_list(INPUT_TEST)), 42);
     }
+
+    #[test]
+    fn test_compiled_same_as_execute_all() {
+        let instructions = Instruction::build_list(INPUT_TEST);
+        let mut regs = Registers::new();
+        execute_all(&instructions, &mut regs);
+        let mut compiled_regs = Registers::new();
+        execute(&instructions, &mut compiled_regs);
+        assert_eq!(regs.get('a'), compiled_regs.get('a'));
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/day2016_12_test.rs"));
+
+    #[test]
+    fn test_part1_rust_code() {
+        assert_eq!(day2016_12_test(), vec![42]);
+        assert_eq!(day2016_12_test_optimized(), vec![42]);
+        assert_eq!(day2016_12_test_loop(), vec![12, 5]);
+        assert_eq!(day2016_12_test_loop_optimized(), vec![12, 5]);
+    }
+
+    #[test]
+    fn test_part1_c_code() {
+        use virtual_cpu::c_code::exec::CBuild;
+
+        let c_build = CBuild::new().cache_dir(None);
+        assert_eq!(c_build.run(&part1_c_code(INPUT_TEST)).unwrap(), "42");
+    }
 }
@@ -1,7 +1,8 @@
 use std::fs;
 
-use virtual_cpu::instruction::{build_list, Condition, Instruction};
-use virtual_cpu::intchar::IntChar;
+use virtual_cpu::bus::OutputCollector;
+use virtual_cpu::instruction::{build_list, Instruction};
+use virtual_cpu::machine::Machine;
 use virtual_cpu::parsing::char;
 use virtual_cpu::registers::Registers;
 use virtual_cpu::run_utils;
@@ -14,48 +15,12 @@ fn build_instruction(s: &str) -> Instruction {
     }
 }
 
-// Executes the instruction specified by ins, modifying the registers if needed.
-fn execute(instructions: &mut Vec<Instruction>, ir: &mut usize, regs: &mut Registers<i64>) {
-    let ins = &instructions[*ir];
-    if let Instruction::Toggle(offset) = ins {
-        let ir_to_toggle = (*ir as i64 + regs.get(*offset)) as usize;
-        if ir_to_toggle < instructions.len() {
-            instructions[ir_to_toggle] = match &instructions[ir_to_toggle] {
-                Instruction::Set(r, x) => {
-                    Instruction::JumpIf(Condition::NotZero, *x, IntChar::Char(*r), |v| v != 0)
-                }
-                Instruction::Add(r, _) => Instruction::Sub(*r, IntChar::from(1)),
-                Instruction::Sub(r, _) => Instruction::Add(*r, IntChar::from(1)),
-                Instruction::JumpIf(_, v, o, _) => match o {
-                    IntChar::Integer(_) => Instruction::Nop,
-                    IntChar::Char(r) => Instruction::Set(*r, *v),
-                },
-                Instruction::Toggle(offset) => Instruction::Add(*offset, IntChar::from(1)),
-                // Instruction::Mult(a, b, r) => Instruction::Mult(*a, *b, *r),
-                _ => ins.clone(),
-            };
-        }
-        *ir += 1;
-    } else {
-        ins.execute(ir, regs);
-    }
-}
-
-fn execute_all(instructions: &[Instruction], regs: &mut Registers<i64>) {
-    let mut modifiable_ins: Vec<Instruction> = instructions.to_vec();
-    // optimizer(&mut modifiable_ins);
-
-    let mut ir = 0;
-    while ir < modifiable_ins.len() {
-        execute(&mut modifiable_ins, &mut ir, regs);
-    }
-}
-
 fn value_sent_to_safe(instructions: &[Instruction]) -> i64 {
     let mut regs = Registers::new();
     regs.set('a', 7);
-    execute_all(instructions, &mut regs);
-    regs.get('a')
+    let mut machine = Machine::new(instructions, regs);
+    machine.run(&mut OutputCollector::default());
+    machine.regs.get('a')
 }
 
 pub fn part1(input: &str) -> String {
@@ -1,7 +1,9 @@
 use std::fs;
 
+use virtual_cpu::bus::StoppingCollector;
 use virtual_cpu::instruction::{build_list, Instruction};
 use virtual_cpu::intchar::IntChar;
+use virtual_cpu::machine::Machine;
 use virtual_cpu::registers::Registers;
 use virtual_cpu::run_utils;
 
@@ -13,18 +15,6 @@ fn build_instruction(s: &str) -> Instruction {
     }
 }
 
-// Executes the instruction specified by ins, modifying the registers if needed.
-// Returns the output of "out" command if it was executed.
-fn execute(instructions: &[Instruction], ir: &mut usize, regs: &mut Registers<i64>) -> Option<i64> {
-    let ins = &instructions[*ir];
-    if let Instruction::Out(x) = ins {
-        *ir += 1;
-        return Some(regs.get_ic(*x));
-    }
-    ins.execute(ir, regs);
-    None
-}
-
 fn is_periodic_clock_signal(clock_signal: &[i64]) -> bool {
     let mut periodic = true;
     let mut iter = clock_signal.iter().peekable();
@@ -41,21 +31,14 @@ fn is_periodic_clock_signal(clock_signal: &[i64]) -> bool {
 
 fn execute_all<const MAX_CLOCK_SIGNALS_TO_CHECK: usize>(
     instructions: &[Instruction],
-    mut regs: Registers<i64>,
+    regs: Registers<i64>,
 ) -> bool {
-    let mut ir = 0;
-    let mut clock_signal: Vec<i64> = Vec::new();
-    while ir < instructions.len()
-        && is_periodic_clock_signal(&clock_signal)
-        && clock_signal.len() < MAX_CLOCK_SIGNALS_TO_CHECK
-    {
-        let out = execute(instructions, &mut ir, &mut regs);
-        if let Some(s) = out {
-            assert!(s == 0 || s == 1);
-            clock_signal.push(s);
-        }
-    }
-    is_periodic_clock_signal(&clock_signal)
+    let mut bus = StoppingCollector::new(|clock_signal: &[i64]| {
+        !is_periodic_clock_signal(clock_signal) || clock_signal.len() >= MAX_CLOCK_SIGNALS_TO_CHECK
+    });
+    Machine::new(instructions, regs).run(&mut bus);
+    assert!(bus.output.iter().all(|s| *s == 0 || *s == 1));
+    is_periodic_clock_signal(&bus.output)
 }
 
 fn lowest_possible_int(instructions: &[Instruction]) -> i64 {
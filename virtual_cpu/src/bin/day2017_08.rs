@@ -0,0 +1,59 @@
+use std::fs;
+
+use virtual_cpu::conditional::{build_list, execute_all, Instruction, Registers};
+use virtual_cpu::run_utils;
+
+// Returns the largest value in any register at the end, and the highest value held during the execution.
+fn largest_value_any_register(instructions: &[Instruction]) -> (i64, i64) {
+    let mut regs = Registers::default();
+    let highest = execute_all(instructions, &mut regs);
+    // Registers that were never modified are at 0, but aren't in the list.
+    let largest = instructions
+        .iter()
+        .flat_map(|ins| [&ins.reg, &ins.cond_reg])
+        .map(|r| regs.get(r).copied().unwrap_or_default())
+        .max()
+        .unwrap_or(0);
+    (largest, highest)
+}
+
+pub fn part1(input: &str) -> String {
+    let instructions = build_list(input);
+    largest_value_any_register(&instructions).0.to_string()
+}
+
+pub fn part2(input: &str) -> String {
+    let instructions = build_list(input);
+    largest_value_any_register(&instructions).1.to_string()
+}
+
+#[allow(dead_code)]
+fn main() {
+    let input_file = run_utils::get_input_file("day2017_08");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    println!("Part 1: {}", part1(&input));
+    println!("Part 2: {}", part2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT_TEST: &str = include_str!("test_input/day2017_08_input_test_1");
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(INPUT_TEST), "1");
+    }
+
+    #[test]
+    fn test_part1_negative() {
+        assert_eq!(part1("a dec 3 if b == 0"), "0");
+        assert_eq!(part1("a dec 3 if a == 0"), "-3");
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(INPUT_TEST), "10");
+    }
+}
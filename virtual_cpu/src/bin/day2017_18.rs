@@ -1,7 +1,9 @@
 use std::fs;
 
+use virtual_cpu::bus::{Channel, SoundRegister};
 use virtual_cpu::instruction::{build_list, Instruction};
 use virtual_cpu::intchar::IntChar;
+use virtual_cpu::machine::Machine;
 use virtual_cpu::parsing::char;
 use virtual_cpu::registers::Registers;
 use virtual_cpu::run_utils;
@@ -15,45 +17,33 @@ fn build_instruction(s: &str) -> Instruction {
     }
 }
 
-// Executes the instruction specified by ins, modifying the registers if needed.
-fn execute_sound_playing(
-    instructions: &[Instruction],
-    ir: &mut usize,
-    regs: &mut Registers<i64>,
-    last_sound_played: &mut i64,
-) -> Option<i64> {
-    let ins = &instructions[*ir];
-    match ins {
-        Instruction::Snd(x) => {
-            // plays a sound with a frequency equal to the value of X
-            *last_sound_played = regs.get_ic(*x);
-            *ir += 1;
-        }
-        Instruction::Rcv(x) => {
-            if regs.get(*x) != 0 {
-                // recovers the frequency of the last sound played
-                return Some(*last_sound_played);
-            }
-            *ir += 1;
-        }
-        _ => ins.execute(ir, regs),
-    }
-    None
+fn recovered_frequency_value(instructions: &[Instruction]) -> i64 {
+    let mut bus = SoundRegister::default();
+    Machine::new(instructions, Registers::new()).run(&mut bus);
+    bus.recovered.expect("Didn't find a recovered sound")
 }
 
-fn recovered_frequency_value(instructions: &[Instruction]) -> i64 {
-    let mut regs = Registers::new();
-    let mut last_sound_played = 0;
-    let mut ir = 0;
-    while ir < instructions.len() {
-        // println!("{}: Exec {:?} for {:?}", ir, instructions[ir], regs);
-        if let Some(recv_snd) =
-            execute_sound_playing(instructions, &mut ir, &mut regs, &mut last_sound_played)
-        {
-            return recv_snd;
+// Runs two instances of the program, connected with channels, until both are stuck waiting.
+fn program_1_send_count(instructions: &[Instruction]) -> usize {
+    let mut machines: Vec<Machine> = (0..2)
+        .map(|id| {
+            let mut regs = Registers::new();
+            regs.set('p', id);
+            Machine::new(instructions, regs)
+        })
+        .collect();
+    let mut channels = [Channel::default(), Channel::default()];
+
+    loop {
+        let steps = machines[0].run(&mut channels[0]) + machines[1].run(&mut channels[1]);
+        let [c0, c1] = &mut channels;
+        let transferred = c0.transfer_to(c1) | c1.transfer_to(c0);
+        if steps == 0 && !transferred {
+            // Deadlock or both terminated.
+            break;
         }
     }
-    panic!("Didn't find a recovered sound")
+    channels[1].sent_count
 }
 
 pub fn part1(input: &str) -> String {
@@ -61,11 +51,17 @@ pub fn part1(input: &str) -> String {
     recovered_frequency_value(&instructions).to_string()
 }
 
+pub fn part2(input: &str) -> String {
+    let instructions = build_list(input, build_instruction);
+    program_1_send_count(&instructions).to_string()
+}
+
 #[allow(dead_code)]
 fn main() {
     let input_file = run_utils::get_input_file("day2017_18");
     let input = fs::read_to_string(input_file).expect("Unable to read input file");
     println!("Part 1: {}", part1(&input));
+    println!("Part 2: {}", part2(&input));
 }
 
 #[cfg(test)]
@@ -81,4 +77,14 @@ mod tests {
             4
         );
     }
+
+    const INPUT_TEST_2: &str = include_str!("test_input/day2017_18_input_test_2");
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(
+            program_1_send_count(&build_list(INPUT_TEST_2, build_instruction)),
+            3
+        );
+    }
 }
@@ -1,32 +1,21 @@
 use std::fs;
 
+use virtual_cpu::bus::OutputCollector;
 use virtual_cpu::c_code::gen::get_c_code_full;
 use virtual_cpu::instruction::Instruction;
+use virtual_cpu::machine::Machine;
 use virtual_cpu::registers::Registers;
 use virtual_cpu::run_utils;
 
-fn execute(instructions: &[Instruction], ir: &mut usize, regs: &mut Registers<i64>) -> bool {
-    let ins = &instructions[*ir];
-    match ins {
-        // Overwriting Mul from default
-        Instruction::Mul(x, y) => {
-            regs.set(*x, regs.get(*x) * regs.get_ic(*y));
-            *ir += 1;
-            return true;
-        }
-        _ => ins.execute(ir, regs),
-    }
-    false
-}
-
 fn mul_count(instructions: &[Instruction]) -> usize {
     let mut mul_invocations = 0;
-    let mut regs = Registers::new();
-    let mut ir = 0;
-    while ir < instructions.len() {
-        if execute(instructions, &mut ir, &mut regs) {
+    let mut machine = Machine::new(instructions, Registers::new());
+    let mut bus = OutputCollector::default();
+    while let Some(ins) = machine.current() {
+        if matches!(ins, Instruction::Mul(..)) {
             mul_invocations += 1;
         }
+        machine.step(&mut bus);
     }
     mul_invocations
 }
@@ -0,0 +1,68 @@
+use std::fs;
+
+use virtual_cpu::elfcode::{decode_program, find_opcodes, Sample};
+use virtual_cpu::run_utils;
+
+fn samples_matching_3_or_more(samples: &[Sample]) -> usize {
+    samples
+        .iter()
+        .filter(|s| s.matching_opcodes().len() >= 3)
+        .count()
+}
+
+fn reg0_at_end(samples: &[Sample], test_program: &[[i64; 4]]) -> i64 {
+    let opcodes = find_opcodes(samples);
+    // Verifies the opcodes mapping we found work for all the samples.
+    assert!(samples
+        .iter()
+        .all(|sample| sample.matches(opcodes[sample.opcode_number()])));
+
+    let program = decode_program(test_program, &opcodes);
+    let mut regs = vec![0; 4];
+    program.execute_all(&mut regs);
+    regs[0]
+}
+
+pub fn part1(input: &str) -> String {
+    let (samples, _) = Sample::build_list(input);
+    samples_matching_3_or_more(&samples).to_string()
+}
+
+pub fn part2(input: &str) -> String {
+    let (samples, test_program) = Sample::build_list(input);
+    reg0_at_end(&samples, &test_program).to_string()
+}
+
+#[allow(dead_code)]
+fn main() {
+    let input_file = run_utils::get_input_file("day2018_16");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    println!("Part 1: {}", part1(&input));
+    println!("Part 2: {}", part2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_cpu::elfcode::Opcode;
+
+    use super::*;
+
+    const INPUT_TEST: &str = include_str!("test_input/day2018_16_input_test_1");
+
+    #[test]
+    fn test_sample_matches() {
+        let sample = &Sample::build_list(INPUT_TEST).0[0];
+        assert!(!sample.matches(Opcode::Addr));
+        assert!(sample.matches(Opcode::Addi));
+        assert_eq!(
+            sample.matching_opcodes(),
+            [Opcode::Addi, Opcode::Mulr, Opcode::Seti]
+        );
+    }
+
+    #[test]
+    fn test_part1() {
+        let (samples, _) = Sample::build_list(INPUT_TEST);
+        assert_eq!(samples_matching_3_or_more(&samples), 1);
+    }
+}
@@ -0,0 +1,121 @@
+use std::fs;
+use std::ops::RangeInclusive;
+
+use virtual_cpu::c_code::elfcode::{get_c_code, get_c_code_full};
+use virtual_cpu::elfcode::{Instruction, Opcode, Program};
+use virtual_cpu::run_utils;
+
+fn reg0_at_end(program: &Program) -> i64 {
+    let mut regs = vec![0; program.registers_count()];
+    program.execute_all(&mut regs);
+    regs[0]
+}
+
+pub fn part1(input: &str) -> String {
+    let program = Program::build(input);
+    reg0_at_end(&program).to_string()
+}
+
+pub fn part1_c_code(input: &str) -> String {
+    let program = Program::build(input);
+    get_c_code(&program, &[], &[0])
+}
+
+// Two nested loops summing all the factors of the register N into the register S:
+//
+//     seti 1 _ I ; seti 1 _ J ; mulr I J P ; eqrr P N P ; addr P ip ip ; addi ip 1 ip
+//     addr I S S ; addi J 1 J ; gtrr J N P ; addr ip P ip ; seti _ _ ip
+//     addi I 1 I ; gtrr I N P ; addr P ip ip ; seti _ _ ip ; mulr ip ip ip
+//
+// The last instruction ends the program.
+// Returns the range of the loops, N and S.
+fn find_sum_of_factors(program: &Program) -> Option<(RangeInclusive<usize>, i64, usize)> {
+    use Opcode::*;
+    const OPCODES: [Opcode; 16] = [
+        Seti, Seti, Mulr, Eqrr, Addr, Addi, Addr, Addi, Gtrr, Addr, Seti, Addi, Gtrr, Addr, Seti,
+        Mulr,
+    ];
+    let ip = program.ip_binding?;
+    let is_pair = |ins: &Instruction, x: usize, y: usize| {
+        let (x, y) = (x as i64, y as i64);
+        (ins.a, ins.b) == (x, y) || (ins.a, ins.b) == (y, x)
+    };
+    program
+        .instructions
+        .windows(OPCODES.len())
+        .enumerate()
+        .find_map(|(start, w)| {
+            if w.iter().zip(OPCODES).any(|(ins, op)| ins.opcode != op) {
+                return None;
+            }
+            let (i, j, product, sum) = (w[0].c, w[1].c, w[2].c, w[6].c);
+            let number = if w[3].a == product as i64 {
+                w[3].b
+            } else {
+                w[3].a
+            };
+            let matches = is_pair(&w[2], i, j)
+                && is_pair(&w[6], i, sum)
+                && (w[8].a, w[8].b) == (j as i64, number)
+                && (w[12].a, w[12].b) == (i as i64, number)
+                && is_pair(&w[15], ip, ip)
+                && w[15].c == ip;
+            matches.then_some((start..=start + OPCODES.len() - 1, number, sum))
+        })
+}
+
+pub fn part2_c_code(input: &str) -> String {
+    let program = Program::build(input);
+    let ip_reg = program.ip_binding.unwrap();
+    let (range, number, sum) =
+        find_sum_of_factors(&program).expect("Didn't find the sum of factors loops");
+    // The replacement ends the program, like the last instruction of the loops.
+    let optimizations = vec![(
+        range,
+        format!(
+            "\t\t\t// Sum of factors optimization
+\t\t\tr{1} = 0;
+\t\t\tfor (long long i = 1; i <= r{0}; i++) {{
+\t\t\t\tif (r{0} % i == 0) {{
+\t\t\t\t\tr{1} += i;
+\t\t\t\t}}
+\t\t\t}}
+\t\t\tr{2} = {3};
+",
+            number,
+            sum,
+            ip_reg,
+            program.instructions.len()
+        ),
+    )];
+    get_c_code_full(&program, &[1], &[0], &optimizations)
+}
+
+#[allow(dead_code)]
+fn main() {
+    let input_file = run_utils::get_input_file("day2018_19");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    println!("Part 1: {}", part1(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT_TEST_1: &str = include_str!("test_input/day2018_19_input_test_1");
+    const INPUT_TEST_2: &str = include_str!("test_input/day2018_19_input_test_2");
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(reg0_at_end(&Program::build(INPUT_TEST_1)), 6);
+    }
+
+    #[test]
+    fn test_sum_of_factors() {
+        let program = Program::build(INPUT_TEST_2);
+        assert_eq!(find_sum_of_factors(&program), Some((1..=16, 2, 0)));
+        assert_eq!(reg0_at_end(&program), 1 + 2 + 3 + 4 + 6 + 12);
+
+        assert_eq!(find_sum_of_factors(&Program::build(INPUT_TEST_1)), None);
+    }
+}
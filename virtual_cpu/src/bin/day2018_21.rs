@@ -0,0 +1,164 @@
+use std::fs;
+use std::ops::RangeInclusive;
+
+use virtual_cpu::c_code::elfcode::get_c_code_full;
+use virtual_cpu::elfcode::{Opcode, Program};
+use virtual_cpu::run_utils;
+
+// The only instruction using r0 is the check that ends the program, of the form `eqrr X 0 Y`.
+// Returns its position and the register compared to r0.
+fn find_halting_check(program: &Program) -> (usize, usize) {
+    program
+        .instructions
+        .iter()
+        .enumerate()
+        .find_map(|(ip, ins)| {
+            if ins.opcode != Opcode::Eqrr {
+                return None;
+            }
+            match (ins.a, ins.b) {
+                (0, r) | (r, 0) => Some((ip, usize::try_from(r).unwrap())),
+                _ => None,
+            }
+        })
+        .expect("Didn't find the check on r0")
+}
+
+// The program halts the fastest if r0 has the value compared at the first check.
+fn reg0_halt_least_ins(program: &Program) -> i64 {
+    let (check_ip, compared_reg) = find_halting_check(program);
+    let mut regs = vec![0; program.registers_count()];
+    let mut ip = 0;
+    program.execute_until(&mut ip, &mut regs, |ip, _| ip == check_ip);
+    regs[compared_reg]
+}
+
+pub fn part1(input: &str) -> String {
+    let program = Program::build(input);
+    reg0_halt_least_ins(&program).to_string()
+}
+
+// A loop dividing the register N by D, putting the result into Q:
+//
+//     addi Q 1 T ; muli T D T ; gtrr T N T ; addr T ip ip ; addi ip 1 ip
+//     seti <after the loop> _ ip ; addi Q 1 Q ; seti <start of the loop> _ ip
+//
+// Q is set to 0 before the loop, and T is 1 once it's done.
+fn find_division_loop(program: &Program) -> Option<(RangeInclusive<usize>, String)> {
+    use Opcode::*;
+    const OPCODES: [Opcode; 8] = [Addi, Muli, Gtrr, Addr, Addi, Seti, Addi, Seti];
+    let ip = program.ip_binding?;
+    let ip_val = ip as i64;
+    program
+        .instructions
+        .windows(OPCODES.len())
+        .enumerate()
+        .find_map(|(start, w)| {
+            if w.iter().zip(OPCODES).any(|(ins, op)| ins.opcode != op) {
+                return None;
+            }
+            let (quotient, tmp, divisor, number) = (w[0].a, w[0].c, w[1].b, w[2].b);
+            let tmp_val = tmp as i64;
+            let matches = w[0].b == 1
+                && (w[1].a, w[1].c) == (tmp_val, tmp)
+                && (w[2].a, w[2].c) == (tmp_val, tmp)
+                && ((w[3].a, w[3].b) == (tmp_val, ip_val) || (w[3].a, w[3].b) == (ip_val, tmp_val))
+                && w[3].c == ip
+                && (w[4].a, w[4].b, w[4].c) == (ip_val, 1, ip)
+                && (w[5].a, w[5].c) == ((start + OPCODES.len() - 1) as i64, ip)
+                && (w[6].a, w[6].b, w[6].c) == (quotient, 1, quotient as usize)
+                && (w[7].a, w[7].c) == (start as i64 - 1, ip);
+            matches.then(|| {
+                (
+                    start..=start + OPCODES.len() - 1,
+                    format!(
+                        "\t\t\tr{0} = r{1} / {2};\n\t\t\tr{3} = 1;\n",
+                        quotient, number, divisor, tmp
+                    ),
+                )
+            })
+        })
+}
+
+pub fn part1_c_code(input: &str) -> String {
+    let program = Program::build(input);
+    let (check_ip, compared_reg) = find_halting_check(&program);
+    let mut optimizations = vec![(
+        check_ip..=check_ip,
+        format!(
+            "\t\t\tprintf(\"%lli\\n\", r{});
+\t\t\treturn 0;
+",
+            compared_reg
+        ),
+    )];
+    optimizations.extend(find_division_loop(&program));
+    get_c_code_full(&program, &[], &[], &optimizations)
+}
+
+pub fn part2_c_code(input: &str) -> String {
+    let program = Program::build(input);
+    let (check_ip, compared_reg) = find_halting_check(&program);
+    let check_result_reg = program.instructions[check_ip].c;
+    // The compared values are all on 24 bits. We keep running until we get one we have already seen,
+    // the answer is the one just before.
+    let mut optimizations = vec![(
+        check_ip..=check_ip,
+        format!(
+            "\t\t\tstatic char seen[1 << 24];
+\t\t\tstatic long long last = 0;
+\t\t\tif (seen[r{0}]) {{
+\t\t\t\tprintf(\"%lli\\n\", last);
+\t\t\t\treturn 0;
+\t\t\t}}
+\t\t\tseen[r{0}] = 1;
+\t\t\tlast = r{0};
+\t\t\tr{1} = 0;
+",
+            compared_reg, check_result_reg
+        ),
+    )];
+    optimizations.extend(find_division_loop(&program));
+    get_c_code_full(&program, &[], &[], &optimizations)
+}
+
+#[allow(dead_code)]
+fn main() {
+    let input_file = run_utils::get_input_file("day2018_21");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    println!("Part 1: {}", part1(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT_TEST: &str = include_str!("test_input/day2018_21_input_test_1");
+
+    #[test]
+    fn test_part1() {
+        let program = Program::build(INPUT_TEST);
+        let reg0 = reg0_halt_least_ins(&program);
+        assert_eq!(reg0, 1000 / 256);
+
+        // With this value the program halts, instead of looping forever.
+        let mut regs = vec![0; program.registers_count()];
+        regs[0] = reg0;
+        let mut ip = 0;
+        let mut steps = 0;
+        program.execute_until(&mut ip, &mut regs, |_, _| {
+            steps += 1;
+            steps > 1000
+        });
+        assert_eq!(ip, program.instructions.len());
+    }
+
+    #[test]
+    fn test_find_division_loop() {
+        let program = Program::build(INPUT_TEST);
+        assert_eq!(
+            find_division_loop(&program),
+            Some((2..=9, "\t\t\tr5 = r4 / 256;\n\t\t\tr2 = 1;\n".to_string()))
+        );
+    }
+}
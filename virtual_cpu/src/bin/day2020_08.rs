@@ -0,0 +1,77 @@
+use std::fs;
+
+use virtual_cpu::instruction::{build_list, Condition, Instruction};
+use virtual_cpu::intchar::IntChar;
+use virtual_cpu::registers::Registers;
+use virtual_cpu::run_utils;
+use virtual_cpu::termination::{repair, run_until_repeat, swap_jmp_nop};
+
+// The accumulator is register 'a'.
+fn build_instruction(s: &str) -> Instruction {
+    let parts: Vec<_> = s.split(' ').collect();
+    let arg = IntChar::new(parts[1]);
+    match parts[0] {
+        "acc" => Instruction::Add('a', arg),
+        "jmp" => Instruction::JumpIf(Condition::True, IntChar::from(0), arg, |_| true),
+        // The argument is kept, as the nop can be changed into a jump.
+        "nop" => Instruction::JumpIf(Condition::False, IntChar::from(0), arg, |_| false),
+        _ => panic!("Invalid instruction {}", s),
+    }
+}
+
+fn accumulator_after_one_run(instructions: &[Instruction]) -> i64 {
+    let mut regs = Registers::new();
+    run_until_repeat(instructions, &mut regs);
+    regs.get('a')
+}
+
+fn accumulator_after_fix(instructions: &[Instruction]) -> i64 {
+    let (_, regs) = repair(instructions, &Registers::new(), swap_jmp_nop).expect("No fix found");
+    regs.get('a')
+}
+
+pub fn part1(input: &str) -> String {
+    let instructions = build_list(input, build_instruction);
+    accumulator_after_one_run(&instructions).to_string()
+}
+
+pub fn part2(input: &str) -> String {
+    let instructions = build_list(input, build_instruction);
+    accumulator_after_fix(&instructions).to_string()
+}
+
+#[allow(dead_code)]
+fn main() {
+    let input_file = run_utils::get_input_file("day2020_08");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    println!("Part 1: {}", part1(&input));
+    println!("Part 2: {}", part2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_cpu::termination::Termination;
+
+    use super::*;
+
+    const INPUT_TEST: &str = include_str!("test_input/day2020_08_input_test_1");
+
+    #[test]
+    fn test_part1() {
+        let instructions = build_list(INPUT_TEST, build_instruction);
+        let mut regs = Registers::new();
+        assert_eq!(
+            run_until_repeat(&instructions, &mut regs),
+            Termination::Loop
+        );
+        assert_eq!(regs.get('a'), 5);
+    }
+
+    #[test]
+    fn test_part2() {
+        let instructions = build_list(INPUT_TEST, build_instruction);
+        let (fixed_index, regs) = repair(&instructions, &Registers::new(), swap_jmp_nop).unwrap();
+        assert_eq!(fixed_index, 7);
+        assert_eq!(regs.get('a'), 8);
+    }
+}
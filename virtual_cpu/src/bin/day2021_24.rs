@@ -0,0 +1,96 @@
+use std::fs;
+
+use virtual_cpu::alu::InputSearch;
+use virtual_cpu::instruction::{build_list, Instruction};
+use virtual_cpu::intchar::IntChar;
+use virtual_cpu::parsing::char;
+use virtual_cpu::run_utils;
+
+fn build_instruction(s: &str) -> Instruction {
+    let parts: Vec<_> = s.split(' ').collect();
+    match *parts.first().unwrap() {
+        "inp" => Instruction::Inp(char(parts[1])),
+        "eql" => Instruction::Eql(char(parts[1]), IntChar::new(parts[2])),
+        _ => Instruction::build(s),
+    }
+}
+
+fn to_number(digits: &[i64]) -> i64 {
+    digits.iter().fold(0, |acc, d| acc * 10 + d)
+}
+
+fn largest_accepted_number(instructions: &[Instruction]) -> i64 {
+    let digits = InputSearch::new(instructions, 1..=9, 'z', 0)
+        .largest()
+        .expect("No valid model number");
+    to_number(&digits)
+}
+
+fn smallest_accepted_number(instructions: &[Instruction]) -> i64 {
+    let digits = InputSearch::new(instructions, 1..=9, 'z', 0)
+        .smallest()
+        .expect("No valid model number");
+    to_number(&digits)
+}
+
+pub fn part1(input: &str) -> String {
+    let instructions = build_list(input, build_instruction);
+    largest_accepted_number(&instructions).to_string()
+}
+
+pub fn part2(input: &str) -> String {
+    let instructions = build_list(input, build_instruction);
+    smallest_accepted_number(&instructions).to_string()
+}
+
+#[allow(dead_code)]
+fn main() {
+    let input_file = run_utils::get_input_file("day2021_24");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    println!("Part 1: {}", part1(&input));
+    println!("Part 2: {}", part2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_cpu::alu::execute_all;
+    use virtual_cpu::registers::Registers;
+
+    use super::*;
+
+    const INPUT_TEST_1: &str = include_str!("test_input/day2021_24_input_test_1");
+    const INPUT_TEST_2: &str = include_str!("test_input/day2021_24_input_test_2");
+    const INPUT_TEST_3: &str = include_str!("test_input/day2021_24_input_test_3");
+
+    fn exec_program(input: &str, values: &[i64]) -> (i64, i64, i64, i64) {
+        let mut regs = Registers::new();
+        execute_all(&build_list(input, build_instruction), &mut regs, values);
+        (regs.get('w'), regs.get('x'), regs.get('y'), regs.get('z'))
+    }
+
+    #[test]
+    fn test_execution() {
+        assert_eq!(exec_program(INPUT_TEST_1, &[22]), (0, -22, 0, 0));
+        assert_eq!(exec_program(INPUT_TEST_2, &[5, 9]), (0, 9, 0, 0));
+        assert_eq!(exec_program(INPUT_TEST_2, &[5, 15]), (0, 15, 0, 1));
+        assert_eq!(exec_program(INPUT_TEST_3, &[10]), (1, 0, 1, 0));
+    }
+
+    #[test]
+    fn test_search() {
+        // z is 1 when the second number is three times the first.
+        let instructions = build_list(INPUT_TEST_2, build_instruction);
+        assert_eq!(
+            InputSearch::new(&instructions, 1..=9, 'z', 1).largest(),
+            Some(vec![3, 9])
+        );
+        assert_eq!(
+            InputSearch::new(&instructions, 1..=9, 'z', 1).smallest(),
+            Some(vec![1, 3])
+        );
+        assert_eq!(
+            InputSearch::new(&instructions, 1..=9, 'z', 2).smallest(),
+            None
+        );
+    }
+}
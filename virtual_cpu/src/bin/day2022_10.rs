@@ -0,0 +1,169 @@
+use std::{fmt, fs};
+
+use virtual_cpu::bus::Bus;
+use virtual_cpu::instruction::{build_list, Instruction};
+use virtual_cpu::intchar::IntChar;
+use virtual_cpu::machine::Machine;
+use virtual_cpu::ocr::parse_letters;
+use virtual_cpu::registers::Registers;
+use virtual_cpu::run_utils;
+
+fn build_instruction(s: &str) -> Instruction {
+    let parts: Vec<_> = s.split(' ').collect();
+    match parts[0] {
+        "addx" => Instruction::Add('x', IntChar::new(parts[1])),
+        "noop" => Instruction::Nop,
+        _ => panic!("Invalid instruction {}", s),
+    }
+}
+
+fn cycle_cost(ins: &Instruction) -> usize {
+    if matches!(ins, Instruction::Add(..)) {
+        2
+    } else {
+        1
+    }
+}
+
+// Runs the program, with the X register starting at 1.
+fn run<B: Bus>(instructions: &[Instruction], bus: &mut B) {
+    let mut regs = Registers::new();
+    regs.set('x', 1);
+    let mut machine = Machine::new(instructions, regs).with_cycle_cost(cycle_cost);
+    machine.run(bus);
+}
+
+// The program does no I/O, these buses just look at the cycles.
+
+#[derive(Default)]
+struct SignalStrengths {
+    sum: i64,
+}
+
+impl Bus for SignalStrengths {
+    fn write(&mut self, _v: i64) {}
+
+    fn read(&mut self, _current: i64) -> Option<i64> {
+        None
+    }
+
+    fn on_cycle(&mut self, cycle: usize, regs: &Registers<i64>) {
+        const CHECKPOINTS: [usize; 6] = [20, 60, 100, 140, 180, 220];
+        if CHECKPOINTS.contains(&cycle) {
+            self.sum += cycle as i64 * regs.get('x');
+        }
+    }
+}
+
+const ROWS: usize = 6;
+const COLS: usize = 40;
+
+struct Crt {
+    screen: [bool; COLS * ROWS],
+}
+
+impl Crt {
+    fn new() -> Self {
+        Self {
+            screen: [false; COLS * ROWS],
+        }
+    }
+}
+
+impl fmt::Display for Crt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self
+            .screen
+            .chunks(COLS)
+            .map(|row| row.iter().map(|e| if *e { '#' } else { '.' }).collect())
+            .collect::<Vec<String>>()
+            .join("\n");
+        write!(f, "{}", s)
+    }
+}
+
+impl Bus for Crt {
+    fn write(&mut self, _v: i64) {}
+
+    fn read(&mut self, _current: i64) -> Option<i64> {
+        None
+    }
+
+    // Draws the pixel if the sprite is currently on the CRT pixel position.
+    fn on_cycle(&mut self, cycle: usize, regs: &Registers<i64>) {
+        let pos = cycle - 1;
+        let sprite_center = regs.get('x');
+        let horiz_pos = (pos % COLS) as i64;
+        if (sprite_center - 1..=sprite_center + 1).contains(&horiz_pos) {
+            if let Some(pixel) = self.screen.get_mut(pos) {
+                *pixel = true;
+            }
+        }
+    }
+}
+
+fn signal_strengths_sum(instructions: &[Instruction]) -> i64 {
+    let mut signal_strengths = SignalStrengths::default();
+    run(instructions, &mut signal_strengths);
+    signal_strengths.sum
+}
+
+fn crt_picture(instructions: &[Instruction]) -> String {
+    let mut crt = Crt::new();
+    run(instructions, &mut crt);
+    crt.to_string()
+}
+
+pub fn part1(input: &str) -> String {
+    let instructions = build_list(input, build_instruction);
+    signal_strengths_sum(&instructions).to_string()
+}
+
+pub fn part2(input: &str) -> String {
+    let instructions = build_list(input, build_instruction);
+    parse_letters(&crt_picture(&instructions)).expect("Invalid screen")
+}
+
+#[allow(dead_code)]
+fn main() {
+    let input_file = run_utils::get_input_file("day2022_10");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    println!("Part 1: {}", part1(&input));
+    println!("Part 2: {}", part2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT_TEST: &str = include_str!("test_input/day2022_10_input_test_1");
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(INPUT_TEST), "13140");
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(
+            crt_picture(&build_list(INPUT_TEST, build_instruction)),
+            "##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."
+        );
+    }
+
+    #[test]
+    fn test_parse_letters() {
+        let screen = ".##..#..#.####
+#..#.#.#.....#
+#..#.##.....#.
+#..#.#.#...#..
+#..#.#.#..#...
+.##..#..#.####";
+        assert_eq!(parse_letters(screen).unwrap(), "OKZ");
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs;
+
+use virtual_cpu::chronospatial::{
+    build, find_reg_a_for_self_replicate, run_program, Instruction, Registers,
+};
+use virtual_cpu::run_utils;
+
+fn final_output(registers: &Registers, program: &[Instruction]) -> String {
+    let mut regs = *registers;
+    let output = run_program(&mut regs, program);
+    output
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn part1(input: &str) -> String {
+    let (registers, program) = build(input);
+    final_output(&registers, &program)
+}
+
+pub fn part2(input: &str) -> String {
+    let (registers, program) = build(input);
+    find_reg_a_for_self_replicate(&registers, &program)
+        .expect("No value of A found")
+        .to_string()
+}
+
+#[allow(dead_code)]
+fn main() {
+    let input_file = run_utils::get_input_file("day2024_17");
+    let input = fs::read_to_string(input_file).expect("Unable to read input file");
+    println!("Part 1: {}", part1(&input));
+    println!("Part 2: {}", part2(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_cpu::chronospatial::{build_program, disassemble, program_to_string};
+
+    use super::*;
+
+    const INPUT_TEST_1: &str = include_str!("test_input/day2024_17_input_test_1");
+    const INPUT_TEST_2: &str = include_str!("test_input/day2024_17_input_test_2");
+
+    #[test]
+    fn test_instructions() {
+        // If register C contains 9, the program 2,6 would set register B to 1.
+        let mut regs = Registers::new(0, 0, 9);
+        assert_eq!(run_program(&mut regs, &build_program("2,6")), vec![]);
+        assert_eq!(regs.b, 1);
+
+        // If register A contains 10, the program 5,0,5,1,5,4 would output 0,1,2.
+        let mut regs = Registers::new(10, 0, 0);
+        assert_eq!(
+            run_program(&mut regs, &build_program("5,0,5,1,5,4")),
+            vec![0, 1, 2]
+        );
+
+        // If register A contains 2024, the program 0,1,5,4,3,0 would output 4,2,5,6,7,7,7,7,3,1,0 and leave 0 in register A.
+        let mut regs = Registers::new(2024, 0, 0);
+        assert_eq!(
+            run_program(&mut regs, &build_program("0,1,5,4,3,0")),
+            vec![4, 2, 5, 6, 7, 7, 7, 7, 3, 1, 0]
+        );
+        assert_eq!(regs.a, 0);
+
+        // If register B contains 29, the program 1,7 would set register B to 26.
+        let mut regs = Registers::new(0, 29, 0);
+        assert_eq!(run_program(&mut regs, &build_program("1,7")), vec![]);
+        assert_eq!(regs.b, 26);
+
+        // If register B contains 2024 and register C contains 43690, the program 4,0 would set register B to 44354.
+        let mut regs = Registers::new(0, 2024, 43690);
+        assert_eq!(run_program(&mut regs, &build_program("4,0")), vec![]);
+        assert_eq!(regs.b, 44354);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let (_, program) = build(INPUT_TEST_2);
+        assert_eq!(program_to_string(&program), "0,3,5,4,3,0");
+        assert_eq!(
+            disassemble(&program),
+            "  0: 0,3  adv 3  A = A >> 3
+  2: 5,4  out A  output A % 8
+  4: 3,0  jnz 0  if A != 0 jump 0
+"
+        );
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(INPUT_TEST_1), "4,6,3,5,6,3,5,2,1,0");
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(INPUT_TEST_2), "117440");
+        // Program 1 is not a loop shifting A by 3 bits.
+        let (registers, program) = build(INPUT_TEST_1);
+        assert_eq!(find_reg_a_for_self_replicate(&registers, &program), None);
+    }
+}
@@ -2,47 +2,99 @@ mod day2015_23;
 mod day2016_12;
 mod day2016_23;
 mod day2016_25;
+mod day2017_08;
 mod day2017_18;
 mod day2017_23;
+mod day2018_16;
+mod day2018_19;
+mod day2018_21;
+mod day2020_08;
+mod day2021_24;
+mod day2022_10;
+mod day2024_17;
 
-use virtual_cpu::c_code::exec::exec_c_code;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use virtual_cpu::c_code::exec::CBuild;
+use virtual_cpu::puzzles;
 use virtual_cpu::run_utils::*;
 
-fn main() {
+fn main() -> ExitCode {
     #[rustfmt::skip]
-    let puzzles = vec![
-        Puzzle::both("day2015_23", 1, day2015_23::part1, day2015_23::part1_c_code),
-        Puzzle::both("day2015_23", 2, day2015_23::part2, day2015_23::part2_c_code),
-        Puzzle::both("day2016_12", 1, day2016_12::part1, day2016_12::part1_c_code),
-        Puzzle::both("day2016_12", 2, day2016_12::part2, day2016_12::part2_c_code),
-        Puzzle::base("day2016_23", 1, day2016_23::part1), // C not possible
-        Puzzle::base("day2016_25", 1, day2016_25::part1),
-        Puzzle::base("day2017_18", 1, day2017_18::part1),
-        Puzzle::base("day2017_23", 1, day2017_23::part1),
-        Puzzle::with_c("day2017_23", 2, day2017_23::part2_c_code), // Only C, other too slow.
+    let puzzles = puzzles![
+        day2015_23 [1: part1 / part1_c_code] [2: part2 / part2_c_code],
+        day2016_12 [1: part1 / part1_c_code] [2: part2 / part2_c_code],
+        day2016_23 [1: part1], // C not possible
+        day2016_25 [1: part1],
+        day2017_08 [1: part1] [2: part2],
+        day2017_18 [1: part1] [2: part2],
+        day2017_23 [1: part1] [2: _ / part2_c_code], // Only C, other too slow.
+        day2018_16 [1: part1] [2: part2],
+        day2018_19 [1: part1 / part1_c_code] [2: _ / part2_c_code], // Only C, other too slow.
+        day2018_21 [1: part1 / part1_c_code] [2: _ / part2_c_code], // Only C, other too slow.
+        day2020_08 [1: part1] [2: part2],
+        day2021_24 [1: part1] [2: part2],
+        day2022_10 [1: part1] [2: part2],
+        day2024_17 [1: part1] [2: part2],
     ];
 
+    let filter = match Filter::from_args(std::env::args().skip(1)) {
+        Ok(filter) => filter,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("Usage: main [--year YYYY] [--day DD] [--part N] [--backend c|native]");
+            return ExitCode::FAILURE;
+        }
+    };
+
     let answers = load_answer_list();
+    // Compiler can be changed with the CC environment variable.
+    let c_build = CBuild::new();
 
-    for puzzle in puzzles {
-        let expected_result = answers
-            .get(&format!("{}_{}", puzzle.name, puzzle.part_nb))
-            .unwrap_or_else(|| {
-                panic!("Missing answer for {} part {}", puzzle.name, puzzle.part_nb)
-            });
+    let mut all_ok = true;
+    for puzzle in puzzles.iter().filter(|p| filter.matches(p)) {
+        let expected_result = answers.get(&format!("{}_{}", puzzle.name, puzzle.part_nb));
 
-        let input = puzzle.get_input();
+        let input = match puzzle.get_input() {
+            Ok(input) => input,
+            Err(err) => {
+                println!(
+                    "❌ {} day {}, part {}",
+                    puzzle.year, puzzle.day, puzzle.part_nb
+                );
+                println!("Unable to read input file: {}", err);
+                all_ok = false;
+                continue;
+            }
+        };
 
-        if let Some(c_code_fn) = puzzle.c_code_fn {
+        let mut results = Vec::new();
+        if let Some(c_code_fn) = puzzle.c_code_fn.filter(|_| filter.runs(Backend::C)) {
+            let start = Instant::now();
             let code = c_code_fn(&input);
             // println!("{}", code);
-            let res = exec_c_code(&code);
-            print_result(&puzzle, &res, expected_result, true);
+            let res = c_build.run(&code).map_err(|err| err.to_string());
+            results.push((res, Backend::C, start.elapsed()));
         }
 
-        if let Some(puzzle_fn) = puzzle.puzzle_fn {
+        if let Some(puzzle_fn) = puzzle.puzzle_fn.filter(|_| filter.runs(Backend::Native)) {
+            let start = Instant::now();
             let res = puzzle_fn(&input);
-            print_result(&puzzle, &res, expected_result, false);
-        };
+            results.push((Ok(res), Backend::Native, start.elapsed()));
+        }
+
+        for (res, backend, elapsed) in results {
+            let status = print_result(puzzle, res, expected_result, backend, elapsed);
+            if matches!(status, Status::Incorrect | Status::Error) {
+                all_ok = false;
+            }
+        }
+    }
+
+    if all_ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
     }
 }
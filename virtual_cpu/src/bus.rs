@@ -0,0 +1,146 @@
+//! Input/Output support for the instructions that need it, like `snd`, `rcv`, `out` or `inp`.
+//!
+//! Similar to the `Bus` of the 2019 Intcode computer.
+
+use std::collections::VecDeque;
+
+use crate::registers::Registers;
+
+/// Trait that abstracts Input/Output support in the machine.
+pub trait Bus {
+    /// Called by the output instructions (`snd`, `out`).
+    fn write(&mut self, v: i64);
+
+    /// Called by the input instructions (`rcv`, `inp`), with the current value of the register.
+    /// Returns the new value of the register, or None if there is no value available,
+    /// in which case the machine waits on this instruction.
+    fn read(&mut self, current: i64) -> Option<i64>;
+
+    /// Indicates that the machine should stop executing.
+    fn is_done(&self) -> bool {
+        false
+    }
+
+    /// Called for each cycle of an instruction, before the instruction modifies the registers.
+    /// Cycles are counted from 1.
+    fn on_cycle(&mut self, _cycle: usize, _regs: &Registers<i64>) {}
+}
+
+/// Collects all the output. There is never any input.
+#[derive(Debug, Default)]
+pub struct OutputCollector {
+    pub output: Vec<i64>,
+}
+
+impl Bus for OutputCollector {
+    fn write(&mut self, v: i64) {
+        self.output.push(v);
+    }
+
+    fn read(&mut self, _current: i64) -> Option<i64> {
+        None
+    }
+}
+
+/// Collects all the output, until the predicate on the output collected so far says to stop.
+pub struct StoppingCollector<F>
+where
+    F: Fn(&[i64]) -> bool,
+{
+    pub output: Vec<i64>,
+    stop_fn: F,
+}
+
+impl<F> StoppingCollector<F>
+where
+    F: Fn(&[i64]) -> bool,
+{
+    pub fn new(stop_fn: F) -> Self {
+        Self {
+            output: Vec::new(),
+            stop_fn,
+        }
+    }
+}
+
+impl<F> Bus for StoppingCollector<F>
+where
+    F: Fn(&[i64]) -> bool,
+{
+    fn write(&mut self, v: i64) {
+        self.output.push(v);
+    }
+
+    fn read(&mut self, _current: i64) -> Option<i64> {
+        None
+    }
+
+    fn is_done(&self) -> bool {
+        (self.stop_fn)(&self.output)
+    }
+}
+
+/// Sound playing, as in 2017 day 18 part 1.
+///
+/// Output plays a sound, and input recovers the frequency of the last sound played,
+/// if the register isn't zero. The machine stops once a sound is recovered.
+#[derive(Debug, Default)]
+pub struct SoundRegister {
+    pub last_sound_played: Option<i64>,
+    pub recovered: Option<i64>,
+}
+
+impl Bus for SoundRegister {
+    fn write(&mut self, v: i64) {
+        self.last_sound_played = Some(v);
+    }
+
+    fn read(&mut self, current: i64) -> Option<i64> {
+        if current != 0 {
+            self.recovered = self.last_sound_played;
+        }
+        // Register isn't modified.
+        Some(current)
+    }
+
+    fn is_done(&self) -> bool {
+        self.recovered.is_some()
+    }
+}
+
+/// Queues based implementation, to connect machines together.
+#[derive(Debug, Clone, Default)]
+pub struct Channel {
+    pub input: VecDeque<i64>,
+    pub output: VecDeque<i64>,
+    /// How many values were written in total.
+    pub sent_count: usize,
+}
+
+impl Channel {
+    pub fn new(input: &[i64]) -> Self {
+        Self {
+            input: input.iter().copied().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Moves the output of this channel to the input of the other one.
+    /// Returns true if anything was transferred.
+    pub fn transfer_to(&mut self, other: &mut Channel) -> bool {
+        let transferred = !self.output.is_empty();
+        other.input.extend(self.output.drain(..));
+        transferred
+    }
+}
+
+impl Bus for Channel {
+    fn write(&mut self, v: i64) {
+        self.output.push_back(v);
+        self.sent_count += 1;
+    }
+
+    fn read(&mut self, _current: i64) -> Option<i64> {
+        self.input.pop_front()
+    }
+}
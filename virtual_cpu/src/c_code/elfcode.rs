@@ -0,0 +1,111 @@
+use std::ops::RangeInclusive;
+
+use crate::elfcode::{Instruction, Opcode, Program};
+
+// Registers are named r0, r1, etc.
+fn reg(r: i64) -> String {
+    format!("r{}", r)
+}
+
+/// Generates C code for the instruction.
+fn instruction_c_version(ins: &Instruction) -> String {
+    let (a, b) = (ins.a, ins.b);
+    let expr = match ins.opcode {
+        Opcode::Addr => format!("{} + {}", reg(a), reg(b)),
+        Opcode::Addi => format!("{} + {}", reg(a), b),
+        Opcode::Mulr => format!("{} * {}", reg(a), reg(b)),
+        Opcode::Muli => format!("{} * {}", reg(a), b),
+        Opcode::Banr => format!("{} & {}", reg(a), reg(b)),
+        Opcode::Bani => format!("{} & {}", reg(a), b),
+        Opcode::Borr => format!("{} | {}", reg(a), reg(b)),
+        Opcode::Bori => format!("{} | {}", reg(a), b),
+        Opcode::Setr => reg(a),
+        Opcode::Seti => a.to_string(),
+        Opcode::Gtir => format!("{} > {}", a, reg(b)),
+        Opcode::Gtri => format!("{} > {}", reg(a), b),
+        Opcode::Gtrr => format!("{} > {}", reg(a), reg(b)),
+        Opcode::Eqir => format!("{} == {}", a, reg(b)),
+        Opcode::Eqri => format!("{} == {}", reg(a), b),
+        Opcode::Eqrr => format!("{} == {}", reg(a), reg(b)),
+    };
+    format!("r{} = {}", ins.c, expr)
+}
+
+/// Transform the elfcode program into C.
+///
+/// As the instruction pointer can be bound to a register, jumps are dynamic.
+/// The generated code is therefore a loop with a `switch` on the instruction pointer.
+///
+/// * `initial_registers` - The initial values of the registers.
+/// * `registers_to_print` - The register's values to print at the end.
+/// * `optimizations` - A block of instructions, indicated by its indexes, to be replaced
+///   with the specified code. The code is executed when the instruction pointer reaches the start
+///   of the block, and execution then continues after the block, unless the code changes the
+///   register bound to the instruction pointer.
+pub fn get_c_code_full(
+    program: &Program,
+    initial_registers: &[i64],
+    registers_to_print: &[usize],
+    optimizations: &[(RangeInclusive<usize>, String)],
+) -> String {
+    let mut code = String::new();
+    code += r"#include <stdio.h>
+
+int main() {
+";
+
+    // Declare all the registers as variables.
+    let count = program.registers_count().max(initial_registers.len());
+    for r in 0..count {
+        let val = initial_registers.get(r).copied().unwrap_or_default();
+        code += &format!("\tlong long r{} = {};\n", r, val);
+    }
+    code += "\tlong long ip = 0;\n\n";
+
+    code += &format!(
+        "\twhile (ip >= 0 && ip < {}) {{\n",
+        program.instructions.len()
+    );
+    // When no register is bound to the instruction pointer, we use a dummy variable for it.
+    let ip_reg = program
+        .ip_binding
+        .map_or("ip_reg".to_string(), |b| format!("r{}", b));
+    if program.ip_binding.is_none() {
+        code += "\t\tlong long ip_reg;\n";
+    }
+    code += &format!("\t\t{} = ip;\n", ip_reg);
+    code += "\t\tswitch (ip) {\n";
+
+    for (i, ins) in program.instructions.iter().enumerate() {
+        if let Some((range, replacement)) =
+            optimizations.iter().find(|(range, _)| *range.start() == i)
+        {
+            code += &format!("\t\tcase {}: {{\n", i);
+            code += &format!("\t\t\t{} = {};\n", ip_reg, range.end());
+            code += replacement;
+            code += "\t\t\tbreak;\n\t\t}\n";
+        } else {
+            code += &format!("\t\tcase {}: {}; break;\n", i, instruction_c_version(ins));
+        }
+    }
+
+    code += "\t\t}\n";
+    code += &format!("\t\tip = {} + 1;\n", ip_reg);
+    code += "\t}\n\n";
+
+    for r in registers_to_print {
+        code += &format!("\tprintf(\"%lli\\n\", r{});\n", r);
+    }
+    code += "\treturn 0;\n";
+    code += "}\n";
+
+    code
+}
+
+pub fn get_c_code(
+    program: &Program,
+    initial_registers: &[i64],
+    registers_to_print: &[usize],
+) -> String {
+    get_c_code_full(program, initial_registers, registers_to_print, &[])
+}
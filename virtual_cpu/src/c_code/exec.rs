@@ -1,15 +1,353 @@
-use std::{fs, process::Command};
-
-pub fn exec_c_code(code: &str) -> String {
-    // Write the C file.
-    fs::write("main.c", code).expect("Unable to write file");
-    // Compile it.
-    let _ = Command::new("gcc").arg("-O3").arg("main.c").output();
-    // Run it.
-    let output = Command::new("./a.out").output().unwrap();
-    // Clean the files.
-    let _ = Command::new("rm").arg("a.out").output();
-    let _ = Command::new("rm").arg("main.c").output();
-
-    String::from_utf8(output.stdout).unwrap().trim().to_string()
+//! Compiles and runs the generated C code.
+//!
+//! Each build happens in its own temporary directory, so several programs can be built
+//! and run in parallel. Compiled binaries are cached in a directory only accessible to the
+//! current user, keyed by a SHA-256 of the compiler version, its options and the source code.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+#[derive(Debug)]
+pub enum CBuildError {
+    Io(io::Error),
+    /// Compilation failed, with the compiler error output.
+    Compile(String),
+    /// Binary ran for longer than the timeout, and was killed.
+    Timeout(Duration),
+    /// Binary didn't exit successfully, with its error output.
+    Run(ExitStatus, String),
+}
+
+impl fmt::Display for CBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CBuildError::Io(err) => write!(f, "I/O error: {}", err),
+            CBuildError::Compile(stderr) => write!(f, "Compilation failed:\n{}", stderr),
+            CBuildError::Timeout(timeout) => write!(f, "Timeout after {:?}", timeout),
+            CBuildError::Run(status, stderr) => {
+                write!(f, "Execution failed ({}):\n{}", status, stderr)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CBuildError {}
+
+impl From<io::Error> for CBuildError {
+    fn from(err: io::Error) -> Self {
+        CBuildError::Io(err)
+    }
+}
+
+/// A temporary directory, deleted when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = env::temp_dir().join(format!(
+            "virtual_cpu_{}_{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Configuration for building and running C code.
+///
+/// By default, the compiler is the one in the `CC` environment variable, or `gcc`.
+#[derive(Debug, Clone)]
+pub struct CBuild {
+    compiler: String,
+    flags: Vec<String>,
+    timeout: Option<Duration>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl Default for CBuild {
+    fn default() -> Self {
+        Self {
+            compiler: env::var("CC").unwrap_or_else(|_| "gcc".to_string()),
+            flags: vec!["-O3".to_string()],
+            timeout: None,
+            cache_dir: default_cache_dir(),
+        }
+    }
+}
+
+// The user's cache directory, `$XDG_CACHE_HOME/virtual_cpu` or `~/.cache/virtual_cpu`.
+// Only on Unix, where we can make sure other users can't put binaries in it.
+fn default_cache_dir() -> Option<PathBuf> {
+    if !cfg!(unix) {
+        return None;
+    }
+    env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .map(|dir| dir.join("virtual_cpu"))
+}
+
+// Creates the cache directory if needed, accessible only by the current user.
+// Returns false if it exists with other permissions, as others could have written into it.
+#[cfg(unix)]
+fn prepare_cache_dir(dir: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if !dir.exists() {
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match fs::DirBuilder::new().mode(0o700).create(dir) {
+            Ok(()) => {}
+            // Created by a parallel build.
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err),
+        }
+    }
+    let metadata = fs::symlink_metadata(dir)?;
+    Ok(metadata.is_dir() && metadata.permissions().mode() & 0o777 == 0o700)
+}
+
+#[cfg(not(unix))]
+fn prepare_cache_dir(_dir: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+impl CBuild {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn compiler(mut self, compiler: &str) -> Self {
+        self.compiler = compiler.to_string();
+        self
+    }
+
+    pub fn clang(self) -> Self {
+        self.compiler("clang")
+    }
+
+    /// Replaces the compiler flags, "-O3" by default.
+    pub fn flags(mut self, flags: &[&str]) -> Self {
+        self.flags = flags.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Maximum time the compiled program can run.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Directory where compiled binaries are kept, or None to disable the cache.
+    pub fn cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+        self.cache_dir = cache_dir;
+        self
+    }
+
+    // Key identifying a binary, built from everything that affects the compilation.
+    // The compiler is identified by its version, as the same name can give another compiler.
+    fn cache_key(&self, code: &str) -> Result<String, CBuildError> {
+        let version = Command::new(&self.compiler)
+            .arg("--version")
+            .output()
+            .map_err(|err| {
+                CBuildError::Compile(format!("Unable to run '{}': {}", self.compiler, err))
+            })?;
+        let mut hasher = Sha256::new();
+        for part in [
+            self.compiler.as_bytes(),
+            &version.stdout,
+            self.flags.join(" ").as_bytes(),
+            code.as_bytes(),
+        ] {
+            // Lengths first, so that the parts can't be confused with each other.
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect())
+    }
+
+    fn compile_into(&self, code: &str, dir: &Path) -> Result<PathBuf, CBuildError> {
+        let source = dir.join("main.c");
+        let binary = dir.join("a.out");
+        fs::write(&source, code)?;
+        let output = Command::new(&self.compiler)
+            .args(&self.flags)
+            .arg(&source)
+            .arg("-o")
+            .arg(&binary)
+            .output()
+            .map_err(|err| {
+                CBuildError::Compile(format!("Unable to run '{}': {}", self.compiler, err))
+            })?;
+        if !output.status.success() {
+            return Err(CBuildError::Compile(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+        Ok(binary)
+    }
+
+    fn execute(&self, binary: &Path) -> Result<String, CBuildError> {
+        let mut child = Command::new(binary)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Read the output in separate threads, so that the program doesn't block on a full pipe.
+        let mut stdout = child.stdout.take().unwrap();
+        let mut stderr = child.stderr.take().unwrap();
+        let stdout_reader = thread::spawn(move || {
+            let mut s = String::new();
+            stdout.read_to_string(&mut s).map(|_| s)
+        });
+        let stderr_reader = thread::spawn(move || {
+            let mut s = String::new();
+            stderr.read_to_string(&mut s).map(|_| s)
+        });
+
+        let start = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if let Some(timeout) = self.timeout {
+                if start.elapsed() > timeout {
+                    child.kill()?;
+                    child.wait()?;
+                    return Err(CBuildError::Timeout(timeout));
+                }
+            }
+            thread::sleep(Duration::from_millis(5));
+        };
+
+        let stdout = stdout_reader.join().unwrap()?;
+        let stderr = stderr_reader.join().unwrap()?;
+        if !status.success() {
+            return Err(CBuildError::Run(status, stderr));
+        }
+        Ok(stdout.trim().to_string())
+    }
+
+    /// Compiles the code and runs it, returning what it printed.
+    pub fn run(&self, code: &str) -> Result<String, CBuildError> {
+        let cache_dir = match &self.cache_dir {
+            Some(dir) if prepare_cache_dir(dir)? => Some(dir),
+            _ => None,
+        };
+        let cached_binary = match cache_dir {
+            Some(dir) => Some(dir.join(self.cache_key(code)?)),
+            None => None,
+        };
+        if let Some(binary) = &cached_binary {
+            if binary.exists() {
+                return self.execute(binary);
+            }
+        }
+
+        let temp_dir = TempDir::new()?;
+        let binary = self.compile_into(code, &temp_dir.0)?;
+        if let Some(cached_binary) = &cached_binary {
+            // Renaming is atomic, so parallel builds of the same code are fine.
+            // Fails if the cache is on another file system, in which case we just don't cache.
+            if fs::rename(&binary, cached_binary).is_ok() {
+                return self.execute(cached_binary);
+            }
+        }
+        self.execute(&binary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO: &str = "#include <stdio.h>\nint main() { printf(\"hello\\n\"); return 0; }\n";
+
+    #[test]
+    fn test_compile_error() {
+        let c_build = CBuild::new().cache_dir(None);
+        let err = c_build.run("int main() { return x; }").unwrap_err();
+        assert!(matches!(err, CBuildError::Compile(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let c_build = CBuild::new()
+            .cache_dir(None)
+            .timeout(Duration::from_millis(100));
+        let err = c_build
+            .run("int main() { volatile int i = 1; while (i) {} return 0; }")
+            .unwrap_err();
+        assert!(matches!(err, CBuildError::Timeout(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache_dir = dir.0.join("cache");
+        let c_build = CBuild::new().cache_dir(Some(cache_dir.clone()));
+        assert_eq!(c_build.run(HELLO).unwrap(), "hello");
+        let entries = || {
+            fs::read_dir(&cache_dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect::<Vec<_>>()
+        };
+        let cached = entries();
+        assert_eq!(cached.len(), 1);
+        let modified = fs::metadata(&cached[0]).unwrap().modified().unwrap();
+
+        // Cache hit: the binary isn't built again.
+        assert_eq!(c_build.run(HELLO).unwrap(), "hello");
+        assert_eq!(entries(), cached);
+        assert_eq!(
+            fs::metadata(&cached[0]).unwrap().modified().unwrap(),
+            modified
+        );
+
+        // Other flags give another binary.
+        let c_build = c_build.flags(&["-O0"]);
+        assert_eq!(c_build.run(HELLO).unwrap(), "hello");
+        assert_eq!(entries().len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cache_dir_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let cache_dir = dir.0.join("cache");
+        assert!(prepare_cache_dir(&cache_dir).unwrap());
+        // Accessible to others: not used.
+        fs::set_permissions(&cache_dir, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(!prepare_cache_dir(&cache_dir).unwrap());
+        let c_build = CBuild::new().cache_dir(Some(cache_dir.clone()));
+        assert_eq!(c_build.run(HELLO).unwrap(), "hello");
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 0);
+    }
 }
@@ -41,6 +41,7 @@ fn instruction_c_version(ins: &Instruction, ir: usize, labels: &[String]) -> Str
         Instruction::Mul(x, y) => format!("{} *= {}", x, y),
         Instruction::Mod(x, y) => format!("{} %= {}", x, y),
         Instruction::Div(x, y) => format!("{} /= {}", x, y),
+        Instruction::Eql(x, y) => format!("{} = {} == {}", x, x, y),
         Instruction::JumpIf(cond, x, y, _) => {
             let index = (ir as i64 + y.get_integer()) as usize;
             match cond {
@@ -49,6 +50,7 @@ fn instruction_c_version(ins: &Instruction, ir: usize, labels: &[String]) -> Str
                 Condition::True => format!("if (1) goto {}", &labels[index]),
                 Condition::Even => format!("if ({} % 2 == 0) goto {}", x, &labels[index]),
                 Condition::EqualOne => format!("if ({} == 1) goto {}", x, &labels[index]),
+                Condition::False => String::new(),
             }
         }
         Instruction::Out(x) => format!("printf(\"%lli\\n\", {})", x),
@@ -63,7 +65,7 @@ fn instruction_c_version(ins: &Instruction, ir: usize, labels: &[String]) -> Str
 /// * `initial_registers` - The registers (aka variables) that must have their values set at something else than zero at the beginning.
 /// * `registers_to_print` - The register's values to print at the end.
 /// * `optimizations` - A block of instructions, indicated by its indexes, to be replaced
-/// with the specified code. If there are multiple ones, their indexes cannot overlap.
+///   with the specified code. If there are multiple ones, their indexes cannot overlap.
 #[allow(clippy::single_match)]
 pub fn get_c_code_full(
     instructions: &[Instruction],
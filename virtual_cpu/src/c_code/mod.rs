@@ -1,2 +1,3 @@
+pub mod elfcode;
 pub mod exec;
 pub mod gen;
@@ -0,0 +1,300 @@
+//! Support for the 2024 day 17 "Chronospatial Computer" 3-bit instructions.
+//!
+//! The computer has three registers A, B and C, and programs are lists of 3-bit numbers,
+//! each instruction being an opcode followed by an operand.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+}
+
+impl Registers {
+    pub fn new(a: u64, b: u64, c: u64) -> Self {
+        Self { a, b, c }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComboOp {
+    LiteralValue(u64), // literal values 0 through 3
+    RegisterA,
+    RegisterB,
+    RegisterC,
+    Reserved,
+}
+
+impl ComboOp {
+    pub fn new(combo: u8) -> Self {
+        match combo {
+            0..=3 => ComboOp::LiteralValue(u64::from(combo)),
+            4 => ComboOp::RegisterA,
+            5 => ComboOp::RegisterB,
+            6 => ComboOp::RegisterC,
+            7 => ComboOp::Reserved,
+            _ => panic!("Invalid combo operand"),
+        }
+    }
+
+    pub fn value(self, regs: &Registers) -> u64 {
+        match self {
+            ComboOp::LiteralValue(val) => val,
+            ComboOp::RegisterA => regs.a,
+            ComboOp::RegisterB => regs.b,
+            ComboOp::RegisterC => regs.c,
+            ComboOp::Reserved => panic!("Reserved operand"),
+        }
+    }
+
+    // Name of the operand in the disassembly.
+    fn name(self) -> String {
+        match self {
+            ComboOp::LiteralValue(v) => v.to_string(),
+            ComboOp::RegisterA => "A".to_string(),
+            ComboOp::RegisterB => "B".to_string(),
+            ComboOp::RegisterC => "C".to_string(),
+            ComboOp::Reserved => "?".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ComboOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                ComboOp::LiteralValue(v) => v,
+                ComboOp::RegisterA => 4,
+                ComboOp::RegisterB => 5,
+                ComboOp::RegisterC => 6,
+                ComboOp::Reserved => 7,
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Adv(ComboOp),
+    Bxl(u64),
+    Bst(ComboOp),
+    Jnz(u64),
+    Bxc(u64),
+    Out(ComboOp),
+    Bdv(ComboOp),
+    Cdv(ComboOp),
+}
+
+// The division instructions divide A by 2 to the power of the operand, which is a right shift.
+fn shift(val: u64, combo: ComboOp, regs: &Registers) -> u64 {
+    u32::try_from(combo.value(regs))
+        .ok()
+        .and_then(|s| val.checked_shr(s))
+        .unwrap_or_default()
+}
+
+impl Instruction {
+    pub fn new(opcode: u8, operand: u8) -> Self {
+        match opcode {
+            0 => Instruction::Adv(ComboOp::new(operand)),
+            1 => Instruction::Bxl(u64::from(operand)),
+            2 => Instruction::Bst(ComboOp::new(operand)),
+            3 => Instruction::Jnz(u64::from(operand)),
+            4 => Instruction::Bxc(u64::from(operand)), // operand is ignored
+            5 => Instruction::Out(ComboOp::new(operand)),
+            6 => Instruction::Bdv(ComboOp::new(operand)),
+            7 => Instruction::Cdv(ComboOp::new(operand)),
+            _ => panic!("Invalid opcode"),
+        }
+    }
+
+    /// Executes the instruction, returning the output value if there is one.
+    pub fn execute(&self, ip: &mut usize, regs: &mut Registers) -> Option<u64> {
+        let mut output = None;
+        match self {
+            Instruction::Adv(combo) => regs.a = shift(regs.a, *combo, regs),
+            Instruction::Bxl(literal) => regs.b ^= literal,
+            Instruction::Bst(combo) => regs.b = combo.value(regs) % 8,
+            Instruction::Jnz(literal) => {
+                if regs.a != 0 {
+                    // We need to divided by two, because in our program vector each instruction + params
+                    // takes one place, while in the source it takes two.
+                    *ip = usize::try_from(*literal).unwrap() / 2;
+                    return None;
+                }
+            }
+            Instruction::Bxc(_) => regs.b ^= regs.c,
+            Instruction::Out(combo) => output = Some(combo.value(regs) % 8),
+            Instruction::Bdv(combo) => regs.b = shift(regs.a, *combo, regs),
+            Instruction::Cdv(combo) => regs.c = shift(regs.a, *combo, regs),
+        }
+        *ip += 1;
+        output
+    }
+
+    /// Mnemonic version of the instruction, with its effect.
+    pub fn disassemble(&self) -> String {
+        match *self {
+            Instruction::Adv(c) => format!("adv {:<3}A = A >> {}", c.name(), c.name()),
+            Instruction::Bxl(v) => format!("bxl {:<3}B = B ^ {}", v, v),
+            Instruction::Bst(c) => format!("bst {:<3}B = {} % 8", c.name(), c.name()),
+            Instruction::Jnz(v) => format!("jnz {:<3}if A != 0 jump {}", v, v),
+            Instruction::Bxc(_) => format!("bxc {:<3}B = B ^ C", ""),
+            Instruction::Out(c) => format!("out {:<3}output {} % 8", c.name(), c.name()),
+            Instruction::Bdv(c) => format!("bdv {:<3}B = A >> {}", c.name(), c.name()),
+            Instruction::Cdv(c) => format!("cdv {:<3}C = A >> {}", c.name(), c.name()),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Adv(combo_op) => write!(f, "0,{combo_op}"),
+            Instruction::Bxl(val) => write!(f, "1,{val}"),
+            Instruction::Bst(combo_op) => write!(f, "2,{combo_op}"),
+            Instruction::Jnz(val) => write!(f, "3,{val}"),
+            Instruction::Bxc(val) => write!(f, "4,{val}"),
+            Instruction::Out(combo_op) => write!(f, "5,{combo_op}"),
+            Instruction::Bdv(combo_op) => write!(f, "6,{combo_op}"),
+            Instruction::Cdv(combo_op) => write!(f, "7,{combo_op}"),
+        }
+    }
+}
+
+pub fn build_program(input: &str) -> Vec<Instruction> {
+    let values: Vec<u8> = input
+        .trim()
+        .split(',')
+        .map(|v| v.parse().unwrap())
+        .collect();
+    values
+        .chunks(2)
+        .map(|p| Instruction::new(p[0], p[1]))
+        .collect()
+}
+
+/// Builds the initial registers and the program from the puzzle input.
+pub fn build(input: &str) -> (Registers, Vec<Instruction>) {
+    let mut it = input.lines();
+    let mut reg = |prefix: &str| -> u64 {
+        it.next()
+            .unwrap()
+            .trim_start_matches(prefix)
+            .parse()
+            .unwrap()
+    };
+    let registers = Registers::new(
+        reg("Register A: "),
+        reg("Register B: "),
+        reg("Register C: "),
+    );
+    it.next();
+    let program = build_program(it.next().unwrap().trim_start_matches("Program: "));
+    (registers, program)
+}
+
+/// The program in its source format, a comma separated list of numbers.
+pub fn program_to_string(program: &[Instruction]) -> String {
+    program
+        .iter()
+        .map(std::string::ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The program as a list of mnemonics, one instruction per line.
+/// Jump targets and addresses are in the source format, where each instruction takes two places.
+pub fn disassemble(program: &[Instruction]) -> String {
+    program
+        .iter()
+        .enumerate()
+        .map(|(ip, ins)| format!("{:>3}: {}  {}\n", ip * 2, ins, ins.disassemble()))
+        .collect()
+}
+
+pub fn run_program(regs: &mut Registers, program: &[Instruction]) -> Vec<u64> {
+    let mut ip: usize = 0;
+    let mut output = Vec::new();
+    while let Some(ins) = program.get(ip) {
+        output.extend(ins.execute(&mut ip, regs));
+    }
+    output
+}
+
+// Runs the program until it outputs its first value.
+fn first_output(mut regs: Registers, program: &[Instruction]) -> Option<u64> {
+    let mut ip: usize = 0;
+    while let Some(ins) = program.get(ip) {
+        if let Some(out) = ins.execute(&mut ip, &mut regs) {
+            return Some(out);
+        }
+    }
+    None
+}
+
+// Checks that the program is a single loop, that outputs one value and shifts A by 3 bits per iteration.
+fn is_shift_loop(program: &[Instruction]) -> bool {
+    let count = |f: fn(&Instruction) -> bool| program.iter().filter(|ins| f(ins)).count();
+    program.last() == Some(&Instruction::Jnz(0))
+        && count(|ins| matches!(ins, Instruction::Jnz(_))) == 1
+        && count(|ins| matches!(ins, Instruction::Out(_))) == 1
+        && count(|ins| matches!(ins, Instruction::Adv(_))) == 1
+        && program.contains(&Instruction::Adv(ComboOp::LiteralValue(3)))
+}
+
+/// Finds the smallest value of register A for which the program outputs `target`.
+///
+/// This works for programs that are a single loop where each iteration outputs one value
+/// and shifts A by 3 bits to the right, until A is zero. The last output then only depends
+/// on the highest bits of A, so A can be rebuilt 3 bits at a time, starting from the last output.
+/// Returns None if the program doesn't have the expected structure, or if no solution exists.
+pub fn find_reg_a_for_output(
+    registers: &Registers,
+    program: &[Instruction],
+    target: &[u64],
+) -> Option<u64> {
+    fn search(
+        registers: &Registers,
+        program: &[Instruction],
+        target: &[u64],
+        remaining: &[u64],
+        high_bits: u64,
+    ) -> Option<u64> {
+        let Some((expected, remaining)) = remaining.split_last() else {
+            // Verifies with a full run, as we assumed B and C don't carry over between iterations.
+            let mut regs = Registers::new(high_bits, registers.b, registers.c);
+            return (run_program(&mut regs, program) == target).then_some(high_bits);
+        };
+        (0..8)
+            .map(|bits| (high_bits << 3) | bits)
+            // A must not be zero, otherwise the loop would have ended before.
+            .filter(|a| *a != 0)
+            .filter(|a| {
+                let regs = Registers::new(*a, registers.b, registers.c);
+                first_output(regs, program) == Some(*expected)
+            })
+            .find_map(|a| search(registers, program, target, remaining, a))
+    }
+
+    if !is_shift_loop(program) {
+        return None;
+    }
+    search(registers, program, target, target, 0)
+}
+
+/// Finds the smallest value of register A for which the program outputs itself.
+pub fn find_reg_a_for_self_replicate(
+    registers: &Registers,
+    program: &[Instruction],
+) -> Option<u64> {
+    let target: Vec<u64> = program_to_string(program)
+        .split(',')
+        .map(|v| v.parse().unwrap())
+        .collect();
+    find_reg_a_for_output(registers, program, &target)
+}
@@ -0,0 +1,322 @@
+//! Fast interpreter: The instructions are first compiled into a form that is quicker to execute.
+//!
+//! Register names are mapped to indexes in an array, operands are resolved to registers or
+//! immediate values, and jumps with a constant offset get their absolute target computed.
+
+use fxhash::FxHashMap;
+
+use crate::instruction::{Condition, Instruction};
+use crate::intchar::IntChar;
+use crate::registers::Registers;
+
+/// Maximum number of registers a compiled program can use.
+pub const MAX_REGISTERS: usize = 26;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Reg(usize),
+    Imm(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JumpTarget {
+    Absolute(usize),
+    Relative(Operand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Set(usize, Operand),
+    Add(usize, Operand),
+    Sub(usize, Operand),
+    Mul(usize, Operand),
+    Mod(usize, Operand),
+    Div(usize, Operand),
+    Eql(usize, Operand),
+    JumpIf(Condition, Operand, JumpTarget),
+    Nop,
+}
+
+#[inline]
+fn value<const N: usize>(regs: &[i64; N], op: Operand) -> i64 {
+    match op {
+        Operand::Reg(r) => regs[r],
+        Operand::Imm(v) => v,
+    }
+}
+
+#[inline]
+fn test(cond: Condition, v: i64) -> bool {
+    match cond {
+        Condition::NotZero => v != 0,
+        Condition::GreaterThanZero => v > 0,
+        Condition::True => true,
+        Condition::Even => v % 2 == 0,
+        Condition::EqualOne => v == 1,
+        Condition::False => false,
+    }
+}
+
+// Assigns an index to each register, in order of appearance.
+#[derive(Default)]
+struct RegisterMap {
+    indexes: FxHashMap<char, usize>,
+    names: Vec<char>,
+}
+
+impl RegisterMap {
+    fn index(&mut self, r: char) -> usize {
+        *self.indexes.entry(r).or_insert_with(|| {
+            self.names.push(r);
+            self.names.len() - 1
+        })
+    }
+
+    fn operand(&mut self, x: IntChar<i64>) -> Operand {
+        match x {
+            IntChar::Integer(v) => Operand::Imm(v),
+            IntChar::Char(r) => Operand::Reg(self.index(r)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompiledProgram {
+    ops: Vec<Op>,
+    // Register names, the index in this vector being the index in the registers array.
+    names: Vec<char>,
+}
+
+impl CompiledProgram {
+    /// Compiles the instructions.
+    /// Returns None if the program uses instructions that need a `Machine`, like I/O or toggle,
+    /// or if it uses more than `MAX_REGISTERS` registers.
+    pub fn compile(instructions: &[Instruction]) -> Option<Self> {
+        let mut map = RegisterMap::default();
+        let mut ops = Vec::with_capacity(instructions.len());
+        for (ir, ins) in instructions.iter().enumerate() {
+            ops.push(match ins {
+                Instruction::Set(x, y) => Op::Set(map.index(*x), map.operand(*y)),
+                Instruction::Add(x, y) => Op::Add(map.index(*x), map.operand(*y)),
+                Instruction::Sub(x, y) => Op::Sub(map.index(*x), map.operand(*y)),
+                Instruction::Mul(x, y) => Op::Mul(map.index(*x), map.operand(*y)),
+                Instruction::Mod(x, y) => Op::Mod(map.index(*x), map.operand(*y)),
+                Instruction::Div(x, y) => Op::Div(map.index(*x), map.operand(*y)),
+                Instruction::Eql(x, y) => Op::Eql(map.index(*x), map.operand(*y)),
+                Instruction::JumpIf(cond, x, y, _) => {
+                    let target = match y {
+                        // A negative target means the program ends.
+                        IntChar::Integer(offset) => JumpTarget::Absolute(
+                            usize::try_from(ir as i64 + offset).unwrap_or(usize::MAX),
+                        ),
+                        IntChar::Char(_) => JumpTarget::Relative(map.operand(*y)),
+                    };
+                    Op::JumpIf(*cond, map.operand(*x), target)
+                }
+                Instruction::Nop => Op::Nop,
+                Instruction::Snd(_)
+                | Instruction::Rcv(_)
+                | Instruction::Out(_)
+                | Instruction::Toggle(_)
+                | Instruction::Inp(_) => return None,
+            });
+        }
+        if map.names.len() > MAX_REGISTERS {
+            return None;
+        }
+        Some(Self {
+            ops,
+            names: map.names,
+        })
+    }
+
+    /// Index of the register in the registers array.
+    pub fn register_index(&self, name: char) -> Option<usize> {
+        self.names.iter().position(|n| *n == name)
+    }
+
+    /// Executes the program on the registers array.
+    pub fn execute<const N: usize>(&self, regs: &mut [i64; N]) {
+        assert!(self.names.len() <= N, "Registers array too small");
+        let mut ir = 0;
+        while let Some(op) = self.ops.get(ir) {
+            match *op {
+                Op::Set(x, y) => regs[x] = value(regs, y),
+                Op::Add(x, y) => regs[x] += value(regs, y),
+                Op::Sub(x, y) => regs[x] -= value(regs, y),
+                Op::Mul(x, y) => regs[x] *= value(regs, y),
+                Op::Mod(x, y) => regs[x] %= value(regs, y),
+                Op::Div(x, y) => regs[x] /= value(regs, y),
+                Op::Eql(x, y) => regs[x] = i64::from(regs[x] == value(regs, y)),
+                Op::JumpIf(cond, x, target) => {
+                    if test(cond, value(regs, x)) {
+                        ir = match target {
+                            JumpTarget::Absolute(t) => t,
+                            JumpTarget::Relative(offset) => {
+                                usize::try_from(ir as i64 + value(regs, offset))
+                                    .unwrap_or(usize::MAX)
+                            }
+                        };
+                        continue;
+                    }
+                }
+                Op::Nop => {}
+            }
+            ir += 1;
+        }
+    }
+
+    /// Executes the program with the registers stored by name, like `instruction::execute_all`.
+    pub fn execute_registers(&self, regs: &mut Registers<i64>) {
+        let mut array = [0; MAX_REGISTERS];
+        for (i, name) in self.names.iter().enumerate() {
+            array[i] = regs.get(*name);
+        }
+        self.execute(&mut array);
+        for (i, name) in self.names.iter().enumerate() {
+            regs.set(*name, array[i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{build_list, execute_all};
+
+    // Runs the program with both interpreters and checks all the registers match.
+    fn check_same_as_execute_all(instructions: &[Instruction], initial: &[(char, i64)]) {
+        let mut regs = Registers::new();
+        for (r, v) in initial {
+            regs.set(*r, *v);
+        }
+        let mut compiled_regs = regs.clone();
+        execute_all(instructions, &mut regs);
+        CompiledProgram::compile(instructions)
+            .unwrap()
+            .execute_registers(&mut compiled_regs);
+        for ins in instructions {
+            for r in ins.get_register_names() {
+                assert_eq!(regs.get(r), compiled_regs.get(r), "Register {}", r);
+            }
+        }
+    }
+
+    #[test]
+    fn test_day2015_23() {
+        let instructions = build_list(
+            include_str!("bin/test_input/day2015_23_input_test_1"),
+            |s| {
+                let t = s.replace(',', "");
+                let parts: Vec<_> = t.split(' ').collect();
+                match parts[0] {
+                    "tpl" => Instruction::Mul(parts[1].chars().next().unwrap(), IntChar::from(3)),
+                    "hlf" => Instruction::Div(parts[1].chars().next().unwrap(), IntChar::from(2)),
+                    _ => Instruction::build(&t),
+                }
+            },
+        );
+        check_same_as_execute_all(&instructions, &[]);
+        check_same_as_execute_all(&instructions, &[('a', 1)]);
+    }
+
+    #[test]
+    fn test_day2016_12() {
+        for input in [
+            include_str!("bin/test_input/day2016_12_input_test_1"),
+            include_str!("bin/test_input/day2016_12_input_test_2"),
+        ] {
+            let instructions = Instruction::build_list(input);
+            check_same_as_execute_all(&instructions, &[]);
+            check_same_as_execute_all(&instructions, &[('c', 1)]);
+        }
+    }
+
+    #[test]
+    fn test_day2020_08() {
+        // The example loops forever, unless the last jump is changed into a nop.
+        let input = include_str!("bin/test_input/day2020_08_input_test_1")
+            .replace("acc", "add a")
+            .replace("jmp -4", "nop -4");
+        let instructions = Instruction::build_list(&input);
+        check_same_as_execute_all(&instructions, &[]);
+    }
+
+    #[test]
+    fn test_day2022_10() {
+        let instructions = build_list(
+            include_str!("bin/test_input/day2022_10_input_test_1"),
+            |s| match s.split_once(' ') {
+                Some(("addx", v)) => Instruction::Add('x', IntChar::new(v)),
+                _ => Instruction::Nop,
+            },
+        );
+        check_same_as_execute_all(&instructions, &[('x', 1)]);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut instructions = Instruction::build_list(
+            "set a 17
+mod a 5
+set b 100
+div b a
+mul b -3
+sub b a",
+        );
+        instructions.push(Instruction::Eql('a', IntChar::from(2)));
+        instructions.push(Instruction::Eql('b', IntChar::Char('a')));
+        check_same_as_execute_all(&instructions, &[]);
+    }
+
+    #[test]
+    fn test_register_jumps() {
+        // Jumps forward by a register offset, and out of the program with a negative one.
+        let instructions = Instruction::build_list(
+            "set b 3
+set c 0
+jnz 1 b
+add c 100
+jmp 3
+add c 1
+jnz 1 -3
+set d 3
+jie c d
+set d -20
+jgz c d
+add c 1000",
+        );
+        check_same_as_execute_all(&instructions, &[]);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let tgl = build_list(
+            include_str!("bin/test_input/day2016_23_input_test_1"),
+            |s| match s.split_once(' ') {
+                Some(("tgl", r)) => Instruction::Toggle(r.chars().next().unwrap()),
+                _ => Instruction::build(s),
+            },
+        );
+        assert!(CompiledProgram::compile(&tgl).is_none());
+        for ins in [
+            Instruction::Snd(IntChar::Char('a')),
+            Instruction::Rcv('a'),
+            Instruction::Out(IntChar::from(0)),
+            Instruction::Inp('w'),
+        ] {
+            assert!(CompiledProgram::compile(&[ins]).is_none());
+        }
+    }
+
+    #[test]
+    fn test_too_many_registers() {
+        let mut instructions: Vec<_> = ('a'..='z')
+            .map(|r| Instruction::Add(r, IntChar::from(1)))
+            .collect();
+        let program = CompiledProgram::compile(&instructions).unwrap();
+        assert_eq!(program.register_index('z'), Some(25));
+        instructions.push(Instruction::Add('A', IntChar::from(1)));
+        assert!(CompiledProgram::compile(&instructions).is_none());
+    }
+}
@@ -0,0 +1,102 @@
+//! Registers modified under a condition, as in 2017 day 8, like
+//!
+//! ```text
+//! b inc 5 if a > 1
+//! ```
+//!
+//! Registers have names with several letters, so they don't fit in the usual `Registers`.
+
+use fxhash::FxHashMap;
+
+use crate::parsing::int;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn build(s: &str) -> Self {
+        match s {
+            "<" => Self::Less,
+            "<=" => Self::LessOrEqual,
+            ">" => Self::Greater,
+            ">=" => Self::GreaterOrEqual,
+            "==" => Self::Equal,
+            "!=" => Self::NotEqual,
+            _ => panic!("Invalid comparison {}", s),
+        }
+    }
+
+    pub fn test(self, a: i64, b: i64) -> bool {
+        match self {
+            Self::Less => a < b,
+            Self::LessOrEqual => a <= b,
+            Self::Greater => a > b,
+            Self::GreaterOrEqual => a >= b,
+            Self::Equal => a == b,
+            Self::NotEqual => a != b,
+        }
+    }
+}
+
+pub type Registers = FxHashMap<String, i64>;
+
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub reg: String,
+    // Decrements are stored as negative increments.
+    pub amount: i64,
+    pub cond_reg: String,
+    pub cmp: Comparison,
+    pub cond_val: i64,
+}
+
+impl Instruction {
+    pub fn build(s: &str) -> Self {
+        let p: Vec<_> = s.split_whitespace().collect();
+        assert!(p.len() == 7 && p[3] == "if", "Invalid instruction {}", s);
+        let amount: i64 = int(p[2]);
+        Self {
+            reg: p[0].to_string(),
+            amount: match p[1] {
+                "inc" => amount,
+                "dec" => -amount,
+                _ => panic!("Invalid operation {}", p[1]),
+            },
+            cond_reg: p[4].to_string(),
+            cmp: Comparison::build(p[5]),
+            cond_val: int(p[6]),
+        }
+    }
+
+    /// Executes the instruction. Returns the new value of the register if it was modified.
+    pub fn execute(&self, regs: &mut Registers) -> Option<i64> {
+        let cond_reg_val = regs.get(&self.cond_reg).copied().unwrap_or_default();
+        if self.cmp.test(cond_reg_val, self.cond_val) {
+            let reg = regs.entry(self.reg.clone()).or_default();
+            *reg += self.amount;
+            Some(*reg)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn build_list(input: &str) -> Vec<Instruction> {
+    input.lines().map(Instruction::build).collect()
+}
+
+/// Executes all the instructions.
+/// Returns the highest value held by any register during the execution (registers start at zero).
+pub fn execute_all(instructions: &[Instruction], regs: &mut Registers) -> i64 {
+    instructions
+        .iter()
+        .filter_map(|ins| ins.execute(regs))
+        .fold(0, i64::max)
+}
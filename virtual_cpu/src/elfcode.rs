@@ -0,0 +1,336 @@
+//! Support for the 2018 "elfcode" instructions (days 16, 19 and 21).
+//!
+//! Registers are identified by a number, and each instruction has the form `opcode A B C`.
+//! The instruction pointer can be bound to a register with the `#ip N` directive.
+
+use std::fmt;
+
+use fxhash::FxHashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Addr,
+    Addi,
+    Mulr,
+    Muli,
+    Banr,
+    Bani,
+    Borr,
+    Bori,
+    Setr,
+    Seti,
+    Gtir,
+    Gtri,
+    Gtrr,
+    Eqir,
+    Eqri,
+    Eqrr,
+}
+use Opcode::*;
+
+/// All the opcodes, in the order of the puzzle description.
+pub const OPCODES: [Opcode; 16] = [
+    Addr, Addi, Mulr, Muli, Banr, Bani, Borr, Bori, Setr, Seti, Gtir, Gtri, Gtrr, Eqir, Eqri, Eqrr,
+];
+
+impl Opcode {
+    pub fn build(s: &str) -> Self {
+        *OPCODES
+            .iter()
+            .find(|op| op.name() == s)
+            .unwrap_or_else(|| panic!("Invalid opcode {}", s))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Addr => "addr",
+            Addi => "addi",
+            Mulr => "mulr",
+            Muli => "muli",
+            Banr => "banr",
+            Bani => "bani",
+            Borr => "borr",
+            Bori => "bori",
+            Setr => "setr",
+            Seti => "seti",
+            Gtir => "gtir",
+            Gtri => "gtri",
+            Gtrr => "gtrr",
+            Eqir => "eqir",
+            Eqri => "eqri",
+            Eqrr => "eqrr",
+        }
+    }
+
+    /// Indicates if the A and B parameters are registers (true) or immediate values (false).
+    /// For the `set` opcodes, B is ignored.
+    pub fn params_are_registers(self) -> (bool, bool) {
+        match self {
+            Addr | Mulr | Banr | Borr | Gtrr | Eqrr => (true, true),
+            Addi | Muli | Bani | Bori | Gtri | Eqri | Setr => (true, false),
+            Gtir | Eqir => (false, true),
+            Seti => (false, false),
+        }
+    }
+
+    pub fn exec(self, regs: &mut [i64], a: i64, b: i64, c: usize) {
+        let r = |x: i64| regs[usize::try_from(x).unwrap()];
+        regs[c] = match self {
+            Addr => r(a) + r(b),
+            Addi => r(a) + b,
+            Mulr => r(a) * r(b),
+            Muli => r(a) * b,
+            Banr => r(a) & r(b),
+            Bani => r(a) & b,
+            Borr => r(a) | r(b),
+            Bori => r(a) | b,
+            Setr => r(a),
+            Seti => a,
+            Gtir => i64::from(a > r(b)),
+            Gtri => i64::from(r(a) > b),
+            Gtrr => i64::from(r(a) > r(b)),
+            Eqir => i64::from(a == r(b)),
+            Eqri => i64::from(r(a) == b),
+            Eqrr => i64::from(r(a) == r(b)),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub a: i64,
+    pub b: i64,
+    pub c: usize,
+}
+
+impl Instruction {
+    pub fn build(s: &str) -> Self {
+        let p: Vec<_> = s.split(' ').collect();
+        Self {
+            opcode: Opcode::build(p[0]),
+            a: p[1].parse().unwrap(),
+            b: p[2].parse().unwrap(),
+            c: p[3].parse().unwrap(),
+        }
+    }
+
+    pub fn exec(&self, regs: &mut [i64]) {
+        self.opcode.exec(regs, self.a, self.b, self.c);
+    }
+
+    /// Returns the registers read by this instruction.
+    pub fn get_read_registers(&self) -> Vec<usize> {
+        let (a_reg, b_reg) = self.opcode.params_are_registers();
+        let mut regs = Vec::new();
+        if a_reg {
+            regs.push(usize::try_from(self.a).unwrap());
+        }
+        if b_reg {
+            regs.push(usize::try_from(self.b).unwrap());
+        }
+        regs
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.opcode.name(), self.a, self.b, self.c)
+    }
+}
+
+/// An elfcode program, with its optional instruction pointer binding.
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub ip_binding: Option<usize>,
+    pub instructions: Vec<Instruction>,
+}
+
+impl Program {
+    /// Builds a program from its text version. The first line may be a `#ip N` directive.
+    pub fn build(input: &str) -> Self {
+        let mut ip_binding = None;
+        let mut instructions = Vec::new();
+        for line in input.lines().filter(|l| !l.is_empty()) {
+            if let Some(ip) = line.strip_prefix("#ip ") {
+                ip_binding = Some(ip.parse().unwrap());
+            } else {
+                instructions.push(Instruction::build(line));
+            }
+        }
+        Self {
+            ip_binding,
+            instructions,
+        }
+    }
+
+    /// Number of registers needed by the program.
+    pub fn registers_count(&self) -> usize {
+        self.instructions
+            .iter()
+            .flat_map(|ins| {
+                let mut regs = ins.get_read_registers();
+                regs.push(ins.c);
+                regs
+            })
+            .chain(self.ip_binding)
+            .max()
+            .map_or(0, |r| r + 1)
+    }
+
+    /// Executes the instruction at `ip`, updating the instruction pointer.
+    pub fn execute(&self, ip: &mut usize, regs: &mut [i64]) {
+        let ins = &self.instructions[*ip];
+        if let Some(b) = self.ip_binding {
+            regs[b] = *ip as i64;
+            ins.exec(regs);
+            *ip = usize::try_from(regs[b] + 1).unwrap_or(usize::MAX);
+        } else {
+            ins.exec(regs);
+            *ip += 1;
+        }
+    }
+
+    /// Executes the program until it halts.
+    /// Returns the number of instructions executed.
+    pub fn execute_all(&self, regs: &mut [i64]) -> usize {
+        let mut ip = 0;
+        self.execute_until(&mut ip, regs, |_, _| false)
+    }
+
+    /// Executes the program until it halts or until `stop` returns true.
+    /// `stop` is called with the instruction pointer and the registers before each instruction.
+    /// Returns the number of instructions executed.
+    pub fn execute_until<F>(&self, ip: &mut usize, regs: &mut [i64], mut stop: F) -> usize
+    where
+        F: FnMut(usize, &[i64]) -> bool,
+    {
+        let mut steps = 0;
+        while *ip < self.instructions.len() && !stop(*ip, regs) {
+            self.execute(ip, regs);
+            steps += 1;
+        }
+        steps
+    }
+}
+
+/// An instruction sample, as found in the day 16 input.
+#[derive(Debug)]
+pub struct Sample {
+    pub before: Vec<i64>,
+    pub instruction: [i64; 4],
+    pub after: Vec<i64>,
+}
+
+fn build_sample_registers(s: &str) -> Vec<i64> {
+    let start = s.chars().position(|c| c == '[').unwrap() + 1;
+    s[start..s.len() - 1]
+        .split(", ")
+        .map(|i| i.parse().unwrap())
+        .collect()
+}
+
+fn build_numeric_instruction(s: &str) -> [i64; 4] {
+    let v: Vec<i64> = s.split(' ').map(|i| i.parse().unwrap()).collect();
+    [v[0], v[1], v[2], v[3]]
+}
+
+impl Sample {
+    /// Builds the list of samples and the program that follows, where opcodes are still numbers.
+    pub fn build_list(input: &str) -> (Vec<Sample>, Vec<[i64; 4]>) {
+        let mut samples = Vec::new();
+        let mut it = input.lines();
+        while let Some(line) = it.next() {
+            if line.is_empty() {
+                break;
+            }
+            samples.push(Sample {
+                before: build_sample_registers(line),
+                instruction: build_numeric_instruction(it.next().unwrap()),
+                after: build_sample_registers(it.next().unwrap()),
+            });
+            it.next();
+        }
+
+        let program = it
+            .filter(|line| !line.is_empty())
+            .map(build_numeric_instruction)
+            .collect();
+
+        (samples, program)
+    }
+
+    pub fn opcode_number(&self) -> usize {
+        usize::try_from(self.instruction[0]).unwrap()
+    }
+
+    /// Checks if executing the sample with this opcode gives the expected result.
+    pub fn matches(&self, opcode: Opcode) -> bool {
+        let mut regs = self.before.clone();
+        let [_, a, b, c] = self.instruction;
+        opcode.exec(&mut regs, a, b, usize::try_from(c).unwrap());
+        regs == self.after
+    }
+
+    /// All the opcodes that behave like this sample.
+    pub fn matching_opcodes(&self) -> Vec<Opcode> {
+        OPCODES
+            .iter()
+            .copied()
+            .filter(|op| self.matches(*op))
+            .collect()
+    }
+}
+
+/// Deduces which number corresponds to which opcode by using the samples.
+/// Returns a vector where indexes are the opcode numbers.
+pub fn find_opcodes(samples: &[Sample]) -> Vec<Opcode> {
+    // Vector index is the opcode number. The values are the possible opcodes matching.
+    let mut candidates: Vec<FxHashSet<Opcode>> = vec![OPCODES.iter().copied().collect(); 16];
+
+    for sample in samples {
+        let set: FxHashSet<Opcode> = sample.matching_opcodes().into_iter().collect();
+        let o = sample.opcode_number();
+        candidates[o] = candidates[o].intersection(&set).copied().collect();
+    }
+
+    // Reduce each set of candidates to one only.
+    while candidates.iter().any(|set| set.len() > 1) {
+        let known: Vec<_> = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, set)| set.len() == 1)
+            .map(|(o, set)| (o, *set.iter().next().unwrap()))
+            .collect();
+        assert!(!known.is_empty(), "Not enough samples to find the opcodes");
+
+        for (o, opcode) in known {
+            for (i, set) in candidates.iter_mut().enumerate() {
+                if i != o {
+                    set.remove(&opcode);
+                }
+            }
+        }
+    }
+
+    candidates
+        .iter()
+        .map(|set| *set.iter().next().expect("No opcode matching"))
+        .collect()
+}
+
+/// Converts a program with numeric opcodes into a real program, using the opcodes mapping.
+pub fn decode_program(program: &[[i64; 4]], opcodes: &[Opcode]) -> Program {
+    Program {
+        ip_binding: None,
+        instructions: program
+            .iter()
+            .map(|[o, a, b, c]| Instruction {
+                opcode: opcodes[usize::try_from(*o).unwrap()],
+                a: *a,
+                b: *b,
+                c: usize::try_from(*c).unwrap(),
+            })
+            .collect(),
+    }
+}
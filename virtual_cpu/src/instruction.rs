@@ -2,17 +2,19 @@ use crate::intchar::IntChar;
 use crate::parsing::char;
 use crate::registers::Registers;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Condition {
     NotZero,
     GreaterThanZero,
     True,
     Even,
     EqualOne,
+    // Jump never taken, 2020 day 8 nop that can be turned into a jump.
+    False,
 }
 use Condition::*;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Instruction {
     Set(char, IntChar<i64>),
     Add(char, IntChar<i64>),
@@ -29,6 +31,9 @@ pub enum Instruction {
     // Day 2016 12, 23, 25
     Out(IntChar<i64>),
     Toggle(char),
+    // Day 2021 24
+    Inp(char),
+    Eql(char, IntChar<i64>),
 }
 
 impl Instruction {
@@ -89,6 +94,10 @@ impl Instruction {
                 regs.set(*x, regs.get(*x) / regs.get_ic(*y));
                 *ir += 1;
             }
+            Instruction::Eql(x, y) => {
+                regs.set(*x, i64::from(regs.get(*x) == regs.get_ic(*y)));
+                *ir += 1;
+            }
             Instruction::JumpIf(_, x, y, test_fn) => {
                 if test_fn(regs.get_ic(*x)) {
                     *ir = (*ir as i64 + regs.get_ic(*y)) as usize;
@@ -97,7 +106,25 @@ impl Instruction {
                 }
             }
             Instruction::Nop => *ir += 1,
-            _ => panic!("Unsupported instruction in Instruction::execute()"),
+            _ => panic!("Unsupported instruction in Instruction::execute(), use a Machine"),
+        }
+    }
+
+    /// Returns the instruction modified by a toggle instruction (2016 day 23).
+    pub fn toggled(&self) -> Instruction {
+        match self {
+            Instruction::Set(r, x) => {
+                Instruction::JumpIf(NotZero, *x, IntChar::Char(*r), |v| v != 0)
+            }
+            Instruction::Add(r, _) => Instruction::Sub(*r, IntChar::from(1)),
+            Instruction::Sub(r, _) => Instruction::Add(*r, IntChar::from(1)),
+            Instruction::JumpIf(_, v, o, _) => match o {
+                // Copying to an integer is invalid, so it's skipped.
+                IntChar::Integer(_) => Instruction::Nop,
+                IntChar::Char(r) => Instruction::Set(*r, *v),
+            },
+            Instruction::Toggle(offset) => Instruction::Add(*offset, IntChar::from(1)),
+            _ => self.clone(),
         }
     }
 
@@ -114,7 +141,8 @@ impl Instruction {
             | Instruction::Sub(x, y)
             | Instruction::Mul(x, y)
             | Instruction::Mod(x, y)
-            | Instruction::Div(x, y) => {
+            | Instruction::Div(x, y)
+            | Instruction::Eql(x, y) => {
                 regs.push(*x);
                 if let IntChar::Char(c) = y {
                     regs.push(*c);
@@ -133,7 +161,7 @@ impl Instruction {
                     regs.push(*c);
                 }
             }
-            Instruction::Rcv(x) | Instruction::Toggle(x) => {
+            Instruction::Rcv(x) | Instruction::Toggle(x) | Instruction::Inp(x) => {
                 regs.push(*x);
             }
             Instruction::Nop => {}
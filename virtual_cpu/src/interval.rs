@@ -0,0 +1,200 @@
+//! Interval analysis of a program: Instead of executing with values, each register
+//! holds the range of values it can have.
+
+use std::ops::{Add, Div, Mul, RangeInclusive, Rem, Sub};
+
+use fxhash::FxHashMap;
+
+use crate::instruction::Instruction;
+use crate::intchar::IntChar;
+use crate::registers::Registers;
+
+/// An inclusive range of possible values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Interval {
+    pub fn new(min: i64, max: i64) -> Self {
+        assert!(min <= max);
+        Self { min, max }
+    }
+
+    pub fn constant(val: i64) -> Self {
+        Self::new(val, val)
+    }
+
+    pub fn contains(self, val: i64) -> bool {
+        self.min <= val && val <= self.max
+    }
+
+    pub fn as_constant(self) -> Option<i64> {
+        if self.min == self.max {
+            Some(self.min)
+        } else {
+            None
+        }
+    }
+
+    // Interval containing all the values.
+    fn from_values(values: &[i64]) -> Self {
+        Self::new(*values.iter().min().unwrap(), *values.iter().max().unwrap())
+    }
+
+    pub fn eql(self, other: Self) -> Self {
+        match (self.as_constant(), other.as_constant()) {
+            (Some(a), Some(b)) => Self::constant(i64::from(a == b)),
+            _ if self.max < other.min || other.max < self.min => Self::constant(0),
+            _ => Self::new(0, 1),
+        }
+    }
+}
+
+impl Add for Interval {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(
+            self.min.saturating_add(other.min),
+            self.max.saturating_add(other.max),
+        )
+    }
+}
+
+impl Sub for Interval {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(
+            self.min.saturating_sub(other.max),
+            self.max.saturating_sub(other.min),
+        )
+    }
+}
+
+impl Mul for Interval {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::from_values(&[
+            self.min.saturating_mul(other.min),
+            self.min.saturating_mul(other.max),
+            self.max.saturating_mul(other.min),
+            self.max.saturating_mul(other.max),
+        ])
+    }
+}
+
+/// Division truncating toward zero.
+impl Div for Interval {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        if other.contains(0) {
+            // Only non-zero divisors matter, and the quotient is at most the dividend.
+            let abs_max = self.min.saturating_abs().max(self.max.saturating_abs());
+            return Self::new(-abs_max, abs_max);
+        }
+        Self::from_values(&[
+            self.min / other.min,
+            self.min / other.max,
+            self.max / other.min,
+            self.max / other.max,
+        ])
+    }
+}
+
+/// Remainder, with the sign of the dividend.
+impl Rem for Interval {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        let divisor_max = other.min.saturating_abs().max(other.max.saturating_abs());
+        if divisor_max == 0 {
+            return self;
+        }
+        if self.min >= 0 && self.max < other.min {
+            // Dividend always smaller than the divisor.
+            return self;
+        }
+        let bound = divisor_max - 1;
+        Self::new(
+            if self.min >= 0 {
+                0
+            } else {
+                -bound.min(-self.min)
+            },
+            if self.max <= 0 {
+                0
+            } else {
+                bound.min(self.max)
+            },
+        )
+    }
+}
+
+/// Registers holding intervals. Unset registers are zero.
+#[derive(Debug, Clone, Default)]
+pub struct IntervalRegisters {
+    pub regs: FxHashMap<char, Interval>,
+}
+
+impl IntervalRegisters {
+    pub fn get(&self, r: char) -> Interval {
+        self.regs.get(&r).copied().unwrap_or(Interval::constant(0))
+    }
+
+    pub fn set(&mut self, r: char, val: Interval) {
+        self.regs.insert(r, val);
+    }
+
+    pub fn get_ic(&self, x: IntChar<i64>) -> Interval {
+        match x {
+            IntChar::Integer(val) => Interval::constant(val),
+            IntChar::Char(src) => self.get(src),
+        }
+    }
+}
+
+impl From<&Registers<i64>> for IntervalRegisters {
+    fn from(registers: &Registers<i64>) -> Self {
+        Self {
+            regs: registers
+                .regs
+                .iter()
+                .map(|(r, v)| (*r, Interval::constant(*v)))
+                .collect(),
+        }
+    }
+}
+
+/// Computes the possible values of the registers after executing the instructions, where
+/// each input instruction can read any value of the `input` range.
+///
+/// Only programs without jumps can be analyzed. None is returned otherwise.
+pub fn analyze(
+    instructions: &[Instruction],
+    regs: &IntervalRegisters,
+    input: &RangeInclusive<i64>,
+) -> Option<IntervalRegisters> {
+    let mut regs = regs.clone();
+    for ins in instructions {
+        match ins {
+            Instruction::Set(x, y) => regs.set(*x, regs.get_ic(*y)),
+            Instruction::Add(x, y) => regs.set(*x, regs.get(*x) + regs.get_ic(*y)),
+            Instruction::Sub(x, y) => regs.set(*x, regs.get(*x) - regs.get_ic(*y)),
+            Instruction::Mul(x, y) => regs.set(*x, regs.get(*x) * regs.get_ic(*y)),
+            Instruction::Div(x, y) => regs.set(*x, regs.get(*x) / regs.get_ic(*y)),
+            Instruction::Mod(x, y) => regs.set(*x, regs.get(*x) % regs.get_ic(*y)),
+            Instruction::Eql(x, y) => regs.set(*x, regs.get(*x).eql(regs.get_ic(*y))),
+            Instruction::Inp(x) => regs.set(*x, Interval::new(*input.start(), *input.end())),
+            Instruction::Nop | Instruction::Snd(_) | Instruction::Out(_) => {}
+            Instruction::JumpIf(..) | Instruction::Rcv(_) | Instruction::Toggle(_) => {
+                return None;
+            }
+        }
+    }
+    Some(regs)
+}
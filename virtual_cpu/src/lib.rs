@@ -1,6 +1,17 @@
+pub mod alu;
+pub mod bus;
 pub mod c_code;
+pub mod chronospatial;
+pub mod compiled;
+pub mod conditional;
+pub mod elfcode;
 pub mod instruction;
 pub mod intchar;
+pub mod interval;
+pub mod machine;
+pub mod ocr;
 pub mod parsing;
 pub mod registers;
 pub mod run_utils;
+pub mod rust_code;
+pub mod termination;
@@ -0,0 +1,100 @@
+//! A machine executing a program, with I/O going through a bus.
+
+use crate::bus::Bus;
+use crate::instruction::Instruction;
+use crate::registers::Registers;
+
+#[derive(Debug, Clone)]
+pub struct Machine {
+    // Instructions are owned, as the program can modify itself.
+    pub instructions: Vec<Instruction>,
+    pub ir: usize,
+    pub regs: Registers<i64>,
+    /// Number of cycles executed so far.
+    pub cycle: usize,
+    cycle_cost: fn(&Instruction) -> usize,
+}
+
+// Calls the cycle hook of the bus for each cycle of an instruction.
+fn run_cycles<B: Bus>(cycle: &mut usize, cost: usize, regs: &Registers<i64>, bus: &mut B) {
+    for _ in 0..cost {
+        *cycle += 1;
+        bus.on_cycle(*cycle, regs);
+    }
+}
+
+impl Machine {
+    pub fn new(instructions: &[Instruction], regs: Registers<i64>) -> Self {
+        Self {
+            instructions: instructions.to_vec(),
+            ir: 0,
+            regs,
+            cycle: 0,
+            cycle_cost: |_| 1,
+        }
+    }
+
+    /// Sets how many cycles each instruction takes. By default, all take one cycle.
+    pub fn with_cycle_cost(mut self, cycle_cost: fn(&Instruction) -> usize) -> Self {
+        self.cycle_cost = cycle_cost;
+        self
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.ir >= self.instructions.len()
+    }
+
+    /// The instruction that will be executed on next step.
+    pub fn current(&self) -> Option<&Instruction> {
+        self.instructions.get(self.ir)
+    }
+
+    /// Executes one instruction.
+    /// Returns false if the machine is halted or waiting for input.
+    pub fn step<B: Bus>(&mut self, bus: &mut B) -> bool {
+        let Some(ins) = self.instructions.get(self.ir) else {
+            return false;
+        };
+        let cost = (self.cycle_cost)(ins);
+        if let Instruction::Rcv(x) | Instruction::Inp(x) = ins {
+            // Cycles are counted only once the machine isn't waiting anymore.
+            let Some(val) = bus.read(self.regs.get(*x)) else {
+                return false;
+            };
+            run_cycles(&mut self.cycle, cost, &self.regs, bus);
+            self.regs.set(*x, val);
+            self.ir += 1;
+            return true;
+        }
+
+        run_cycles(&mut self.cycle, cost, &self.regs, bus);
+        match ins {
+            Instruction::Snd(x) | Instruction::Out(x) => {
+                bus.write(self.regs.get_ic(*x));
+                self.ir += 1;
+            }
+            Instruction::Toggle(offset) => {
+                let to_toggle = self.ir as i64 + self.regs.get(*offset);
+                if let Some(target) = usize::try_from(to_toggle)
+                    .ok()
+                    .and_then(|i| self.instructions.get_mut(i))
+                {
+                    *target = target.toggled();
+                }
+                self.ir += 1;
+            }
+            _ => ins.execute(&mut self.ir, &mut self.regs),
+        }
+        true
+    }
+
+    /// Executes the program until it halts, waits for input or the bus says to stop.
+    /// Returns the number of instructions executed.
+    pub fn run<B: Bus>(&mut self, bus: &mut B) -> usize {
+        let mut steps = 0;
+        while !bus.is_done() && self.step(bus) {
+            steps += 1;
+        }
+        steps
+    }
+}
@@ -0,0 +1,98 @@
+//! Recognition of the letters drawn by some puzzles, like 2022 day 10.
+//!
+//! Letters are 4 pixels wide and 6 pixels high, separated by an empty column,
+//! with '#' for lit pixels and '.' for dark ones.
+
+const LETTER_WIDTH: usize = 4;
+const LETTER_HEIGHT: usize = 6;
+
+// Each letter with its pixels, row by row.
+// The Y is 5 pixels wide, the last column being in the separator: only its first 4 are compared.
+const LETTERS: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+/// Converts the screen into letters. Unknown letters are returned as '?'.
+/// Returns None if the screen isn't 6 rows of the same width.
+pub fn parse_letters(screen: &str) -> Option<String> {
+    let rows: Vec<&[u8]> = screen.lines().map(str::as_bytes).collect();
+    if rows.len() != LETTER_HEIGHT {
+        return None;
+    }
+    let width = rows[0].len();
+    if rows.iter().any(|row| row.len() != width) {
+        return None;
+    }
+    let letters = (0..width)
+        .step_by(LETTER_WIDTH + 1)
+        .map(|start| {
+            let pixels: String = rows
+                .iter()
+                .flat_map(|row| &row[start..(start + LETTER_WIDTH).min(width)])
+                .map(|b| *b as char)
+                .collect();
+            LETTERS
+                .iter()
+                .find(|(_, p)| *p == pixels)
+                .map_or('?', |(c, _)| *c)
+        })
+        .collect();
+    Some(letters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_i_and_y() {
+        let screen = ".###.#...#
+..#..#...#
+..#...#.#.
+..#....#..
+..#....#..
+.###...#..";
+        assert_eq!(parse_letters(screen).unwrap(), "IY");
+    }
+
+    #[test]
+    fn test_unknown_letter() {
+        let screen = "####.
+#..#.
+#..#.
+#..#.
+#..#.
+####.";
+        assert_eq!(parse_letters(screen).unwrap(), "?");
+    }
+
+    #[test]
+    fn test_invalid_screen() {
+        // Rows of different widths.
+        let screen = ".##..#..#
+#..#.#.#.
+#..#.##..
+#..#.#.#.
+#..#.#.#.
+.##..#..";
+        assert_eq!(parse_letters(screen), None);
+        assert_eq!(parse_letters(".##.\n#..#"), None);
+    }
+}
@@ -2,7 +2,7 @@ use fxhash::FxHashMap;
 
 use crate::intchar::IntChar;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Registers<T> {
     pub regs: FxHashMap<char, T>,
 }
@@ -2,6 +2,7 @@
 
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
+use std::time::Duration;
 
 use fxhash::FxHashMap;
 
@@ -12,9 +13,12 @@ pub fn get_input_file(puzzle_name: &str) -> String {
 }
 
 // Loads the list of puzzle answers, saving it into a map "puzzle name" => "answer".
+// If the file doesn't exist, the list is empty.
 pub fn load_answer_list() -> FxHashMap<String, String> {
-    let reader = BufReader::new(File::open(format!("{}/answers", RESOURCES_DIR)).unwrap());
-    reader
+    let Ok(file) = File::open(format!("{}/answers", RESOURCES_DIR)) else {
+        return FxHashMap::default();
+    };
+    BufReader::new(file)
         .lines()
         .map(|line| {
             let l = line.unwrap();
@@ -37,7 +41,7 @@ pub struct Puzzle {
 }
 
 impl Puzzle {
-    fn new(
+    pub fn new(
         name: &str,
         part_nb: u8,
         puzzle_fn: Option<PuzzleFn>,
@@ -54,39 +58,146 @@ impl Puzzle {
         }
     }
 
-    pub fn both(name: &str, part_nb: u8, puzzle_fn: PuzzleFn, c_code_fn: GetCCodeFn) -> Self {
-        Self::new(name, part_nb, Some(puzzle_fn), Some(c_code_fn))
+    pub fn parse_puzzle_name(s: &str) -> (u32, u32) {
+        let p: Vec<_> = s.strip_prefix("day").unwrap().split('_').collect();
+        (p[0].parse().unwrap(), p[1].parse().unwrap())
     }
 
-    pub fn base(name: &str, part_nb: u8, puzzle_fn: PuzzleFn) -> Self {
-        Self::new(name, part_nb, Some(puzzle_fn), None)
+    pub fn get_input(&self) -> std::io::Result<String> {
+        fs::read_to_string(get_input_file(&self.name))
     }
+}
+
+/// Builds the list of puzzles, with the functions in the day modules.
+///
+/// Each part lists the function returning the result, and the one returning the C code.
+/// `_` is for when there is none.
+///
+/// ```ignore
+/// let puzzles = puzzles![
+///     day2016_12 [1: part1 / part1_c_code] [2: part2 / part2_c_code],
+///     day2016_25 [1: part1],
+///     day2017_23 [1: part1] [2: _ / part2_c_code],
+/// ];
+/// ```
+#[macro_export]
+macro_rules! puzzles {
+    ($($day:ident $([$part:literal: $native:tt $(/ $c:tt)?])+),* $(,)?) => {
+        vec![$($(
+            $crate::run_utils::Puzzle::new(
+                stringify!($day),
+                $part,
+                $crate::puzzles!(@fn $day $native),
+                $crate::puzzles!(@fn $day $($c)?),
+            ),
+        )+)*]
+    };
+    (@fn $day:ident) => { None };
+    (@fn $day:ident _) => { None };
+    (@fn $day:ident $f:ident) => { Some($day::$f as fn(&str) -> String) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Native,
+    C,
+}
+
+/// Selection of the puzzles to run, from the command line arguments.
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub year: Option<u32>,
+    pub day: Option<u32>,
+    pub part: Option<u8>,
+    pub backend: Option<Backend>,
+}
 
-    pub fn with_c(name: &str, part_nb: u8, c_code_fn: GetCCodeFn) -> Self {
-        Self::new(name, part_nb, None, Some(c_code_fn))
+impl Filter {
+    /// Parses arguments like `--year 2016 --day 12 --part 2 --backend c`.
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        fn value<T: std::str::FromStr>(name: &str, val: Option<String>) -> Result<T, String> {
+            let val = val.ok_or(format!("Missing value for {}", name))?;
+            val.parse()
+                .map_err(|_| format!("Invalid value for {}: {}", name, val))
+        }
+
+        let mut filter = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--year" => filter.year = Some(value(&arg, args.next())?),
+                "--day" => filter.day = Some(value(&arg, args.next())?),
+                "--part" => filter.part = Some(value(&arg, args.next())?),
+                "--backend" => {
+                    filter.backend = match args.next().as_deref() {
+                        Some("native") => Some(Backend::Native),
+                        Some("c") => Some(Backend::C),
+                        other => return Err(format!("Invalid backend: {:?}", other)),
+                    }
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        Ok(filter)
     }
 
-    pub fn parse_puzzle_name(s: &str) -> (u32, u32) {
-        let p: Vec<_> = s.strip_prefix("day").unwrap().split('_').collect();
-        (p[0].parse().unwrap(), p[1].parse().unwrap())
+    pub fn matches(&self, puzzle: &Puzzle) -> bool {
+        self.year.is_none_or(|y| y == puzzle.year)
+            && self.day.is_none_or(|d| d == puzzle.day)
+            && self.part.is_none_or(|p| p == puzzle.part_nb)
     }
 
-    pub fn get_input(&self) -> String {
-        let input_file = get_input_file(&self.name);
-        fs::read_to_string(input_file).expect("Unable to read input file")
+    pub fn runs(&self, backend: Backend) -> bool {
+        self.backend.is_none_or(|b| b == backend)
     }
 }
 
-pub fn print_result(puzzle: &Puzzle, res: &str, expected: &str, is_c: bool) {
+#[derive(Debug, PartialEq, Eq)]
+pub enum Status {
+    Correct,
+    Incorrect,
+    MissingAnswer,
+    Error,
+}
+
+/// Prints the result of a puzzle run, compared to the expected answer if we have it.
+/// Returns the status of the run.
+pub fn print_result(
+    puzzle: &Puzzle,
+    res: Result<String, String>,
+    expected: Option<&String>,
+    backend: Backend,
+    elapsed: Duration,
+) -> Status {
+    let status = match (&res, expected) {
+        (Err(_), _) => Status::Error,
+        (Ok(_), None) => Status::MissingAnswer,
+        (Ok(r), Some(e)) if r == e => Status::Correct,
+        (Ok(_), Some(_)) => Status::Incorrect,
+    };
     println!(
-        "{} {} day {}, part {}{}",
-        if res == expected { "✅" } else { "❌" },
+        "{} {} day {}, part {}{} ({:.2?})",
+        match status {
+            Status::Correct => "✅",
+            Status::MissingAnswer => "❔",
+            Status::Incorrect | Status::Error => "❌",
+        },
         puzzle.year,
         puzzle.day,
         puzzle.part_nb,
-        if is_c { " C version" } else { "" },
+        if backend == Backend::C {
+            " C version"
+        } else {
+            ""
+        },
+        elapsed,
     );
-    if res != expected {
-        println!("Incorrect result is {}, expected {}", res, expected);
+    match (res, expected) {
+        (Err(err), _) => println!("{}", err),
+        (Ok(r), None) => println!("Missing answer, result is {}", r),
+        (Ok(r), Some(e)) if status == Status::Incorrect => {
+            println!("Incorrect result is {}, expected {}", r, e);
+        }
+        _ => {}
     }
+    status
 }
@@ -0,0 +1,175 @@
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+
+use crate::{
+    instruction::{Condition, Instruction},
+    registers::Registers,
+};
+
+// Index of the instruction a jump goes to, or None if it's before the start of the program.
+fn jump_target(ins: &Instruction, ir: usize) -> Option<Option<usize>> {
+    if let Instruction::JumpIf(_, _, y, _) = ins {
+        Some(usize::try_from(ir as i64 + y.get_integer()).ok())
+    } else {
+        None
+    }
+}
+
+// Code to go to the specified instruction index.
+fn goto(target: Option<usize>) -> String {
+    match target {
+        Some(index) => format!("block = {}; continue;", index),
+        // Jumping before the start ends the program.
+        None => "break;".to_string(),
+    }
+}
+
+/// Generates Rust code for the instruction.
+fn instruction_rust_version(ins: &Instruction, ir: usize) -> String {
+    match ins {
+        Instruction::Set(x, y) => format!("{} = {};", x, y),
+        Instruction::Add(x, y) => format!("{} += {};", x, y),
+        Instruction::Sub(x, y) => format!("{} -= {};", x, y),
+        Instruction::Mul(x, y) => format!("{} *= {};", x, y),
+        Instruction::Mod(x, y) => format!("{} %= {};", x, y),
+        Instruction::Div(x, y) => format!("{} /= {};", x, y),
+        Instruction::Eql(x, y) => format!("{} = i64::from({} == {});", x, x, y),
+        Instruction::JumpIf(cond, x, _, _) => {
+            let goto = goto(jump_target(ins, ir).unwrap());
+            match cond {
+                Condition::NotZero => format!("if {} != 0 {{ {} }}", x, goto),
+                Condition::GreaterThanZero => format!("if {} > 0 {{ {} }}", x, goto),
+                Condition::True => goto,
+                Condition::Even => format!("if {} % 2 == 0 {{ {} }}", x, goto),
+                Condition::EqualOne => format!("if {} == 1 {{ {} }}", x, goto),
+                Condition::False => String::new(),
+            }
+        }
+        Instruction::Out(x) => format!("output.push({});", x),
+        Instruction::Nop => String::new(),
+        _ => panic!("Unsupported instruction for Rust generation: {:?}", ins),
+    }
+}
+
+/// Transform the instructions into a Rust function, that can be written to a file and included
+/// with `include!`, for example from a build script.
+///
+/// The function takes no parameters and returns a `Vec<i64>` with the values output by the
+/// program, followed by the values of the `registers_to_print`.
+///
+/// The instructions are split into basic blocks, and the function body is a `loop` with
+/// a `match` on the current block.
+///
+/// * `fn_name` - Name of the generated function.
+/// * `initial_registers` - The registers (aka variables) that must have their values set at something else than zero at the beginning.
+/// * `registers_to_print` - The register's values to return at the end.
+/// * `optimizations` - A block of instructions, indicated by its indexes, to be replaced
+///   with the specified code. If there are multiple ones, their indexes cannot overlap.
+///   Execution continues after the block, unless the code changes `block` and does `continue`.
+pub fn get_rust_code_full(
+    fn_name: &str,
+    instructions: &[Instruction],
+    initial_registers: &Registers<i64>,
+    registers_to_print: &[char],
+    optimizations: &[(RangeInclusive<usize>, String)],
+) -> String {
+    let mut code = String::new();
+    code += "#[allow(unused_mut, unused_assignments, unused_variables, clippy::all, clippy::pedantic)]\n";
+    code += &format!("pub fn {}() -> Vec<i64> {{\n", fn_name);
+    code += "    let mut output: Vec<i64> = Vec::new();\n";
+
+    // Declare all the registers as variables.
+    let registers: BTreeSet<char> = instructions
+        .iter()
+        .flat_map(Instruction::get_register_names)
+        .chain(registers_to_print.iter().copied())
+        .collect();
+    for r in &registers {
+        code += &format!("    let mut {}: i64 = {};\n", r, initial_registers.get(*r));
+    }
+
+    // Basic blocks start at the beginning, at each jump target and after each jump.
+    // Optimized blocks are handled as basic blocks as well.
+    let mut leaders: BTreeSet<usize> = BTreeSet::from([0]);
+    for (i, ins) in instructions.iter().enumerate() {
+        if let Some(target) = jump_target(ins, i) {
+            leaders.extend(target);
+            leaders.insert(i + 1);
+        }
+    }
+    for (range, _) in optimizations {
+        // Jumps within the block are replaced with it, but jumps from outside cannot land inside.
+        let is_inside = |l: &usize| *range.start() < *l && range.contains(l);
+        if let Some(l) = instructions
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !range.contains(i))
+            .find_map(|(i, ins)| jump_target(ins, i).flatten().filter(is_inside))
+        {
+            panic!("Jump into the optimized block {:?} at {}", range, l);
+        }
+        leaders.retain(|l| !is_inside(l));
+        leaders.insert(*range.start());
+        leaders.insert(range.end() + 1);
+    }
+    leaders.retain(|l| *l < instructions.len());
+
+    code += "\n    let mut block: usize = 0;\n";
+    code += "    loop {\n";
+    code += "        match block {\n";
+    let mut i = 0;
+    while i < instructions.len() {
+        code += &format!("            {} => {{\n", i);
+        let mut always_jumps;
+        loop {
+            if let Some((range, replacement)) =
+                optimizations.iter().find(|(range, _)| *range.start() == i)
+            {
+                code += replacement;
+                i = range.end() + 1;
+                always_jumps = false;
+            } else {
+                always_jumps = matches!(instructions[i], Instruction::JumpIf(Condition::True, ..));
+                let line = instruction_rust_version(&instructions[i], i);
+                if !line.is_empty() {
+                    code += &format!("                {}\n", line);
+                }
+                i += 1;
+            }
+            if i >= instructions.len() || leaders.contains(&i) {
+                break;
+            }
+        }
+        // Falling through to the next block, unless the last instruction always jumps.
+        if !always_jumps {
+            code += &format!("                block = {};\n", i);
+        }
+        code += "            }\n";
+    }
+    code += "            _ => break,\n";
+    code += "        }\n";
+    code += "    }\n\n";
+
+    for r in registers_to_print {
+        code += &format!("    output.push({});\n", r);
+    }
+    code += "    output\n";
+    code += "}\n";
+
+    code
+}
+
+pub fn get_rust_code(
+    fn_name: &str,
+    instructions: &[Instruction],
+    initial_registers: &Registers<i64>,
+    registers_to_print: &[char],
+) -> String {
+    get_rust_code_full(
+        fn_name,
+        instructions,
+        initial_registers,
+        registers_to_print,
+        &[],
+    )
+}
@@ -0,0 +1,67 @@
+//! Detection of programs running forever, and search of a fix that makes them terminate,
+//! as in 2020 day 8.
+
+use crate::instruction::{Condition, Instruction};
+use crate::registers::Registers;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    /// The instruction pointer went outside the program.
+    Halted,
+    /// An instruction was about to be executed a second time.
+    Loop,
+}
+
+/// Executes the program until it ends or an instruction is about to be executed a second time.
+///
+/// Repeating an instruction means an infinite loop only if the registers don't influence
+/// the jumps, which is the case when all jumps are unconditional.
+pub fn run_until_repeat(instructions: &[Instruction], regs: &mut Registers<i64>) -> Termination {
+    let mut visited = vec![false; instructions.len()];
+    let mut ir = 0;
+    while ir < instructions.len() {
+        if visited[ir] {
+            return Termination::Loop;
+        }
+        visited[ir] = true;
+        instructions[ir].execute(&mut ir, regs);
+    }
+    Termination::Halted
+}
+
+/// Turns an unconditional jump into a no-op, and a no-op (a jump never taken) into a jump.
+pub fn swap_jmp_nop(ins: &Instruction) -> Option<Instruction> {
+    match ins {
+        Instruction::JumpIf(Condition::True, x, y, _) => {
+            Some(Instruction::JumpIf(Condition::False, *x, *y, |_| false))
+        }
+        Instruction::JumpIf(Condition::False, x, y, _) => {
+            Some(Instruction::JumpIf(Condition::True, *x, *y, |_| true))
+        }
+        _ => None,
+    }
+}
+
+/// Tries to modify each instruction with `mutate`, one at a time, until the program halts.
+/// Returns the index of the modified instruction and the registers at the end.
+pub fn repair<F>(
+    instructions: &[Instruction],
+    initial_registers: &Registers<i64>,
+    mutate: F,
+) -> Option<(usize, Registers<i64>)>
+where
+    F: Fn(&Instruction) -> Option<Instruction>,
+{
+    let mut instructions = instructions.to_vec();
+    for i in 0..instructions.len() {
+        if let Some(mutated) = mutate(&instructions[i]) {
+            let original = std::mem::replace(&mut instructions[i], mutated);
+            let mut regs = initial_registers.clone();
+            if run_until_repeat(&instructions, &mut regs) == Termination::Halted {
+                return Some((i, regs));
+            }
+            instructions[i] = original;
+        }
+    }
+    None
+}